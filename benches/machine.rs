@@ -0,0 +1,264 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use p3_keccak::Keccak256Hash;
+use p3_keccak_machine::{
+    chips::{DIGEST_WIDTH, MERKLE_TREE_DEPTH},
+    config::{default_challenger, default_config, MyConfig, Val},
+    generate_hash_trace, generate_merkle_path_trace, KeccakMachine,
+};
+use p3_machine::machine::Machine;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::{CompressionFunctionFromHasher, CryptographicHasher};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tracing::{span, Subscriber};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer, Registry,
+};
+
+const RANDOM_SEED: u64 = 0;
+const MESSAGE_BYTES: [usize; 4] = [32, 64, 1 << 10, 1 << 16];
+
+type Traces = Vec<Option<RowMajorMatrix<Val>>>;
+
+/// The traces proving the path of a random leaf through random siblings at depth
+/// `DEPTH`, along with the machine and the output. The leaf index is kept below `2^30`
+/// so that it fits in a field element.
+fn merkle_traces<const DEPTH: usize>() -> (KeccakMachine, Vec<u8>, Traces) {
+    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+    let leaf_index = rng.gen_range(0..1 << DEPTH.min(30));
+    let leaf_hash = rng.gen();
+    let siblings = core::array::from_fn(|_| rng.gen());
+
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let (output, machine, traces) =
+        generate_merkle_path_trace::<MyConfig, _, DEPTH>(leaf_index, leaf_hash, siblings, &hasher);
+    (machine, output, traces)
+}
+
+fn random_message(num_bytes: usize) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+    (0..num_bytes).map(|_| rng.gen()).collect()
+}
+
+fn bench_merkle_depth<const DEPTH: usize>(c: &mut Criterion) {
+    let (machine, output, _) = merkle_traces::<DEPTH>();
+    let public_values = KeccakMachine::public_values(&output);
+    let config = default_config();
+    let (pk, vk) = machine.setup(&config);
+
+    let mut group = c.benchmark_group("merkle");
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::new("generate_trace", DEPTH), |b| {
+        b.iter(merkle_traces::<DEPTH>)
+    });
+    group.bench_function(BenchmarkId::new("prove", DEPTH), |b| {
+        b.iter_batched(
+            || merkle_traces::<DEPTH>().2,
+            |traces| {
+                machine.prove(
+                    &config,
+                    &mut default_challenger(),
+                    &pk,
+                    traces,
+                    &public_values,
+                )
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    let proof = machine.prove(
+        &config,
+        &mut default_challenger(),
+        &pk,
+        merkle_traces::<DEPTH>().2,
+        &public_values,
+    );
+    group.bench_function(BenchmarkId::new("verify", DEPTH), |b| {
+        b.iter(|| {
+            machine
+                .verify(
+                    &config,
+                    &mut default_challenger(),
+                    &vk,
+                    &proof,
+                    &public_values,
+                )
+                .unwrap()
+        })
+    });
+    group.finish();
+
+    print_phases(&format!("merkle/{DEPTH}"), || {
+        let traces = merkle_traces::<DEPTH>().2;
+        machine.prove(
+            &config,
+            &mut default_challenger(),
+            &pk,
+            traces,
+            &public_values,
+        );
+    });
+}
+
+fn bench_merkle(c: &mut Criterion) {
+    bench_merkle_depth::<MERKLE_TREE_DEPTH>(c);
+    bench_merkle_depth::<16>(c);
+    bench_merkle_depth::<32>(c);
+}
+
+fn bench_hash(c: &mut Criterion) {
+    let config = default_config();
+
+    let mut group = c.benchmark_group("hash");
+    group.sample_size(10);
+    for num_bytes in MESSAGE_BYTES {
+        let message = random_message(num_bytes);
+        let (digest, machine, _) = generate_hash_trace::<MyConfig>(&message);
+        let public_values = KeccakMachine::public_values(&digest);
+        let (pk, vk) = machine.setup(&config);
+
+        group.throughput(Throughput::Bytes(num_bytes as u64));
+        group.bench_with_input(BenchmarkId::new("native", num_bytes), &message, |b, m| {
+            b.iter(|| Keccak256Hash.hash_iter(m.iter().copied()))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("generate_trace", num_bytes),
+            &message,
+            |b, m| b.iter(|| generate_hash_trace::<MyConfig>(m)),
+        );
+        group.bench_with_input(BenchmarkId::new("prove", num_bytes), &message, |b, m| {
+            b.iter_batched(
+                || generate_hash_trace::<MyConfig>(m).2,
+                |traces| {
+                    machine.prove(
+                        &config,
+                        &mut default_challenger(),
+                        &pk,
+                        traces,
+                        &public_values,
+                    )
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        let traces = generate_hash_trace::<MyConfig>(&message).2;
+        let proof = machine.prove(
+            &config,
+            &mut default_challenger(),
+            &pk,
+            traces,
+            &public_values,
+        );
+        group.bench_with_input(BenchmarkId::new("verify", num_bytes), &proof, |b, proof| {
+            b.iter(|| {
+                machine
+                    .verify(
+                        &config,
+                        &mut default_challenger(),
+                        &vk,
+                        proof,
+                        &public_values,
+                    )
+                    .unwrap()
+            })
+        });
+
+        print_phases(&format!("hash/{num_bytes}"), || {
+            let traces = generate_hash_trace::<MyConfig>(&message).2;
+            machine.prove(
+                &config,
+                &mut default_challenger(),
+                &pk,
+                traces,
+                &public_values,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// The prover phases that spans are attributed to, by span name.
+fn phase(span_name: &str) -> Option<&'static str> {
+    let name = span_name.to_lowercase();
+    if name.contains("generate") {
+        Some("trace generation")
+    } else if name.contains("quotient") {
+        Some("quotient")
+    } else if name.contains("commit") {
+        Some("commitment")
+    } else if name.contains("open") || name.contains("fri") || name.contains("query") {
+        Some("FRI")
+    } else {
+        None
+    }
+}
+
+fn phase_times() -> &'static Mutex<BTreeMap<&'static str, Duration>> {
+    static PHASE_TIMES: OnceLock<Mutex<BTreeMap<&'static str, Duration>>> = OnceLock::new();
+    PHASE_TIMES.get_or_init(Default::default)
+}
+
+struct PhaseStart(&'static str, Instant);
+
+/// Adds the time spent in every span to its phase. Spans nested in a span of the
+/// same phase are already accounted for by their ancestor.
+struct PhaseLayer;
+
+impl<S> Layer<S> for PhaseLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let Some(phase) = phase(span.name()) else {
+            return;
+        };
+        if span
+            .scope()
+            .skip(1)
+            .any(|ancestor| self::phase(ancestor.name()) == Some(phase))
+        {
+            return;
+        }
+        span.extensions_mut()
+            .insert(PhaseStart(phase, Instant::now()));
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        if let Some(PhaseStart(phase, start)) = span.extensions().get::<PhaseStart>() {
+            *phase_times().lock().unwrap().entry(phase).or_default() += start.elapsed();
+        }
+    }
+}
+
+/// Runs `f` once and prints the time it spent in each prover phase. Criterion only
+/// times whole calls, so this is how the phases are split out.
+fn print_phases(name: &str, f: impl FnOnce()) {
+    static SUBSCRIBER: OnceLock<()> = OnceLock::new();
+    SUBSCRIBER.get_or_init(|| Registry::default().with(PhaseLayer).init());
+
+    phase_times().lock().unwrap().clear();
+    let start = Instant::now();
+    f();
+    let total = start.elapsed();
+
+    println!("{name}: {total:?} in total");
+    for (phase, time) in phase_times().lock().unwrap().iter() {
+        println!(
+            "    {phase:<16} {time:>12.3?} ({:.1}%)",
+            100.0 * time.as_secs_f64() / total.as_secs_f64()
+        );
+    }
+}
+
+criterion_group!(benches, bench_merkle, bench_hash);
+criterion_main!(benches);
@@ -0,0 +1,190 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use p3_keccak::Keccak256Hash;
+use p3_keccak_machine::{
+    chips::{
+        byte_memory::ByteMemoryChip,
+        keccak_permute::KeccakPermuteChip,
+        keccak_sponge::{trace::KeccakSpongeOp, KeccakSpongeChip},
+        memory::{
+            trace::{MemoryOp, OperationKind},
+            MemoryChip,
+        },
+        memory_image::MemoryImageChip,
+        merkle_root::{MerkleRootChip, MerkleRootOp},
+        range_checker::RangeCheckerChip,
+        xor::XorChip,
+        DIGEST_WIDTH, KECCAK_U64_LIMBS, MAX_U8, MEMORY_WORD_BYTES, NUM_BYTES,
+    },
+    config::Val,
+};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CompressionFunctionFromHasher;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const RANDOM_SEED: u64 = 0;
+const INPUT_BYTES: [usize; 2] = [1 << 10, 1 << 14];
+const NUM_MERKLE_PATHS: usize = 16;
+const NUM_BYTE_OPS: usize = 1 << 12;
+
+fn sponge_op(num_bytes: usize, is_memory_op: bool) -> KeccakSpongeOp {
+    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+    KeccakSpongeOp {
+        is_memory_op,
+        // The digest is written after the input.
+        dst_addr: num_bytes.div_ceil(MEMORY_WORD_BYTES) as u32,
+        input: (0..num_bytes).map(|_| rng.gen()).collect(),
+        ..Default::default()
+    }
+}
+
+fn bench_sponge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_trace");
+    for num_bytes in INPUT_BYTES {
+        let op = sponge_op(num_bytes, false);
+        group.bench_with_input(BenchmarkId::new("KeccakSponge", num_bytes), &op, |b, op| {
+            b.iter(|| KeccakSpongeChip::generate_trace::<Val>(vec![op.clone()]))
+        });
+
+        // The chips fed by the sponge get the workload of hashing the same input.
+        let sponge_trace: RowMajorMatrix<Val> = KeccakSpongeChip::generate_trace(vec![op]);
+        let (xor_ops, permute_ops) = KeccakSpongeChip::generate_dependent_ops(&sponge_trace);
+        group.bench_with_input(
+            BenchmarkId::new("Xor", num_bytes),
+            &xor_ops,
+            |b, xor_ops| b.iter(|| XorChip::<NUM_BYTES>::generate_trace::<Val>(xor_ops.clone())),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("KeccakPermute", num_bytes),
+            &permute_ops,
+            |b, permute_ops| {
+                b.iter(|| {
+                    KeccakPermuteChip::<KECCAK_U64_LIMBS>::generate_trace::<Val>(
+                        permute_ops.clone(),
+                    )
+                })
+            },
+        );
+        let range_counts = KeccakSpongeChip::generate_range_counts(&sponge_trace);
+        group.bench_with_input(
+            BenchmarkId::new("Range8", num_bytes),
+            &range_counts,
+            |b, range_counts| {
+                b.iter(|| RangeCheckerChip::<MAX_U8>::generate_trace::<Val>(range_counts.clone()))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_memory(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_trace");
+    for num_bytes in INPUT_BYTES {
+        let op = sponge_op(num_bytes, true);
+        let mut image = op.input.clone();
+        image.resize(op.dst_addr as usize * MEMORY_WORD_BYTES + DIGEST_WIDTH, 0);
+        let memory_ops = op.memory_ops();
+
+        group.bench_with_input(
+            BenchmarkId::new("Memory", num_bytes),
+            &memory_ops,
+            |b, memory_ops| {
+                b.iter(|| {
+                    MemoryChip::<MEMORY_WORD_BYTES>::generate_trace::<Val>(
+                        &image,
+                        memory_ops.clone(),
+                    )
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("MemoryImage", num_bytes),
+            &memory_ops,
+            |b, memory_ops| {
+                // The digest is the output, hashed after the input, whose hash is the
+                // only call.
+                let dst_addr = op.dst_addr as usize;
+                let output = dst_addr..dst_addr + DIGEST_WIDTH / MEMORY_WORD_BYTES;
+                b.iter(|| {
+                    MemoryImageChip::<MEMORY_WORD_BYTES>::generate_trace::<Val>(
+                        &image,
+                        output.clone(),
+                        1,
+                        1,
+                        memory_ops,
+                    )
+                })
+            },
+        );
+    }
+
+    // Random byte reads and writes, two timestamps apart.
+    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+    let image: Vec<u8> = (0..NUM_BYTE_OPS).map(|_| rng.gen()).collect();
+    let mut memory = image.clone();
+    let byte_ops: Vec<MemoryOp<1>> = (0..NUM_BYTE_OPS)
+        .map(|i| {
+            let addr = rng.gen_range(0..image.len());
+            let kind = if rng.gen() {
+                memory[addr] = rng.gen();
+                OperationKind::Write
+            } else {
+                OperationKind::Read
+            };
+            MemoryOp {
+                addr: addr as u32,
+                timestamp: 2 * i as u32,
+                value: [memory[addr]],
+                kind,
+            }
+        })
+        .collect();
+    group.bench_with_input(
+        BenchmarkId::new("ByteMemory", NUM_BYTE_OPS),
+        &byte_ops,
+        |b, byte_ops| {
+            b.iter(|| ByteMemoryChip::<MEMORY_WORD_BYTES>::generate_trace::<Val>(&image, byte_ops))
+        },
+    );
+    group.finish();
+}
+
+fn merkle_root_ops<const DEPTH: usize>() -> Vec<MerkleRootOp<u8, DEPTH, DIGEST_WIDTH>> {
+    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+    (0..NUM_MERKLE_PATHS)
+        .map(|i| MerkleRootOp {
+            leaf_index: rng.gen_range(0..1 << DEPTH),
+            leaf_hash: rng.gen(),
+            siblings: core::array::from_fn(|_| rng.gen()),
+            first_hash_id: i * DEPTH,
+            leaf_preimage: None,
+            root_write: None,
+            sorted_pair: false,
+        })
+        .collect()
+}
+
+fn bench_merkle_root_depth<const DEPTH: usize>(c: &mut Criterion) {
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let ops = merkle_root_ops::<DEPTH>();
+    c.benchmark_group("generate_trace").bench_with_input(
+        BenchmarkId::new("MerkleRoot", DEPTH),
+        &ops,
+        |b, ops| {
+            b.iter(|| {
+                MerkleRootChip::<DEPTH, DIGEST_WIDTH>::generate_trace::<Val, _, _>(
+                    ops.clone(),
+                    &hasher,
+                )
+            })
+        },
+    );
+}
+
+fn bench_merkle_root(c: &mut Criterion) {
+    bench_merkle_root_depth::<8>(c);
+    bench_merkle_root_depth::<16>(c);
+    bench_merkle_root_depth::<32>(c);
+}
+
+criterion_group!(benches, bench_sponge, bench_memory, bench_merkle_root);
+criterion_main!(benches);
@@ -4,31 +4,38 @@ use p3_air::{Air, AirBuilder, BaseAir};
 use p3_field::AbstractField;
 use p3_matrix::Matrix;
 
-use super::columns::{KeccakCols, NUM_KECCAK_COLS};
+use super::columns::KeccakCols;
 use super::constants::rc_value_bit;
 use super::logic::{andn_gen, xor3_gen, xor_gen};
 use super::round_flags::eval_round_flags;
-use super::{BITS_PER_LIMB, NUM_ROUNDS, U64_LIMBS};
+use super::{bits_per_limb, NUM_ROUNDS};
 
-/// Assumes the field size is at least 16 bits.
+/// Splits each lane into `U64_LIMBS` limbs, which must fit in the field.
 #[derive(Debug)]
-pub struct KeccakAir {}
+pub struct KeccakAir<const U64_LIMBS: usize> {}
 
-impl<F> BaseAir<F> for KeccakAir {
+impl<F, const U64_LIMBS: usize> BaseAir<F> for KeccakAir<U64_LIMBS> {
     fn width(&self) -> usize {
-        NUM_KECCAK_COLS
+        KeccakCols::<F, U64_LIMBS>::num_cols()
     }
 }
 
-impl<AB: AirBuilder> Air<AB> for KeccakAir {
+impl<AB: AirBuilder, const U64_LIMBS: usize> Air<AB> for KeccakAir<U64_LIMBS> {
     #[inline]
     fn eval(&self, builder: &mut AB) {
-        eval_round_flags(builder);
+        let bits_per_limb = bits_per_limb(U64_LIMBS);
+        assert!(
+            bits_per_limb < AB::F::bits(),
+            "{bits_per_limb}-bit limbs don't fit in a {}-bit field",
+            AB::F::bits()
+        );
+
+        eval_round_flags::<AB, U64_LIMBS>(builder);
 
         let main = builder.main();
         let (local, next) = (main.row_slice(0), main.row_slice(1));
-        let local: &KeccakCols<AB::Var> = (*local).borrow();
-        let next: &KeccakCols<AB::Var> = (*next).borrow();
+        let local: &KeccakCols<AB::Var, U64_LIMBS> = (*local).borrow();
+        let next: &KeccakCols<AB::Var, U64_LIMBS> = (*next).borrow();
 
         let first_step = local.step_flags[0];
         let final_step = local.step_flags[NUM_ROUNDS - 1];
@@ -95,7 +102,7 @@ impl<AB: AirBuilder> Air<AB> for KeccakAir {
 
                 for limb in 0..U64_LIMBS {
                     let a_limb = local.a[y][x][limb];
-                    let computed_limb = (limb * BITS_PER_LIMB..(limb + 1) * BITS_PER_LIMB)
+                    let computed_limb = (limb * bits_per_limb..(limb + 1) * bits_per_limb)
                         .rev()
                         .fold(AB::Expr::zero(), |acc, z| acc.double() + get_bit(z));
                     builder.assert_eq(computed_limb, a_limb);
@@ -128,7 +135,7 @@ impl<AB: AirBuilder> Air<AB> for KeccakAir {
                 };
 
                 for limb in 0..U64_LIMBS {
-                    let computed_limb = (limb * BITS_PER_LIMB..(limb + 1) * BITS_PER_LIMB)
+                    let computed_limb = (limb * bits_per_limb..(limb + 1) * bits_per_limb)
                         .rev()
                         .fold(AB::Expr::zero(), |acc, z| acc.double() + get_bit(z));
                     builder.assert_eq(computed_limb, local.a_prime_prime[y][x][limb]);
@@ -138,8 +145,8 @@ impl<AB: AirBuilder> Air<AB> for KeccakAir {
 
         // A'''[0, 0] = A''[0, 0] XOR RC
         for limb in 0..U64_LIMBS {
-            let computed_a_prime_prime_0_0_limb = (limb * BITS_PER_LIMB
-                ..(limb + 1) * BITS_PER_LIMB)
+            let computed_a_prime_prime_0_0_limb = (limb * bits_per_limb
+                ..(limb + 1) * bits_per_limb)
                 .rev()
                 .fold(AB::Expr::zero(), |acc, z| {
                     acc.double() + local.a_prime_prime_0_0_bits[z]
@@ -161,8 +168,8 @@ impl<AB: AirBuilder> Air<AB> for KeccakAir {
 
         for limb in 0..U64_LIMBS {
             let a_prime_prime_prime_0_0_limb = local.a_prime_prime_prime_0_0_limbs[limb];
-            let computed_a_prime_prime_prime_0_0_limb = (limb * BITS_PER_LIMB
-                ..(limb + 1) * BITS_PER_LIMB)
+            let computed_a_prime_prime_prime_0_0_limb = (limb * bits_per_limb
+                ..(limb + 1) * bits_per_limb)
                 .rev()
                 .fold(AB::Expr::zero(), |acc, z| acc.double() + get_xored_bit(z));
             builder.assert_eq(
@@ -1,10 +1,7 @@
-use core::mem::{size_of, transmute};
-
 use p3_derive::Columnar;
-use p3_util::indices_arr;
 
 use super::constants::R;
-use super::{NUM_ROUNDS, RATE_LIMBS, U64_LIMBS};
+use super::{NUM_ROUNDS, RATE_LANES};
 
 /// Note: The ordering of each array is based on the input mapping. As the spec says,
 ///
@@ -13,9 +10,12 @@ use super::{NUM_ROUNDS, RATE_LIMBS, U64_LIMBS};
 /// Thus, for example, `a_prime` is stored in `y, x, z` order. This departs from the more common
 /// convention of `x, y, z` order, but it has the benefit that input lists map to AIR columns in a
 /// nicer way.
+///
+/// Each 64-bit lane is stored as `U64_LIMBS` little-endian limbs of `64 / U64_LIMBS`
+/// bits, see [`u64_limbs`](super::u64_limbs).
 #[derive(Debug, Columnar)]
 #[repr(C)]
-pub struct KeccakCols<T> {
+pub struct KeccakCols<T, const U64_LIMBS: usize> {
     /// The `i`th value is set to 1 if we are in the `i`th round, otherwise 0.
     pub step_flags: [T; NUM_ROUNDS],
 
@@ -60,7 +60,7 @@ pub struct KeccakCols<T> {
     pub a_prime_prime_prime_0_0_limbs: [T; U64_LIMBS],
 }
 
-impl<T: Copy> KeccakCols<T> {
+impl<T: Copy, const U64_LIMBS: usize> KeccakCols<T, U64_LIMBS> {
     pub fn b(&self, x: usize, y: usize, z: usize) -> T {
         debug_assert!(x < 5);
         debug_assert!(y < 5);
@@ -91,8 +91,8 @@ impl<T: Copy> KeccakCols<T> {
     }
 }
 
-pub fn input_limb(i: usize) -> usize {
-    debug_assert!(i < RATE_LIMBS);
+pub fn input_limb<const U64_LIMBS: usize>(i: usize) -> usize {
+    debug_assert!(i < RATE_LANES * U64_LIMBS);
 
     let i_u64 = i / U64_LIMBS;
     let limb_index = i % U64_LIMBS;
@@ -101,11 +101,11 @@ pub fn input_limb(i: usize) -> usize {
     let y = i_u64 / 5;
     let x = i_u64 % 5;
 
-    KECCAK_COL_MAP.preimage[y][x][limb_index]
+    KeccakCols::<usize, U64_LIMBS>::col_map().preimage[y][x][limb_index]
 }
 
-pub fn output_limb(i: usize) -> usize {
-    debug_assert!(i < RATE_LIMBS);
+pub fn output_limb<const U64_LIMBS: usize>(i: usize) -> usize {
+    debug_assert!(i < RATE_LANES * U64_LIMBS);
 
     let i_u64 = i / U64_LIMBS;
     let limb_index = i % U64_LIMBS;
@@ -114,13 +114,5 @@ pub fn output_limb(i: usize) -> usize {
     let y = i_u64 / 5;
     let x = i_u64 % 5;
 
-    KECCAK_COL_MAP.a_prime_prime_prime(y, x, limb_index)
-}
-
-pub const NUM_KECCAK_COLS: usize = size_of::<KeccakCols<u8>>();
-pub(crate) const KECCAK_COL_MAP: KeccakCols<usize> = make_col_map();
-
-const fn make_col_map() -> KeccakCols<usize> {
-    let indices_arr = indices_arr::<NUM_KECCAK_COLS>();
-    unsafe { transmute::<[usize; NUM_KECCAK_COLS], KeccakCols<usize>>(indices_arr) }
+    KeccakCols::<usize, U64_LIMBS>::col_map().a_prime_prime_prime(y, x, limb_index)
 }
@@ -8,18 +8,20 @@ use p3_maybe_rayon::prelude::*;
 use p3_util::ceil_div_usize;
 use tracing::instrument;
 
-use super::columns::{KeccakCols, NUM_KECCAK_COLS};
+use super::columns::KeccakCols;
 use super::constants::rc_value_limb;
 use super::logic::{andn, xor};
-use super::{BITS_PER_LIMB, NUM_ROUNDS, U64_LIMBS};
+use super::{bits_per_limb, NUM_ROUNDS};
 
 // TODO: Take generic iterable
 #[instrument(name = "generate Keccak trace", skip_all)]
-pub fn generate_trace_rows<F: PrimeField64>(inputs: Vec<[u64; 25]>) -> RowMajorMatrix<F> {
+pub fn generate_trace_rows<F: PrimeField64, const U64_LIMBS: usize>(
+    inputs: Vec<[u64; 25]>,
+) -> RowMajorMatrix<F> {
+    let num_cols = KeccakCols::<F, U64_LIMBS>::num_cols();
     let num_rows = (inputs.len() * NUM_ROUNDS).next_power_of_two();
-    let mut trace =
-        RowMajorMatrix::new(vec![F::zero(); num_rows * NUM_KECCAK_COLS], NUM_KECCAK_COLS);
-    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<KeccakCols<F>>() };
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<KeccakCols<F, U64_LIMBS>>() };
     assert!(prefix.is_empty(), "Alignment should match");
     assert!(suffix.is_empty(), "Alignment should match");
     assert_eq!(rows.len(), num_rows);
@@ -40,10 +42,18 @@ pub fn generate_trace_rows<F: PrimeField64>(inputs: Vec<[u64; 25]>) -> RowMajorM
 }
 
 /// `rows` will normally consist of 24 rows, with an exception for the final row.
-pub fn generate_trace_rows_for_perm<F: PrimeField64>(
-    rows: &mut [&mut KeccakCols<F>],
+pub fn generate_trace_rows_for_perm<F: PrimeField64, const U64_LIMBS: usize>(
+    rows: &mut [&mut KeccakCols<F, U64_LIMBS>],
     input: [u64; 25],
 ) {
+    let bits_per_limb = bits_per_limb(U64_LIMBS);
+    assert!(
+        bits_per_limb < F::bits(),
+        "{bits_per_limb}-bit limbs don't fit in a {}-bit field",
+        F::bits()
+    );
+    let limb_mask = u64::MAX >> (64 - bits_per_limb);
+
     // Populate the preimage for each row.
     for row in rows.iter_mut() {
         for y in 0..5 {
@@ -51,7 +61,7 @@ pub fn generate_trace_rows_for_perm<F: PrimeField64>(
                 let input_xy = input[y * 5 + x];
                 for limb in 0..U64_LIMBS {
                     row.preimage[y][x][limb] =
-                        F::from_canonical_u64((input_xy >> (16 * limb)) & 0xFFFF);
+                        F::from_canonical_u64((input_xy >> (bits_per_limb * limb)) & limb_mask);
                 }
             }
         }
@@ -62,7 +72,8 @@ pub fn generate_trace_rows_for_perm<F: PrimeField64>(
         for x in 0..5 {
             let input_xy = input[y * 5 + x];
             for limb in 0..U64_LIMBS {
-                rows[0].a[y][x][limb] = F::from_canonical_u64((input_xy >> (16 * limb)) & 0xFFFF);
+                rows[0].a[y][x][limb] =
+                    F::from_canonical_u64((input_xy >> (bits_per_limb * limb)) & limb_mask);
             }
         }
     }
@@ -83,16 +94,21 @@ pub fn generate_trace_rows_for_perm<F: PrimeField64>(
     }
 }
 
-fn generate_trace_row_for_round<F: PrimeField64>(row: &mut KeccakCols<F>, round: usize) {
+fn generate_trace_row_for_round<F: PrimeField64, const U64_LIMBS: usize>(
+    row: &mut KeccakCols<F, U64_LIMBS>,
+    round: usize,
+) {
+    let bits_per_limb = bits_per_limb(U64_LIMBS);
+
     row.step_flags[round] = F::one();
 
     // Populate C[x] = xor(A[x, 0], A[x, 1], A[x, 2], A[x, 3], A[x, 4]).
     for x in 0..5 {
         for z in 0..64 {
-            let limb = z / BITS_PER_LIMB;
-            let bit_in_limb = z % BITS_PER_LIMB;
+            let limb = z / bits_per_limb;
+            let bit_in_limb = z % bits_per_limb;
             let a = (0..5).map(|y| {
-                let a_limb = row.a[y][x][limb].as_canonical_u64() as u16;
+                let a_limb = row.a[y][x][limb].as_canonical_u64();
                 ((a_limb >> bit_in_limb) & 1) != 0
             });
             row.c[x][z] = F::from_bool(a.fold(false, |acc, x| acc ^ x));
@@ -117,9 +133,9 @@ fn generate_trace_row_for_round<F: PrimeField64>(row: &mut KeccakCols<F>, round:
     for x in 0..5 {
         for y in 0..5 {
             for z in 0..64 {
-                let limb = z / BITS_PER_LIMB;
-                let bit_in_limb = z % BITS_PER_LIMB;
-                let a_limb = row.a[y][x][limb].as_canonical_u64() as u16;
+                let limb = z / bits_per_limb;
+                let bit_in_limb = z % bits_per_limb;
+                let a_limb = row.a[y][x][limb].as_canonical_u64();
                 let a_bit = F::from_bool(((a_limb >> bit_in_limb) & 1) != 0);
                 row.a_prime[y][x][z] = xor([a_bit, row.c[x][z], row.c_prime[x][z]]);
             }
@@ -131,7 +147,7 @@ fn generate_trace_row_for_round<F: PrimeField64>(row: &mut KeccakCols<F>, round:
     for y in 0..5 {
         for x in 0..5 {
             for limb in 0..U64_LIMBS {
-                row.a_prime_prime[y][x][limb] = (limb * BITS_PER_LIMB..(limb + 1) * BITS_PER_LIMB)
+                row.a_prime_prime[y][x][limb] = (limb * bits_per_limb..(limb + 1) * bits_per_limb)
                     .rev()
                     .fold(F::zero(), |acc, z| {
                         let bit = xor([
@@ -148,7 +164,7 @@ fn generate_trace_row_for_round<F: PrimeField64>(row: &mut KeccakCols<F>, round:
     let mut val = 0;
     for limb in 0..U64_LIMBS {
         let val_limb = row.a_prime_prime[0][0][limb].as_canonical_u64();
-        val |= val_limb << (limb * BITS_PER_LIMB);
+        val |= val_limb << (limb * bits_per_limb);
     }
     let val_bits: Vec<bool> = (0..64)
         .scan(val, |acc, _| {
@@ -163,8 +179,8 @@ fn generate_trace_row_for_round<F: PrimeField64>(row: &mut KeccakCols<F>, round:
 
     // A''[0, 0] is additionally xor'd with RC.
     for limb in 0..U64_LIMBS {
-        let rc_lo = rc_value_limb(round, limb);
+        let rc_lo = rc_value_limb(round, limb, bits_per_limb);
         row.a_prime_prime_prime_0_0_limbs[limb] =
-            F::from_canonical_u16(row.a_prime_prime[0][0][limb].as_canonical_u64() as u16 ^ rc_lo);
+            F::from_canonical_u64(row.a_prime_prime[0][0][limb].as_canonical_u64() ^ rc_lo);
     }
 }
@@ -11,7 +11,20 @@ pub use constants::*;
 pub use generation::*;
 
 pub const NUM_ROUNDS: usize = 24;
-const BITS_PER_LIMB: usize = 16;
-pub const U64_LIMBS: usize = 64 / BITS_PER_LIMB;
 const RATE_BITS: usize = 1088;
-const RATE_LIMBS: usize = RATE_BITS / BITS_PER_LIMB;
+const RATE_LANES: usize = RATE_BITS / 64;
+
+/// Number of limbs per 64-bit lane on a field with `field_bits` bits. Limbs are as
+/// wide as possible while still fitting in the field, so lanes are split into four
+/// 16-bit limbs on BabyBear and Mersenne31, and into two 32-bit limbs on Goldilocks.
+pub const fn u64_limbs(field_bits: usize) -> usize {
+    let mut bits_per_limb = 64;
+    while bits_per_limb >= field_bits {
+        bits_per_limb /= 2;
+    }
+    64 / bits_per_limb
+}
+
+pub const fn bits_per_limb(u64_limbs: usize) -> usize {
+    64 / u64_limbs
+}
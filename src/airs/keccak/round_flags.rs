@@ -7,11 +7,11 @@ use super::columns::KeccakCols;
 use super::NUM_ROUNDS;
 
 #[inline]
-pub(crate) fn eval_round_flags<AB: AirBuilder>(builder: &mut AB) {
+pub(crate) fn eval_round_flags<AB: AirBuilder, const U64_LIMBS: usize>(builder: &mut AB) {
     let main = builder.main();
     let (local, next) = (main.row_slice(0), main.row_slice(1));
-    let local: &KeccakCols<AB::Var> = (*local).borrow();
-    let next: &KeccakCols<AB::Var> = (*next).borrow();
+    let local: &KeccakCols<AB::Var, U64_LIMBS> = (*local).borrow();
+    let next: &KeccakCols<AB::Var, U64_LIMBS> = (*next).borrow();
 
     // Initially, the first step flag should be 1 while the others should be 0.
     builder.when_first_row().assert_one(local.step_flags[0]);
@@ -0,0 +1,305 @@
+use std::{env, fs, process};
+
+use p3_keccak::Keccak256Hash;
+use p3_keccak_machine::{
+    chips::{DIGEST_WIDTH, MERKLE_TREE_DEPTH, MERKLE_TREE_MAX_LEAVES},
+    config::{default_challenger, default_config, MyConfig, Val},
+    generate_hash_trace, generate_machine_trace, generate_tree_trace, serialize,
+    verify_keccak_machine_proof, KeccakMachine, MerkleDepth, ProofFormat, PublicValues,
+};
+use p3_machine::machine::Machine;
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher};
+
+const USAGE: &str = "\
+Usage:
+    keccak-machine prove-merkle <leaves> <index> <proof-out> [--json]
+    keccak-machine prove-hash <input> <proof-out> [--json]
+    keccak-machine prove-tree <leaves> <proof-out> [--json]
+    keccak-machine verify merkle <proof> <output>
+    keccak-machine verify hash <proof> <input> <output>
+    keccak-machine verify tree <proof> <leaves> <output>
+    keccak-machine inspect merkle <leaves> <index> [--report]
+    keccak-machine inspect hash <input> [--report]
+    keccak-machine inspect tree <leaves> [--report]
+
+<leaves> holds the leaf hashes, either as a JSON array of hex strings or as
+whitespace-separated hex strings. Merkle paths take up to 2^8 leaves, padded with
+zero leaves, and trees any power of two between 2 and 2^16. <input> holds the bytes to hash, either as hex or
+as JSON (a hex string or an array of bytes). <output> is the output printed by
+the prove command, in hex. The verifying key is set up from the statement being
+verified, the input or the leaves of the hash or the tree, rather than read from
+the prover. --report lists the constraints and interactions of every chip along
+with its trace height.";
+
+type Traces = Vec<Option<RowMajorMatrix<Val>>>;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let format = if args.iter().any(|arg| arg == "--json") {
+        ProofFormat::Json
+    } else {
+        ProofFormat::Bincode
+    };
+    let report = args.iter().any(|arg| arg == "--report");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|&arg| arg != "--json" && arg != "--report")
+        .collect();
+
+    let result = match args.as_slice() {
+        ["prove-merkle", leaves, index, proof_out] => {
+            prove_merkle(leaves, index, proof_out, format)
+        }
+        ["prove-hash", input, proof_out] => prove_hash(input, proof_out, format),
+        ["prove-tree", leaves, proof_out] => prove_tree(leaves, proof_out, format),
+        ["verify", "merkle", proof, output] => verify(
+            &KeccakMachine::merkle_path(MerkleDepth::default()),
+            proof,
+            output,
+        ),
+        ["verify", "hash", proof, input, output] => {
+            read_input(input).and_then(|input| verify(&KeccakMachine::hash(&input), proof, output))
+        }
+        ["verify", "tree", proof, leaves, output] => read_tree_leaves(leaves)
+            .and_then(|leaves| verify(&KeccakMachine::tree(&leaves), proof, output)),
+        ["inspect", "merkle", leaves, index] => merkle_traces(leaves, index)
+            .map(|(_, machine, traces)| inspect(&machine, &traces, report)),
+        ["inspect", "hash", input] => read_input(input).map(|input| {
+            let (_, machine, traces) = generate_hash_trace::<MyConfig>(&input);
+            inspect(&machine, &traces, report)
+        }),
+        ["inspect", "tree", leaves] => read_tree_leaves(leaves).map(|leaves| {
+            let (_, _, machine, traces) = generate_tree_trace::<MyConfig>(&leaves);
+            inspect(&machine, &traces, report)
+        }),
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
+
+fn prove_merkle(
+    leaves: &str,
+    index: &str,
+    proof_out: &str,
+    format: ProofFormat,
+) -> Result<(), String> {
+    let (output, machine, traces) = merkle_traces(leaves, index)?;
+    prove(&machine, traces, &output, proof_out, format)?;
+    println!("root: {}", to_hex(&output[..DIGEST_WIDTH]));
+    println!("leaf: {}", to_hex(&output[DIGEST_WIDTH..2 * DIGEST_WIDTH]));
+    println!("leaf index: {index}");
+    Ok(())
+}
+
+fn prove_hash(input: &str, proof_out: &str, format: ProofFormat) -> Result<(), String> {
+    let input = read_input(input)?;
+    let (digest, machine, traces) = generate_hash_trace::<MyConfig>(&input);
+    prove(&machine, traces, &digest, proof_out, format)?;
+    println!("digest: {}", to_hex(&digest));
+    Ok(())
+}
+
+fn prove_tree(leaves: &str, proof_out: &str, format: ProofFormat) -> Result<(), String> {
+    let leaves = read_tree_leaves(leaves)?;
+    let (root, commitment, machine, traces) = generate_tree_trace::<MyConfig>(&leaves);
+    let output = [root, commitment].concat();
+    prove(&machine, traces, &output, proof_out, format)?;
+    println!("root: {}", to_hex(&root));
+    println!("leaves commitment: {}", to_hex(&commitment));
+    Ok(())
+}
+
+/// Verifies the proof in the file `proof` of `machine`, the claimed statement, against
+/// the claimed `output`, given in hex.
+fn verify(machine: &KeccakMachine, proof: &str, output: &str) -> Result<(), String> {
+    let proof = fs::read(proof).map_err(|err| format!("failed to read {proof}: {err}"))?;
+    let public_values: PublicValues = KeccakMachine::public_values(&from_hex(output)?);
+
+    verify_keccak_machine_proof(&proof, machine, &public_values).map_err(|err| err.to_string())?;
+    println!("proof verified");
+    Ok(())
+}
+
+fn inspect(machine: &KeccakMachine, traces: &Traces, report: bool) {
+    if report {
+        print!("{}", machine.report(traces));
+        return;
+    }
+
+    println!("{:<16} {:>10} {:>10}", "chip", "height", "width");
+    for (chip, trace) in machine.chips().iter().zip(traces) {
+        // The chip's name is the name of its `KeccakMachineChip` variant.
+        let name = format!("{chip:?}");
+        let name = name.split('(').next().unwrap_or_default();
+        match trace {
+            Some(trace) => println!("{:<16} {:>10} {:>10}", name, trace.height(), trace.width()),
+            None => println!("{:<16} {:>10} {:>10}", name, "-", "-"),
+        }
+    }
+}
+
+/// Proves `traces`, whose output words end up holding `output`, writes the proof, and
+/// prints the output to pass to `verify`.
+fn prove(
+    machine: &KeccakMachine,
+    traces: Traces,
+    output: &[u8],
+    proof_out: &str,
+    format: ProofFormat,
+) -> Result<(), String> {
+    let config = default_config();
+    let (pk, _) = machine.setup(&config);
+    let mut challenger = default_challenger();
+    let public_values = KeccakMachine::public_values(output);
+    let proof = machine.prove(&config, &mut challenger, &pk, traces, &public_values);
+
+    let proof = serialize(&proof, format).map_err(|err| err.to_string())?;
+    fs::write(proof_out, proof).map_err(|err| format!("failed to write {proof_out}: {err}"))?;
+    println!("output: {}", to_hex(output));
+    Ok(())
+}
+
+/// Builds the tree over the leaves in `leaves`, padded with zero leaves, and generates
+/// the traces proving the path of the leaf at `index`. Returns the output, the root,
+/// the leaf and the leaf index, the machine and the traces.
+fn merkle_traces(leaves: &str, index: &str) -> Result<(Vec<u8>, KeccakMachine, Traces), String> {
+    const NUM_LEAVES: usize = 1 << MERKLE_TREE_DEPTH;
+
+    let mut leaves = read_leaves(leaves)?;
+    if !(1..=NUM_LEAVES).contains(&leaves.len()) {
+        return Err(format!(
+            "expected between 1 and {NUM_LEAVES} leaves, found {}",
+            leaves.len()
+        ));
+    }
+    let leaf_index: usize = index
+        .parse()
+        .map_err(|err| format!("invalid leaf index {index}: {err}"))?;
+    if leaf_index >= leaves.len() {
+        return Err(format!("leaf index {leaf_index} is out of range"));
+    }
+    leaves.resize(NUM_LEAVES, [0; DIGEST_WIDTH]);
+
+    let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+    let mut digests = vec![leaves];
+    while digests.last().unwrap().len() > 1 {
+        let next_level = digests
+            .last()
+            .unwrap()
+            .chunks_exact(2)
+            .map(|chunk| hasher.compress([chunk[0], chunk[1]]))
+            .collect();
+        digests.push(next_level);
+    }
+
+    Ok(generate_machine_trace::<MyConfig, _>(
+        leaf_index, digests, &hasher,
+    ))
+}
+
+fn read_leaves(path: &str) -> Result<Vec<[u8; DIGEST_WIDTH]>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    let leaves: Vec<String> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str(&contents).map_err(|err| format!("invalid leaves: {err}"))?
+    } else {
+        contents.split_whitespace().map(str::to_string).collect()
+    };
+    leaves
+        .iter()
+        .map(|leaf| {
+            from_hex(leaf)?
+                .try_into()
+                .map_err(|_| format!("leaf {leaf} is not {DIGEST_WIDTH} bytes long"))
+        })
+        .collect()
+}
+
+fn read_tree_leaves(path: &str) -> Result<Vec<[u8; DIGEST_WIDTH]>, String> {
+    let leaves = read_leaves(path)?;
+    if !leaves.len().is_power_of_two() || !(2..=MERKLE_TREE_MAX_LEAVES).contains(&leaves.len()) {
+        return Err(format!(
+            "expected a power of two of leaves between 2 and {MERKLE_TREE_MAX_LEAVES}, found {}",
+            leaves.len()
+        ));
+    }
+    Ok(leaves)
+}
+
+fn read_input(path: &str) -> Result<Vec<u8>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    let contents = contents.trim();
+    if contents.starts_with('[') {
+        serde_json::from_str(contents).map_err(|err| format!("invalid input: {err}"))
+    } else if contents.starts_with('"') {
+        let hex: String =
+            serde_json::from_str(contents).map_err(|err| format!("invalid input: {err}"))?;
+        from_hex(&hex)
+    } else {
+        from_hex(contents)
+    }
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if hex.len() % 2 != 0 {
+        return Err(format!("odd number of hex digits in {hex}"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|err| format!("invalid hex {hex}: {err}"))
+        })
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn test_verify_tampered_output() -> Result<(), String> {
+        let dir = env::temp_dir().join(format!("keccak-machine-{}", process::id()));
+        fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        // Fewer leaves than the tree holds, which are padded with zero leaves.
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaves = (0..100)
+            .map(|_| to_hex(&seeded_rng.gen::<[u8; DIGEST_WIDTH]>()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let leaves_path = path("leaves.txt");
+        fs::write(&leaves_path, leaves).map_err(|err| err.to_string())?;
+        let proof_path = path("proof.bin");
+        prove_merkle(&leaves_path, "3", &proof_path, ProofFormat::Bincode)?;
+
+        let machine = KeccakMachine::merkle_path(MerkleDepth::default());
+        let (output, _, _) = merkle_traces(&leaves_path, "3")?;
+        verify(&machine, &proof_path, &to_hex(&output))?;
+        // A wrong root, leaf or leaf index.
+        for i in [0, DIGEST_WIDTH, 2 * DIGEST_WIDTH] {
+            let mut wrong_output = output.clone();
+            wrong_output[i] ^= 1;
+            assert!(verify(&machine, &proof_path, &to_hex(&wrong_output)).is_err());
+        }
+        assert!(verify(&machine, &proof_path, "").is_err());
+        assert!(merkle_traces(&leaves_path, "100").is_err());
+
+        fs::remove_dir_all(&dir).map_err(|err| err.to_string())
+    }
+}
@@ -3,7 +3,7 @@ use p3_keccak_machine::KeccakMachine;
 use p3_machine::machine::Machine;
 
 pub fn main() {
-    let machine = KeccakMachine;
+    let machine = KeccakMachine::default();
 
     machine.write_schema_to_file::<BabyBear>("schema.dbml");
 }
@@ -1,6 +1,6 @@
 use p3_derive::Bus;
 
-#[derive(Bus, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Bus, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum KeccakMachineBus {
     KeccakPermuteInput = 0,
     KeccakPermuteOutput = 1,
@@ -8,6 +8,27 @@ pub enum KeccakMachineBus {
     KeccakSpongeOutput = 3,
     XorInput = 4,
     XorOutput = 5,
-    // Range8 = 6,
-    // Memory = 7,
+    Range8 = 6,
+    Memory = 7,
+    MemoryInit = 8,
+    MemoryFinal = 9,
+    MerkleTreeNode = 10,
+    SpongeCall = 11,
+}
+
+impl KeccakMachineBus {
+    pub const ALL: [KeccakMachineBus; 12] = [
+        KeccakMachineBus::KeccakPermuteInput,
+        KeccakMachineBus::KeccakPermuteOutput,
+        KeccakMachineBus::KeccakSpongeInput,
+        KeccakMachineBus::KeccakSpongeOutput,
+        KeccakMachineBus::XorInput,
+        KeccakMachineBus::XorOutput,
+        KeccakMachineBus::Range8,
+        KeccakMachineBus::Memory,
+        KeccakMachineBus::MemoryInit,
+        KeccakMachineBus::MemoryFinal,
+        KeccakMachineBus::MerkleTreeNode,
+        KeccakMachineBus::SpongeCall,
+    ];
 }
@@ -0,0 +1,49 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use super::{columns::ByteMemoryCols, ByteMemoryChip};
+
+impl<F, const WORD_BYTES: usize> BaseAir<F> for ByteMemoryChip<WORD_BYTES> {
+    fn width(&self) -> usize {
+        ByteMemoryCols::<F, WORD_BYTES>::num_cols()
+    }
+}
+
+impl<AB: AirBuilder, const WORD_BYTES: usize> Air<AB> for ByteMemoryChip<WORD_BYTES> {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &ByteMemoryCols<AB::Var, WORD_BYTES> = (*local).borrow();
+
+        builder.assert_bool(local.is_read);
+        builder.assert_bool(local.is_write);
+        let is_real = local.is_read + local.is_write;
+        builder.assert_bool(is_real.clone());
+
+        // Real rows access exactly one byte of the word.
+        let mut offset_sum = AB::Expr::zero();
+        let mut selected_byte = AB::Expr::zero();
+        for (&offset, &byte) in local.offset.iter().zip(local.word.iter()) {
+            builder.assert_bool(offset);
+            offset_sum += offset.into();
+            selected_byte += offset * byte;
+        }
+        builder.assert_eq(offset_sum, is_real);
+
+        // A read returns the selected byte of the word.
+        builder
+            .when(local.is_read)
+            .assert_eq(local.value, selected_byte);
+
+        // The new word only differs from the old one at the selected byte, which holds
+        // the value. For reads, the value is already the selected byte.
+        for i in 0..WORD_BYTES {
+            builder.assert_eq(
+                local.new_word[i],
+                local.word[i] + local.offset[i] * (local.value - local.word[i]),
+            );
+        }
+    }
+}
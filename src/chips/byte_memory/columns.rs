@@ -0,0 +1,26 @@
+use p3_derive::Columnar;
+
+#[repr(C)]
+#[derive(Columnar)]
+pub struct ByteMemoryCols<T, const WORD_BYTES: usize> {
+    pub timestamp: T,
+
+    /// Word address of the accessed byte.
+    pub addr: T,
+
+    /// One-hot encoding of the position of the accessed byte within its word.
+    pub offset: [T; WORD_BYTES],
+
+    /// The byte that is read or written.
+    pub value: T,
+
+    /// The word before the access.
+    pub word: [T; WORD_BYTES],
+
+    /// The word after the access. Equal to `word` for reads.
+    pub new_word: [T; WORD_BYTES],
+
+    pub is_read: T,
+
+    pub is_write: T,
+}
@@ -0,0 +1,93 @@
+use core::iter::once;
+
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::ByteMemoryCols, ByteMemoryChip};
+
+impl<F: Field, const WORD_BYTES: usize> BaseInteractionAir<F> for ByteMemoryChip<WORD_BYTES> {
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = ByteMemoryCols::<_, WORD_BYTES>::from_slice(main_indices);
+
+        // The byte address is recovered from the word address and the offset.
+        let byte_addr = VirtualPairCol::new_main(
+            once((col_map.addr, F::from_canonical_usize(WORD_BYTES)))
+                .chain(
+                    col_map
+                        .offset
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, offset)| (offset, F::from_canonical_usize(i))),
+                )
+                .collect(),
+            F::zero(),
+        );
+        vec![Interaction {
+            fields: vec![
+                VirtualPairCol::single_main(col_map.timestamp),
+                byte_addr,
+                VirtualPairCol::single_main(col_map.value),
+                VirtualPairCol::single_main(col_map.is_write),
+            ],
+            count: VirtualPairCol::sum_main(vec![col_map.is_read, col_map.is_write]),
+            argument_index: self.bus_byte_memory,
+        }]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = ByteMemoryCols::<_, WORD_BYTES>::from_slice(main_indices);
+
+        vec![
+            // Every access reads the whole word...
+            Interaction {
+                fields: [col_map.timestamp, col_map.addr]
+                    .into_iter()
+                    .chain(col_map.word)
+                    .map(VirtualPairCol::single_main)
+                    .chain(once(VirtualPairCol::constant(F::zero())))
+                    .collect(),
+                count: VirtualPairCol::sum_main(vec![col_map.is_read, col_map.is_write]),
+                argument_index: self.bus_memory,
+            },
+            // ... and writes write it back at the next timestamp.
+            Interaction {
+                fields: once(VirtualPairCol::new_main(
+                    vec![(col_map.timestamp, F::one())],
+                    F::one(),
+                ))
+                .chain(
+                    once(col_map.addr)
+                        .chain(col_map.new_word)
+                        .map(VirtualPairCol::single_main),
+                )
+                .chain(once(VirtualPairCol::constant(F::one())))
+                .collect(),
+                count: VirtualPairCol::single_main(col_map.is_write),
+                argument_index: self.bus_memory,
+            },
+        ]
+    }
+}
+
+impl<F: Field, const WORD_BYTES: usize> InteractionAir<F> for ByteMemoryChip<WORD_BYTES> {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = ByteMemoryCols::<F, WORD_BYTES>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = ByteMemoryCols::<F, WORD_BYTES>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB: InteractionAirBuilder, const WORD_BYTES: usize> Rap<AB> for ByteMemoryChip<WORD_BYTES> {}
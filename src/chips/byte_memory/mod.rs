@@ -0,0 +1,157 @@
+mod air;
+mod columns;
+mod interaction;
+pub mod trace;
+
+/// Byte view of a word-addressed `MemoryChip`. It receives byte reads and writes,
+/// with byte addresses, and turns them into word accesses: each access reads the
+/// whole word at its timestamp, and writes send the updated word back at the next
+/// timestamp. Byte writes to the same word must therefore be at least two
+/// timestamps apart.
+#[derive(Default, Clone, Debug)]
+pub struct ByteMemoryChip<const WORD_BYTES: usize> {
+    pub bus_byte_memory: usize,
+    pub bus_memory: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl<const WORD_BYTES: usize> p3_air_util::AirLogger for ByteMemoryChip<WORD_BYTES> {
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::ByteMemoryCols::<usize, WORD_BYTES>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::ByteMemoryCols::<usize, WORD_BYTES>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chips::memory::{
+            trace::{MemoryOp, OperationKind},
+            MemoryChip,
+        },
+        config::Val,
+        test_util::{
+            assert_mutations_rejected, assert_prove_and_verify_fails, prove_and_verify,
+            random_mutations,
+        },
+    };
+
+    use columns::ByteMemoryCols;
+    use p3_field::AbstractField;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const WORD_BYTES: usize = 4;
+    const IMAGE_SIZE: usize = 64;
+    const NUM_OPS: usize = 200;
+
+    /// Generates a random image and random byte accesses over it, two timestamps
+    /// apart.
+    fn generate_ops(seed: u64) -> (Vec<u8>, Vec<MemoryOp<1>>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let image: Vec<u8> = (0..IMAGE_SIZE).map(|_| rng.gen()).collect();
+        let mut memory = image.clone();
+        let operations = (0..NUM_OPS)
+            .map(|i| {
+                let addr = rng.gen_range(0..IMAGE_SIZE);
+                let kind = if rng.gen() {
+                    memory[addr] = rng.gen();
+                    OperationKind::Write
+                } else {
+                    OperationKind::Read
+                };
+                MemoryOp {
+                    addr: addr as u32,
+                    timestamp: 2 * i as u32,
+                    value: [memory[addr]],
+                    kind,
+                }
+            })
+            .collect();
+
+        (image, operations)
+    }
+
+    fn rows_mut(trace: &mut RowMajorMatrix<Val>) -> &mut [ByteMemoryCols<Val, WORD_BYTES>] {
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<ByteMemoryCols<Val, WORD_BYTES>>()
+        };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        rows
+    }
+
+    #[test]
+    fn test_byte_memory_prove() -> Result<(), VerificationError> {
+        let (image, operations) = generate_ops(0);
+        let trace = ByteMemoryChip::<WORD_BYTES>::generate_trace(&image, &operations);
+        let chip = ByteMemoryChip::<WORD_BYTES> {
+            ..Default::default()
+        };
+
+        prove_and_verify(&chip, trace, vec![])
+    }
+
+    #[test]
+    fn test_byte_memory_word_ops() -> Result<(), VerificationError> {
+        let (image, operations) = generate_ops(0);
+        let word_ops = ByteMemoryChip::<WORD_BYTES>::word_ops(&image, &operations);
+        let trace = MemoryChip::<WORD_BYTES>::generate_trace(&image, word_ops);
+
+        prove_and_verify(&MemoryChip::<WORD_BYTES>::default(), trace, vec![])
+    }
+
+    #[test]
+    fn test_byte_memory_write_other_byte() {
+        let (image, operations) = generate_ops(0);
+        let mut trace = ByteMemoryChip::<WORD_BYTES>::generate_trace(&image, &operations);
+        let rows = rows_mut(&mut trace);
+        let row = rows.iter_mut().find(|row| row.is_write.is_one()).unwrap();
+        let i = row
+            .offset
+            .iter()
+            .position(|offset| offset.is_zero())
+            .unwrap();
+        row.new_word[i] += Val::one();
+
+        assert_prove_and_verify_fails(&ByteMemoryChip::<WORD_BYTES>::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_byte_memory_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let (image, operations) = generate_ops(RANDOM_SEED);
+        let trace = ByteMemoryChip::<WORD_BYTES>::generate_trace(&image, &operations);
+        let chip = ByteMemoryChip::<WORD_BYTES> {
+            ..Default::default()
+        };
+
+        let col_map = ByteMemoryCols::<usize, WORD_BYTES>::col_map();
+        let cols = [
+            col_map.offset.as_slice(),
+            col_map.word.as_slice(),
+            col_map.new_word.as_slice(),
+            &[
+                col_map.timestamp,
+                col_map.addr,
+                col_map.value,
+                col_map.is_read,
+                col_map.is_write,
+            ],
+        ]
+        .concat();
+        let mutations = random_mutations(0..NUM_OPS, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+}
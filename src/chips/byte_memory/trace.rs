@@ -0,0 +1,105 @@
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_matrix::dense::RowMajorMatrix;
+use tracing::instrument;
+
+use super::{columns::ByteMemoryCols, ByteMemoryChip};
+use crate::chips::memory::trace::{image_num_words, image_word, MemoryOp, OperationKind};
+
+/// A byte access together with the word it touches, before and after the access.
+struct WordAccess<'a, const WORD_BYTES: usize> {
+    op: &'a MemoryOp<1>,
+    word: [u8; WORD_BYTES],
+    new_word: [u8; WORD_BYTES],
+}
+
+impl<const WORD_BYTES: usize> ByteMemoryChip<WORD_BYTES> {
+    /// Generates the trace for the byte accesses in `operations`, whose addresses are
+    /// byte addresses. The accesses are replayed in timestamp order on the initial
+    /// memory `image`, so they must be the only accesses to the words they touch.
+    #[instrument(name = "generate ByteMemory trace", skip_all)]
+    pub fn generate_trace<F: PrimeField64>(
+        image: &[u8],
+        operations: &[MemoryOp<1>],
+    ) -> RowMajorMatrix<F> {
+        let num_cols = ByteMemoryCols::<F, WORD_BYTES>::num_cols();
+        let num_real_rows = operations.len();
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) =
+            unsafe { trace.values.align_to_mut::<ByteMemoryCols<F, WORD_BYTES>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        for (row, access) in rows.iter_mut().zip(replay(image, operations)) {
+            let offset = access.op.addr as usize % WORD_BYTES;
+            row.timestamp = F::from_canonical_u32(access.op.timestamp);
+            row.addr = F::from_canonical_usize(access.op.addr as usize / WORD_BYTES);
+            row.offset[offset] = F::one();
+            row.value = F::from_canonical_u8(access.op.value[0]);
+            row.word = access.word.map(F::from_canonical_u8);
+            row.new_word = access.new_word.map(F::from_canonical_u8);
+            match access.op.kind {
+                OperationKind::Read => row.is_read = F::one(),
+                OperationKind::Write => row.is_write = F::one(),
+            }
+        }
+
+        trace
+    }
+
+    /// Returns the word accesses the adapter performs for the byte accesses in
+    /// `operations`: every access reads its word at its timestamp, and writes write
+    /// the updated word back at the next timestamp.
+    pub fn word_ops(image: &[u8], operations: &[MemoryOp<1>]) -> Vec<MemoryOp<WORD_BYTES>> {
+        replay(image, operations)
+            .flat_map(|access| {
+                let addr = access.op.addr / WORD_BYTES as u32;
+                let read = MemoryOp {
+                    addr,
+                    timestamp: access.op.timestamp,
+                    value: access.word,
+                    kind: OperationKind::Read,
+                };
+                let write = match access.op.kind {
+                    OperationKind::Read => None,
+                    OperationKind::Write => Some(MemoryOp {
+                        addr,
+                        timestamp: access.op.timestamp + 1,
+                        value: access.new_word,
+                        kind: OperationKind::Write,
+                    }),
+                };
+                core::iter::once(read).chain(write)
+            })
+            .collect()
+    }
+}
+
+/// Applies the byte accesses in `operations`, in timestamp order, to the words of
+/// `image`. The accesses are returned in their original order.
+fn replay<'a, const WORD_BYTES: usize>(
+    image: &[u8],
+    operations: &'a [MemoryOp<1>],
+) -> impl Iterator<Item = WordAccess<'a, WORD_BYTES>> {
+    let mut memory = (0..image_num_words::<WORD_BYTES>(image))
+        .map(|addr| image_word::<WORD_BYTES>(image, addr))
+        .collect_vec();
+    let mut accesses = operations
+        .iter()
+        .enumerate()
+        .sorted_by_key(|(_, op)| op.timestamp)
+        .map(|(i, op)| {
+            let word_addr = op.addr as usize / WORD_BYTES;
+            let word = memory[word_addr];
+            if let OperationKind::Write = op.kind {
+                memory[word_addr][op.addr as usize % WORD_BYTES] = op.value[0];
+            }
+            let new_word = memory[word_addr];
+            (i, WordAccess { op, word, new_word })
+        })
+        .collect_vec();
+    accesses.sort_by_key(|(i, _)| *i);
+    accesses.into_iter().map(|(_, access)| access)
+}
@@ -0,0 +1,97 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use super::{
+    columns::{ContractAddressCols, NONCE_BYTES},
+    ContractAddressChip, RLP_LIST_PREFIX, RLP_STRING_PREFIX,
+};
+use crate::chips::ETH_ADDRESS_BYTES;
+
+impl<F> BaseAir<F> for ContractAddressChip {
+    fn width(&self) -> usize {
+        ContractAddressCols::<F>::num_cols()
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for ContractAddressChip {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &ContractAddressCols<AB::Var> = (*local).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_create2);
+        builder.assert_bool(local.is_create);
+        builder.assert_bool(local.nonce_high_bit);
+        builder.assert_eq(local.is_create2 + local.is_create, local.is_real);
+
+        // A CREATE nonce has between 0 and `NONCE_BYTES` significant bytes, and the
+        // ones before them are zero.
+        for flag in local.nonce_len {
+            builder.assert_bool(flag);
+        }
+        builder.assert_eq(
+            local
+                .nonce_len
+                .iter()
+                .map(|&flag| flag.into())
+                .sum::<AB::Expr>(),
+            local.is_create,
+        );
+        let len: AB::Expr = local
+            .nonce_len
+            .iter()
+            .enumerate()
+            .map(|(l, &flag)| flag * AB::Expr::from_canonical_usize(l))
+            .sum();
+        for i in 0..NONCE_BYTES {
+            let is_leading_zero: AB::Expr = local.nonce_len[..NONCE_BYTES - i]
+                .iter()
+                .map(|&flag| flag.into())
+                .sum();
+            builder.assert_zero(is_leading_zero * local.nonce[i]);
+        }
+
+        // The first significant byte isn't zero, so that the encoding is minimal.
+        let lead: AB::Expr = (1..=NONCE_BYTES)
+            .map(|l| local.nonce_len[l] * local.nonce[NONCE_BYTES - l])
+            .sum();
+        builder.assert_eq(
+            lead * local.nonce_lead_inv,
+            local.is_create - local.nonce_len[0],
+        );
+
+        // The high bit of the last byte is range checked on the buses, and tells the
+        // single bytes below `0x80` apart.
+        builder.assert_eq(
+            local.is_single_byte,
+            local.nonce_len[1] * (AB::Expr::one() - local.nonce_high_bit),
+        );
+
+        // RLP encodes zero as the empty string, a single byte below `0x80` as itself,
+        // and any other nonce as its length followed by its significant bytes.
+        let is_long = local.is_create - local.nonce_len[0] - local.is_single_byte;
+        builder.assert_eq(
+            local.encoded_nonce[0],
+            local.nonce_len[0] * AB::Expr::from_canonical_u8(RLP_STRING_PREFIX)
+                + local.is_single_byte * local.nonce[NONCE_BYTES - 1]
+                + is_long.clone() * (AB::Expr::from_canonical_u8(RLP_STRING_PREFIX) + len.clone()),
+        );
+        for j in 0..NONCE_BYTES {
+            let byte: AB::Expr = (j + 1..=NONCE_BYTES)
+                .map(|l| local.nonce_len[l] * local.nonce[NONCE_BYTES - l + j])
+                .sum();
+            builder.assert_eq(local.encoded_nonce[j + 1], is_long.clone() * byte);
+        }
+
+        // The payload is the encoded deployer, a byte of prefix followed by its bytes,
+        // and the encoded nonce.
+        let payload_len = AB::Expr::from_canonical_usize(ETH_ADDRESS_BYTES + 2) + is_long * len;
+        builder.when(local.is_create).assert_eq(
+            local.list_prefix,
+            AB::Expr::from_canonical_u8(RLP_LIST_PREFIX) + payload_len,
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use p3_derive::Columnar;
+
+use crate::chips::{DIGEST_WIDTH, ETH_ADDRESS_BYTES, MEMORY_WORD_BYTES};
+
+/// Number of bytes of a CREATE nonce, a big-endian `u64` stored in a memory word.
+pub const NONCE_BYTES: usize = MEMORY_WORD_BYTES;
+
+/// A row per deployment, which hashes the preimage of the contract address: for
+/// CREATE2, `0xff || deployer || salt || init_code_hash`, and for CREATE, the RLP
+/// encoding of `[deployer, nonce]`.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct ContractAddressCols<T> {
+    pub is_real: T,
+
+    pub is_create2: T,
+
+    pub is_create: T,
+
+    /// Timestamp of the reads. The address is written at the next timestamp.
+    pub timestamp: T,
+
+    /// Word address of the deployer, left-padded with zeros to a digest.
+    pub deployer_addr: T,
+
+    /// Word address of the salt followed by the init code hash for CREATE2, and of
+    /// the nonce for CREATE.
+    pub input_addr: T,
+
+    /// Word address the address is written to, left-padded with zeros to a digest.
+    pub address_addr: T,
+
+    /// Id of the sponge operation that hashes the preimage.
+    pub hash_id: T,
+
+    pub deployer: [T; ETH_ADDRESS_BYTES],
+
+    pub salt: [T; DIGEST_WIDTH],
+
+    pub init_code_hash: [T; DIGEST_WIDTH],
+
+    /// The big-endian bytes of the nonce.
+    pub nonce: [T; NONCE_BYTES],
+
+    /// One-hot flags of the number of significant bytes of the nonce, from 0 to
+    /// `NONCE_BYTES`.
+    pub nonce_len: [T; NONCE_BYTES + 1],
+
+    /// Inverse of the first significant byte of the nonce, which is non-zero.
+    pub nonce_lead_inv: T,
+
+    /// The high bit of the last byte of the nonce.
+    pub nonce_high_bit: T,
+
+    /// Whether the nonce is a single byte below `0x80`, which RLP encodes as itself.
+    pub is_single_byte: T,
+
+    /// The RLP list prefix, `0xc0` plus the length of the payload.
+    pub list_prefix: T,
+
+    /// The RLP encoding of the nonce, padded with zeros.
+    pub encoded_nonce: [T; NONCE_BYTES + 1],
+
+    pub digest: [T; DIGEST_WIDTH],
+}
@@ -0,0 +1,229 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{
+    columns::{ContractAddressCols, NONCE_BYTES},
+    ContractAddressChip, CREATE2_PREFIX, RLP_STRING_PREFIX,
+};
+use crate::chips::{
+    keccak_sponge::{
+        columns::KECCAK_CHUNK_BYTES,
+        util::{sponge_input_interactions, sponge_output_interaction},
+    },
+    DIGEST_WIDTH, ETH_ADDRESS_BYTES, MEMORY_WORD_BYTES,
+};
+
+impl<F: Field> BaseInteractionAir<F> for ContractAddressChip {
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = ContractAddressCols::from_slice(main_indices);
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
+                .digest
+                .into_iter()
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(col_map.is_real),
+            self.bus_hasher_output,
+        )]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = ContractAddressCols::from_slice(main_indices);
+        let bytes = |cols: &[usize]| {
+            cols.iter()
+                .map(|&col| VirtualPairCol::single_main(col))
+                .collect_vec()
+        };
+        let zeros = |len: usize| {
+            (0..len)
+                .map(|_| VirtualPairCol::constant(F::zero()))
+                .collect_vec()
+        };
+
+        // Accesses the words `bytes` from `addr` on. Reads happen at `timestamp` and
+        // writes at the next timestamp.
+        let memory_accesses = |addr: usize,
+                               offset: usize,
+                               bytes: Vec<VirtualPairCol<F>>,
+                               is_write: bool,
+                               count: usize| {
+            bytes
+                .chunks(MEMORY_WORD_BYTES)
+                .enumerate()
+                .map(|(k, word)| Interaction {
+                    fields: once(VirtualPairCol::new_main(
+                        vec![(col_map.timestamp, F::one())],
+                        F::from_bool(is_write),
+                    ))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(addr, F::one())],
+                        F::from_canonical_usize(offset + k),
+                    )))
+                    .chain(word.iter().cloned())
+                    .chain(once(VirtualPairCol::constant(F::from_bool(is_write))))
+                    .collect(),
+                    count: VirtualPairCol::single_main(count),
+                    argument_index: self.bus_memory,
+                })
+                .collect_vec()
+        };
+
+        // The deployer and the address are stored left-padded with zeros to a digest,
+        // as the ABI encodes addresses.
+        let padding = DIGEST_WIDTH - ETH_ADDRESS_BYTES;
+        let deployer_word = [zeros(padding), bytes(&col_map.deployer)].concat();
+        let address_word = [zeros(padding), bytes(&col_map.digest[padding..])].concat();
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+
+        let create2_preimage = once(VirtualPairCol::constant(F::from_canonical_u8(
+            CREATE2_PREFIX,
+        )))
+        .chain(bytes(&col_map.deployer))
+        .chain(bytes(&col_map.salt))
+        .chain(bytes(&col_map.init_code_hash))
+        .collect_vec();
+
+        // The RLP encoding of `[deployer, nonce]` takes from 23 to 31 bytes, depending
+        // on the encoding of the nonce, so its chunks are sent with their own lengths:
+        // the third chunk lacks a byte iff the nonce is encoded in a single byte, and
+        // the fourth one holds the significant bytes of the nonce past the first.
+        let create_preimage = [
+            vec![
+                VirtualPairCol::single_main(col_map.list_prefix),
+                VirtualPairCol::constant(F::from_canonical_usize(
+                    RLP_STRING_PREFIX as usize + ETH_ADDRESS_BYTES,
+                )),
+            ],
+            bytes(&col_map.deployer),
+            bytes(&col_map.encoded_nonce),
+        ]
+        .concat();
+        let is_long = vec![
+            (col_map.is_create, F::one()),
+            (col_map.nonce_len[0], -F::one()),
+            (col_map.is_single_byte, -F::one()),
+        ];
+        let chunk_lens = [
+            VirtualPairCol::constant(F::from_canonical_usize(KECCAK_CHUNK_BYTES)),
+            VirtualPairCol::constant(F::from_canonical_usize(KECCAK_CHUNK_BYTES)),
+            VirtualPairCol::new_main(is_long, F::from_canonical_usize(KECCAK_CHUNK_BYTES - 1)),
+            VirtualPairCol::new_main(
+                (2..=NONCE_BYTES)
+                    .map(|l| (col_map.nonce_len[l], F::from_canonical_usize(l - 1)))
+                    .collect(),
+                F::zero(),
+            ),
+        ];
+        let chunk_counts = [
+            VirtualPairCol::single_main(col_map.is_create),
+            VirtualPairCol::single_main(col_map.is_create),
+            VirtualPairCol::single_main(col_map.is_create),
+            VirtualPairCol::new_main(
+                (2..=NONCE_BYTES)
+                    .map(|l| (col_map.nonce_len[l], F::one()))
+                    .collect(),
+                F::zero(),
+            ),
+        ];
+        let create_chunks = create_preimage
+            .chunks(KECCAK_CHUNK_BYTES)
+            .zip(chunk_lens)
+            .zip(chunk_counts)
+            .enumerate()
+            .map(|(k, ((chunk, len), count))| Interaction {
+                fields: once(VirtualPairCol::single_main(col_map.hash_id))
+                    .chain(once(VirtualPairCol::constant(F::from_canonical_usize(
+                        k * KECCAK_CHUNK_BYTES,
+                    ))))
+                    .chain(chunk.iter().cloned())
+                    .chain(zeros(KECCAK_CHUNK_BYTES - chunk.len()))
+                    .chain(once(len))
+                    .collect(),
+                count,
+                argument_index: self.bus_hasher_input,
+            })
+            .collect_vec();
+
+        // The high bit of the last byte of the nonce is its only bit past the low 7,
+        // whose double is a byte.
+        let nonce_high_bit_range_check = Interaction {
+            fields: vec![VirtualPairCol::new_main(
+                vec![
+                    (col_map.nonce[NONCE_BYTES - 1], F::two()),
+                    (col_map.nonce_high_bit, -F::from_canonical_u32(256)),
+                ],
+                F::zero(),
+            )],
+            count: VirtualPairCol::single_main(col_map.is_create),
+            argument_index: self.bus_range_8,
+        };
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                create2_preimage,
+                VirtualPairCol::single_main(col_map.is_create2),
+                self.bus_hasher_input,
+            ),
+            create_chunks,
+            vec![nonce_high_bit_range_check],
+            memory_accesses(
+                col_map.deployer_addr,
+                0,
+                deployer_word,
+                false,
+                col_map.is_real,
+            ),
+            memory_accesses(
+                col_map.input_addr,
+                0,
+                bytes(&col_map.salt),
+                false,
+                col_map.is_create2,
+            ),
+            memory_accesses(
+                col_map.input_addr,
+                words_per_digest,
+                bytes(&col_map.init_code_hash),
+                false,
+                col_map.is_create2,
+            ),
+            memory_accesses(
+                col_map.input_addr,
+                0,
+                bytes(&col_map.nonce),
+                false,
+                col_map.is_create,
+            ),
+            memory_accesses(col_map.address_addr, 0, address_word, true, col_map.is_real),
+        ]
+        .concat()
+    }
+}
+
+impl<F: Field> InteractionAir<F> for ContractAddressChip {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = ContractAddressCols::<F>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = ContractAddressCols::<F>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB: InteractionAirBuilder> Rap<AB> for ContractAddressChip {}
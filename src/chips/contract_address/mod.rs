@@ -0,0 +1,207 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+
+pub use trace::{address_word, rlp_encode_nonce, ContractAddressOp, ContractAddressOpKind};
+
+/// Prefix of the CREATE2 preimage.
+const CREATE2_PREFIX: u8 = 0xff;
+/// RLP prefix of a string, to which its length is added.
+const RLP_STRING_PREFIX: u8 = 0x80;
+/// RLP prefix of a short list, to which the length of its payload is added.
+const RLP_LIST_PREFIX: u8 = 0xc0;
+
+/// Proves the addresses of contracts deployed with CREATE2, from the deployer, the
+/// salt and the init code hash, and with CREATE, from the deployer and its nonce.
+/// Each deployment is a row that reads its inputs from memory, hashes its preimage
+/// over the sponge buses, and writes the address back to memory. The init code hash
+/// is read from memory too, where a memory operation of the sponge can write it from
+/// init code of any length.
+#[derive(Default, Clone, Debug)]
+pub struct ContractAddressChip {
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+
+    pub bus_range_8: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl p3_air_util::AirLogger for ContractAddressChip {
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::ContractAddressCols::<usize>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::ContractAddressCols::<usize>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::ContractAddressCols;
+
+    use itertools::Itertools;
+    use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_symmetric::CryptographicHasher;
+    use p3_uni_stark::VerificationError;
+
+    fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+        (0..N)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect_vec()
+            .try_into()
+            .unwrap()
+    }
+
+    fn create_op(deployer: [u8; 20], nonce: u64, hash_id: usize) -> ContractAddressOp {
+        ContractAddressOp {
+            kind: ContractAddressOpKind::Create { nonce },
+            deployer,
+            timestamp: 0,
+            deployer_addr: (hash_id * 9) as u32,
+            input_addr: (hash_id * 9 + 4) as u32,
+            address_addr: (hash_id * 9 + 5) as u32,
+            hash_id,
+        }
+    }
+
+    fn create2_op(
+        deployer: [u8; 20],
+        salt: [u8; 32],
+        init_code: &[u8],
+        hash_id: usize,
+    ) -> ContractAddressOp {
+        ContractAddressOp {
+            kind: ContractAddressOpKind::Create2 {
+                salt,
+                init_code_hash: Keccak256Hash.hash_iter(init_code.iter().copied()),
+            },
+            deployer,
+            timestamp: 0,
+            deployer_addr: (hash_id * 16) as u32,
+            input_addr: (hash_id * 16 + 4) as u32,
+            address_addr: (hash_id * 16 + 12) as u32,
+            hash_id,
+        }
+    }
+
+    /// CREATEs of nonces of every encoding.
+    fn generate_create_ops() -> Vec<ContractAddressOp> {
+        let deployer = from_hex("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        [1, 0x7f, 0x80, 0x1234, 1 << 40, u64::MAX]
+            .into_iter()
+            .enumerate()
+            .map(|(i, nonce)| create_op(deployer, nonce, i))
+            .collect()
+    }
+
+    fn generate_ops_trace(ops: &[ContractAddressOp]) -> RowMajorMatrix<Val> {
+        ContractAddressChip::generate_trace(ops)
+    }
+
+    #[test]
+    fn test_contract_address_rlp_nonce() {
+        assert_eq!(rlp_encode_nonce(0), [0x80]);
+        assert_eq!(rlp_encode_nonce(0x7f), [0x7f]);
+        assert_eq!(rlp_encode_nonce(0x80), [0x81, 0x80]);
+        assert_eq!(rlp_encode_nonce(0x1234), [0x82, 0x12, 0x34]);
+        assert_eq!(rlp_encode_nonce(u64::MAX), [[0x88], [0xff; 8]].concat());
+    }
+
+    #[test]
+    fn test_contract_address_known_create() {
+        let deployer = from_hex("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        let addresses = [
+            "cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d",
+            "343c43a37d37dff08ae8c4a11544c718abb4fcf8",
+            "f778b86fa74e846c4f0a1fbd1335fe81c00a0c91",
+            "fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c",
+        ];
+        for (nonce, address) in addresses.into_iter().enumerate() {
+            let op = create_op(deployer, nonce as u64, 0);
+            assert_eq!(op.address(), from_hex(address));
+        }
+    }
+
+    #[test]
+    fn test_contract_address_known_create2() {
+        // Examples of EIP-1014.
+        let op = create2_op([0; 20], [0; 32], &[0], 0);
+        assert_eq!(
+            op.address(),
+            from_hex("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38")
+        );
+
+        let deployer = from_hex("00000000000000000000000000000000deadbeef");
+        let mut salt = [0; 32];
+        salt[28..].copy_from_slice(&[0xca, 0xfe, 0xba, 0xbe]);
+        let op = create2_op(deployer, salt, &[0xde, 0xad, 0xbe, 0xef], 0);
+        assert_eq!(
+            op.address(),
+            from_hex("60f3f640a8508fc6a86d45df051962668e1e8ac7")
+        );
+    }
+
+    #[test]
+    fn test_contract_address_prove() -> Result<(), VerificationError> {
+        let deployer = from_hex("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        let mut ops = generate_create_ops();
+        ops.push(create_op(deployer, 0, ops.len()));
+        ops.push(create2_op(deployer, [7; 32], &[0x60; 300], ops.len()));
+
+        let trace = generate_ops_trace(&ops);
+        prove_and_verify(&ContractAddressChip::default(), trace, vec![])
+    }
+
+    #[test]
+    fn test_contract_address_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let chip = ContractAddressChip::default();
+        let col_map = ContractAddressCols::<usize>::col_map();
+
+        // The CREATE2 inputs are free in a CREATE, and the CREATE ones in a CREATE2.
+        let create_ops = generate_create_ops();
+        let trace = generate_ops_trace(&create_ops);
+        let cols = (0..ContractAddressCols::<Val>::num_cols())
+            .filter(|col| !col_map.salt.contains(col) && !col_map.init_code_hash.contains(col))
+            .collect_vec();
+        let mutations = random_mutations(0..create_ops.len(), &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+
+        let create2_ops = (0..4)
+            .map(|i| create2_op([i as u8; 20], [i as u8 + 1; 32], &[i as u8; 100], i))
+            .collect_vec();
+        let trace = generate_ops_trace(&create2_ops);
+        let cols = [
+            col_map.deployer.as_slice(),
+            col_map.salt.as_slice(),
+            col_map.init_code_hash.as_slice(),
+            col_map.digest.as_slice(),
+            &[
+                col_map.is_real,
+                col_map.is_create2,
+                col_map.is_create,
+                col_map.timestamp,
+                col_map.deployer_addr,
+                col_map.input_addr,
+                col_map.address_addr,
+                col_map.hash_id,
+            ],
+        ]
+        .concat();
+        let mutations = random_mutations(0..create2_ops.len(), &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+}
@@ -0,0 +1,240 @@
+use alloc::collections::BTreeMap;
+use core::borrow::Borrow;
+
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use tracing::instrument;
+
+use super::{
+    columns::{ContractAddressCols, NONCE_BYTES},
+    ContractAddressChip, CREATE2_PREFIX, RLP_LIST_PREFIX, RLP_STRING_PREFIX,
+};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    DIGEST_WIDTH, ETH_ADDRESS_BYTES, MEMORY_WORD_BYTES,
+};
+
+#[derive(Clone, Debug)]
+pub enum ContractAddressOpKind {
+    /// `keccak(0xff || deployer || salt || init_code_hash)`, whose salt and init code
+    /// hash are read from `input_addr` on.
+    Create2 {
+        salt: [u8; DIGEST_WIDTH],
+        init_code_hash: [u8; DIGEST_WIDTH],
+    },
+    /// `keccak(rlp([deployer, nonce]))`, whose nonce is read from `input_addr` as a
+    /// big-endian word.
+    Create { nonce: u64 },
+}
+
+#[derive(Clone, Debug)]
+pub struct ContractAddressOp {
+    pub kind: ContractAddressOpKind,
+    pub deployer: [u8; ETH_ADDRESS_BYTES],
+    /// Timestamp of the reads. The address is written at the next timestamp.
+    pub timestamp: u32,
+    /// Word address of the deployer, left-padded with zeros to a digest.
+    pub deployer_addr: u32,
+    /// Word address of the salt followed by the init code hash, or of the nonce.
+    pub input_addr: u32,
+    /// Word address the address is written to, left-padded with zeros to a digest.
+    pub address_addr: u32,
+    /// Id of the sponge operation hashing the preimage.
+    pub hash_id: usize,
+}
+
+/// The RLP encoding of `nonce`: the empty string for zero, the byte itself below
+/// `0x80`, and the significant big-endian bytes prefixed with their length otherwise.
+pub fn rlp_encode_nonce(nonce: u64) -> Vec<u8> {
+    let bytes = nonce.to_be_bytes();
+    let significant = &bytes[(nonce.leading_zeros() / 8) as usize..];
+    match significant {
+        [] => vec![RLP_STRING_PREFIX],
+        [byte] if *byte < RLP_STRING_PREFIX => vec![*byte],
+        _ => [
+            &[RLP_STRING_PREFIX + significant.len() as u8][..],
+            significant,
+        ]
+        .concat(),
+    }
+}
+
+/// Pads `address` to a digest with leading zeros, as the ABI encodes addresses.
+pub fn address_word(address: &[u8; ETH_ADDRESS_BYTES]) -> [u8; DIGEST_WIDTH] {
+    let mut word = [0; DIGEST_WIDTH];
+    word[DIGEST_WIDTH - ETH_ADDRESS_BYTES..].copy_from_slice(address);
+    word
+}
+
+impl ContractAddressOp {
+    /// The input hashed into the address.
+    pub fn preimage(&self) -> Vec<u8> {
+        match &self.kind {
+            ContractAddressOpKind::Create2 {
+                salt,
+                init_code_hash,
+            } => [&[CREATE2_PREFIX][..], &self.deployer, salt, init_code_hash].concat(),
+            ContractAddressOpKind::Create { nonce } => {
+                let payload = [
+                    &[RLP_STRING_PREFIX + ETH_ADDRESS_BYTES as u8][..],
+                    &self.deployer,
+                    &rlp_encode_nonce(*nonce),
+                ]
+                .concat();
+                [&[RLP_LIST_PREFIX + payload.len() as u8][..], &payload].concat()
+            }
+        }
+    }
+
+    pub fn digest(&self) -> [u8; DIGEST_WIDTH] {
+        Keccak256Hash.hash_iter(self.preimage())
+    }
+
+    /// The address of the deployed contract, the last `ETH_ADDRESS_BYTES` bytes of
+    /// the hash of the preimage.
+    pub fn address(&self) -> [u8; ETH_ADDRESS_BYTES] {
+        self.digest()[DIGEST_WIDTH - ETH_ADDRESS_BYTES..]
+            .try_into()
+            .unwrap()
+    }
+
+    pub fn sponge_op(&self) -> KeccakSpongeOp {
+        KeccakSpongeOp {
+            input: self.preimage(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the memory accesses of the operation: the deployer and the inputs are
+    /// read at `timestamp`, and the padded address is written at `timestamp + 1`.
+    pub fn memory_ops(&self) -> Vec<MemoryOp<MEMORY_WORD_BYTES>> {
+        let words = |addr: u32, bytes: &[u8], timestamp: u32, kind: OperationKind| {
+            (0..bytes.len() / MEMORY_WORD_BYTES)
+                .map(|k| MemoryOp {
+                    addr: addr + k as u32,
+                    timestamp,
+                    value: image_word(bytes, k),
+                    kind: kind.clone(),
+                })
+                .collect_vec()
+        };
+
+        let input = match &self.kind {
+            ContractAddressOpKind::Create2 {
+                salt,
+                init_code_hash,
+            } => [*salt, *init_code_hash].concat(),
+            ContractAddressOpKind::Create { nonce } => nonce.to_be_bytes().to_vec(),
+        };
+        [
+            words(
+                self.deployer_addr,
+                &address_word(&self.deployer),
+                self.timestamp,
+                OperationKind::Read,
+            ),
+            words(self.input_addr, &input, self.timestamp, OperationKind::Read),
+            words(
+                self.address_addr,
+                &address_word(&self.address()),
+                self.timestamp + 1,
+                OperationKind::Write,
+            ),
+        ]
+        .concat()
+    }
+}
+
+impl ContractAddressChip {
+    #[instrument(name = "generate ContractAddress trace", skip_all)]
+    pub fn generate_trace<F: PrimeField64>(operations: &[ContractAddressOp]) -> RowMajorMatrix<F> {
+        let num_cols = ContractAddressCols::<F>::num_cols();
+
+        let num_real_rows = operations.len();
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) =
+            unsafe { trace.values.align_to_mut::<ContractAddressCols<F>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations);
+
+        trace
+    }
+
+    /// Returns the doubled low 7 bits of the last byte of every CREATE nonce, which
+    /// are range checked.
+    pub fn generate_range_counts<F: PrimeField64>(trace: &RowMajorMatrix<F>) -> BTreeMap<u32, u32> {
+        let mut count = BTreeMap::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &ContractAddressCols<F> = row.borrow();
+            if row.is_create.is_zero() {
+                continue;
+            }
+            let last_byte = row.nonce[NONCE_BYTES - 1].as_canonical_u64() as u32;
+            count
+                .entry(2 * (last_byte & 0x7f))
+                .and_modify(|c| *c += 1)
+                .or_insert(1);
+        }
+        count
+    }
+
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut ContractAddressCols<F>],
+        ops: &[ContractAddressOp],
+    ) {
+        for (row, op) in rows.iter_mut().zip(ops.iter()) {
+            Self::populate_row(row, op);
+        }
+    }
+
+    pub fn populate_row<F: PrimeField64>(row: &mut ContractAddressCols<F>, op: &ContractAddressOp) {
+        row.is_real = F::one();
+        row.timestamp = F::from_canonical_u32(op.timestamp);
+        row.deployer_addr = F::from_canonical_u32(op.deployer_addr);
+        row.input_addr = F::from_canonical_u32(op.input_addr);
+        row.address_addr = F::from_canonical_u32(op.address_addr);
+        row.hash_id = F::from_canonical_usize(op.hash_id);
+        row.deployer = op.deployer.map(F::from_canonical_u8);
+        row.digest = op.digest().map(F::from_canonical_u8);
+
+        match &op.kind {
+            ContractAddressOpKind::Create2 {
+                salt,
+                init_code_hash,
+            } => {
+                row.is_create2 = F::one();
+                row.salt = salt.map(F::from_canonical_u8);
+                row.init_code_hash = init_code_hash.map(F::from_canonical_u8);
+            }
+            ContractAddressOpKind::Create { nonce } => {
+                let nonce_bytes = nonce.to_be_bytes();
+                let len = NONCE_BYTES - (nonce.leading_zeros() / 8) as usize;
+                let last_byte = nonce_bytes[NONCE_BYTES - 1];
+                let encoded_nonce = rlp_encode_nonce(*nonce);
+
+                row.is_create = F::one();
+                row.nonce = nonce_bytes.map(F::from_canonical_u8);
+                row.nonce_len[len] = F::one();
+                if len > 0 {
+                    row.nonce_lead_inv =
+                        F::from_canonical_u8(nonce_bytes[NONCE_BYTES - len]).inverse();
+                }
+                row.nonce_high_bit = F::from_bool(last_byte >= RLP_STRING_PREFIX);
+                row.is_single_byte = F::from_bool(len == 1 && last_byte < RLP_STRING_PREFIX);
+                row.list_prefix = F::from_canonical_u8(op.preimage()[0]);
+                for (col, &byte) in row.encoded_nonce.iter_mut().zip(&encoded_nonce) {
+                    *col = F::from_canonical_u8(byte);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use p3_derive::Columnar;
+
+use crate::chips::DIGEST_WIDTH;
+
+/// A row per signed message, which hashes the prefix `0x1901`, the domain separator
+/// and the hash of the message into the digest that gets signed.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct Eip712Cols<T> {
+    pub is_real: T,
+
+    /// Timestamp of the reads of the domain separator and of the message hash. The
+    /// digest is written at the next timestamp.
+    pub timestamp: T,
+
+    /// Word address of the domain separator, which the message hash and then the
+    /// digest follow.
+    pub addr: T,
+
+    /// Id of the sponge operation that hashes the signing preimage.
+    pub hash_id: T,
+
+    pub domain_separator: [T; DIGEST_WIDTH],
+
+    /// `hashStruct(message)`.
+    pub message_hash: [T; DIGEST_WIDTH],
+
+    pub digest: [T; DIGEST_WIDTH],
+}
@@ -0,0 +1,104 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::Eip712Cols, Eip712Chip, EIP712_PREFIX};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    DIGEST_WIDTH, MEMORY_WORD_BYTES,
+};
+
+impl<F: Field> BaseInteractionAir<F> for Eip712Chip {
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = Eip712Cols::from_slice(main_indices);
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
+                .digest
+                .into_iter()
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(col_map.is_real),
+            self.bus_hasher_output,
+        )]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = Eip712Cols::from_slice(main_indices);
+
+        // Accesses the words `bytes` from the word `offset` of the record on. Reads
+        // happen at `timestamp` and writes at the next timestamp.
+        let memory_accesses = |offset: usize, bytes: &[usize], is_write: bool| {
+            bytes
+                .chunks(MEMORY_WORD_BYTES)
+                .enumerate()
+                .map(|(k, word)| Interaction {
+                    fields: once(VirtualPairCol::new_main(
+                        vec![(col_map.timestamp, F::one())],
+                        F::from_bool(is_write),
+                    ))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(col_map.addr, F::one())],
+                        F::from_canonical_usize(offset + k),
+                    )))
+                    .chain(word.iter().map(|&byte| VirtualPairCol::single_main(byte)))
+                    .chain(once(VirtualPairCol::constant(F::from_bool(is_write))))
+                    .collect(),
+                    count: VirtualPairCol::single_main(col_map.is_real),
+                    argument_index: self.bus_memory,
+                })
+                .collect_vec()
+        };
+
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+        let preimage = EIP712_PREFIX
+            .into_iter()
+            .map(|byte| VirtualPairCol::constant(F::from_canonical_u8(byte)))
+            .chain(
+                col_map
+                    .domain_separator
+                    .into_iter()
+                    .chain(col_map.message_hash)
+                    .map(VirtualPairCol::single_main),
+            )
+            .collect_vec();
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                preimage,
+                VirtualPairCol::single_main(col_map.is_real),
+                self.bus_hasher_input,
+            ),
+            memory_accesses(0, &col_map.domain_separator, false),
+            memory_accesses(words_per_digest, &col_map.message_hash, false),
+            memory_accesses(2 * words_per_digest, &col_map.digest, true),
+        ]
+        .concat()
+    }
+}
+
+impl<F: Field> InteractionAir<F> for Eip712Chip {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = Eip712Cols::<F>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = Eip712Cols::<F>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB: InteractionAirBuilder> Rap<AB> for Eip712Chip {}
@@ -0,0 +1,234 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+mod typed_data;
+
+pub use trace::Eip712Op;
+pub use typed_data::{HashPreimage, TypedData, TypedDataError, TypedDataField};
+
+/// Prefix of the EIP-712 signing preimage: the EIP-191 prefix `0x19`, and the
+/// version `0x01` of structured data.
+const EIP712_PREFIX: [u8; 2] = [0x19, 0x01];
+
+/// Proves EIP-712 digests `keccak256(0x1901 || domainSeparator || hashStruct(message))`.
+/// Each digest is a row that reads the domain separator and the message hash from
+/// memory, hashes them over the sponge buses, and writes the digest back to memory.
+/// Memory operations of the sponge write the two hashes beforehand, by hashing the
+/// preimages that `TypedData` builds from the leaves up.
+#[derive(Default, Clone, Debug)]
+pub struct Eip712Chip {
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl p3_air_util::AirLogger for Eip712Chip {
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::Eip712Cols::<usize>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::Eip712Cols::<usize>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::Eip712Cols;
+
+    use itertools::Itertools;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// The example of the EIP.
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+        (0..N)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect_vec()
+            .try_into()
+            .unwrap()
+    }
+
+    /// Typed data with a single member `value` of type `ty`.
+    fn single_member(ty: &str, value: &str) -> TypedData {
+        TypedData::from_json(&format!(
+            r#"{{
+                "types": {{ "EIP712Domain": [], "Value": [{{ "name": "value", "type": "{ty}" }}] }},
+                "primaryType": "Value",
+                "domain": {{}},
+                "message": {{ "value": {value} }}
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    /// Encodes `value` as the single member of type `ty` of a struct.
+    fn encode(ty: &str, value: &str) -> Result<[u8; 32], TypedDataError> {
+        let preimage = single_member(ty, value).message_preimage()?;
+        Ok(preimage.input[32..].try_into().unwrap())
+    }
+
+    fn random_ops(num_ops: usize, seed: u64) -> Vec<Eip712Op> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..num_ops)
+            .map(|i| Eip712Op {
+                timestamp: 0,
+                addr: (i * 12) as u32,
+                hash_id: i,
+                domain_separator: rng.gen(),
+                message_hash: rng.gen(),
+            })
+            .collect()
+    }
+
+    fn generate_ops_trace(ops: &[Eip712Op]) -> RowMajorMatrix<Val> {
+        Eip712Chip::generate_trace(ops)
+    }
+
+    #[test]
+    fn test_eip712_mail() -> Result<(), TypedDataError> {
+        let typed_data = TypedData::from_json(MAIL)?;
+        assert_eq!(
+            typed_data.encode_type("Mail")?,
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            typed_data.message_preimage()?.children[0].1.digest(),
+            from_hex("a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2")
+        );
+        assert_eq!(
+            typed_data.domain_separator()?,
+            from_hex("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+        assert_eq!(
+            typed_data.message_hash()?,
+            from_hex("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e")
+        );
+        assert_eq!(
+            typed_data.digest()?,
+            from_hex("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+
+        // The type string, the two persons with their names, and the contents.
+        let preimage = typed_data.message_preimage()?;
+        assert_eq!(preimage.children.len(), 4);
+        assert_eq!(preimage.height(), 2);
+
+        let op = Eip712Op {
+            domain_separator: typed_data.domain_separator()?,
+            message_hash: typed_data.message_hash()?,
+            ..Default::default()
+        };
+        assert_eq!(op.digest(), typed_data.digest()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_eip712_encode_values() -> Result<(), TypedDataError> {
+        let mut word = [0; 32];
+        word[31] = 1;
+        assert_eq!(encode("bool", "true")?, word);
+        assert_eq!(encode("uint8", "\"0x01\"")?, word);
+        assert_eq!(encode("int256", "-1")?, [0xff; 32]);
+        assert_eq!(encode("int8", "\"-128\"")?[31], 0x80);
+        assert_eq!(encode("int8", "\"-128\"")?[..31], [0xff; 31]);
+        assert_eq!(
+            encode("uint256", &format!("\"0x{}\"", "ff".repeat(32)))?,
+            [0xff; 32]
+        );
+
+        let mut word = [0; 32];
+        word[..2].copy_from_slice(&[0xab, 0xcd]);
+        assert_eq!(encode("bytes2", "\"0xabcd\"")?, word);
+
+        // Dynamic values and arrays are hashed.
+        let preimage = single_member("uint8[2]", "[1, 2]").message_preimage()?;
+        assert_eq!(preimage.children[1].1.input.len(), 64);
+
+        // Values out of the range of their type are rejected.
+        let too_large = format!("\"0x1{}\"", "00".repeat(32));
+        for (ty, value) in [
+            ("uint8", "256"),
+            ("uint8", "-1"),
+            ("int8", "128"),
+            ("int8", "\"-129\""),
+            ("uint256", too_large.as_str()),
+            ("bytes2", "\"0xab\""),
+            ("address", "\"0x01\""),
+            ("uint8[2]", "[1]"),
+            ("bool", "1"),
+        ] {
+            assert!(
+                matches!(encode(ty, value), Err(TypedDataError::InvalidValue { .. })),
+                "{value} of type {ty}"
+            );
+        }
+        assert!(matches!(
+            encode("uint7", "1"),
+            Err(TypedDataError::UnknownType(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_eip712_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_ops_trace(&random_ops(5, RANDOM_SEED));
+        prove_and_verify(&Eip712Chip::default(), trace, vec![])
+    }
+
+    #[test]
+    fn test_eip712_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let trace = generate_ops_trace(&random_ops(4, RANDOM_SEED));
+
+        let cols = (0..Eip712Cols::<Val>::num_cols()).collect_vec();
+        let mutations = random_mutations(0..4, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&Eip712Chip::default(), &trace, &mutations);
+    }
+}
@@ -0,0 +1,111 @@
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use tracing::instrument;
+
+use super::{columns::Eip712Cols, Eip712Chip, EIP712_PREFIX};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    DIGEST_WIDTH, MEMORY_WORD_BYTES,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct Eip712Op {
+    /// Timestamp of the reads of the domain separator and of the message hash. The
+    /// digest is written at the next timestamp.
+    pub timestamp: u32,
+    /// Word address of the domain separator, which the message hash and then the
+    /// digest follow.
+    pub addr: u32,
+    /// Id of the sponge operation hashing the signing preimage.
+    pub hash_id: usize,
+    pub domain_separator: [u8; DIGEST_WIDTH],
+    /// `hashStruct(message)`.
+    pub message_hash: [u8; DIGEST_WIDTH],
+}
+
+impl Eip712Op {
+    /// The signing preimage `0x1901 || domainSeparator || hashStruct(message)`.
+    pub fn preimage(&self) -> Vec<u8> {
+        EIP712_PREFIX
+            .into_iter()
+            .chain(self.domain_separator)
+            .chain(self.message_hash)
+            .collect()
+    }
+
+    pub fn digest(&self) -> [u8; DIGEST_WIDTH] {
+        Keccak256Hash.hash_iter(self.preimage())
+    }
+
+    pub fn sponge_op(&self) -> KeccakSpongeOp {
+        KeccakSpongeOp {
+            input: self.preimage(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the memory accesses of the operation: the domain separator and the
+    /// message hash are read at `timestamp`, and the digest is written at
+    /// `timestamp + 1`.
+    pub fn memory_ops(&self) -> Vec<MemoryOp<MEMORY_WORD_BYTES>> {
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+        let inputs = [self.domain_separator, self.message_hash].concat();
+        let reads = (0..2 * words_per_digest).map(|k| MemoryOp {
+            addr: self.addr + k as u32,
+            timestamp: self.timestamp,
+            value: image_word(&inputs, k),
+            kind: OperationKind::Read,
+        });
+        let digest = self.digest();
+        let writes = (0..words_per_digest).map(|k| MemoryOp {
+            addr: self.addr + (2 * words_per_digest + k) as u32,
+            timestamp: self.timestamp + 1,
+            value: image_word(&digest, k),
+            kind: OperationKind::Write,
+        });
+        reads.chain(writes).collect()
+    }
+}
+
+impl Eip712Chip {
+    #[instrument(name = "generate Eip712 trace", skip_all)]
+    pub fn generate_trace<F: PrimeField64>(operations: &[Eip712Op]) -> RowMajorMatrix<F> {
+        let num_cols = Eip712Cols::<F>::num_cols();
+
+        let num_real_rows = operations.len();
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<Eip712Cols<F>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations);
+
+        trace
+    }
+
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut Eip712Cols<F>],
+        ops: &[Eip712Op],
+    ) {
+        for (row, op) in rows.iter_mut().zip(ops.iter()) {
+            Self::populate_row(row, op);
+        }
+    }
+
+    pub fn populate_row<F: PrimeField64>(row: &mut Eip712Cols<F>, op: &Eip712Op) {
+        row.is_real = F::one();
+        row.timestamp = F::from_canonical_u32(op.timestamp);
+        row.addr = F::from_canonical_u32(op.addr);
+        row.hash_id = F::from_canonical_usize(op.hash_id);
+        row.domain_separator = op.domain_separator.map(F::from_canonical_u8);
+        row.message_hash = op.message_hash.map(F::from_canonical_u8);
+        row.digest = op.digest().map(F::from_canonical_u8);
+    }
+}
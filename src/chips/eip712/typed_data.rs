@@ -0,0 +1,370 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::fmt;
+
+use itertools::Itertools;
+use p3_keccak::Keccak256Hash;
+use p3_symmetric::CryptographicHasher;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::EIP712_PREFIX;
+use crate::chips::{DIGEST_WIDTH, ETH_ADDRESS_BYTES};
+
+/// Name of the struct type of the domain.
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+#[derive(Debug)]
+pub enum TypedDataError {
+    Json(serde_json::Error),
+    UnknownType(String),
+    MissingField { type_name: String, field: String },
+    InvalidValue { ty: String, value: Value },
+}
+
+impl fmt::Display for TypedDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedDataError::Json(err) => write!(f, "invalid typed data: {err}"),
+            TypedDataError::UnknownType(ty) => write!(f, "unknown type {ty}"),
+            TypedDataError::MissingField { type_name, field } => {
+                write!(f, "missing field {field} of {type_name}")
+            }
+            TypedDataError::InvalidValue { ty, value } => {
+                write!(f, "invalid value {value} of type {ty}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypedDataError {}
+
+/// A member of a struct type.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// Typed structured data, in the JSON format of `eth_signTypedData_v4`. The struct
+/// types include `EIP712Domain`, the type of `domain`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+/// A Keccak256 preimage of the encoding of typed data: a type string, a dynamic
+/// value, the encoding of the elements of an array, or the type hash and the
+/// encoded members of a struct. Some of its words are the digests of other
+/// preimages, which have to be hashed first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashPreimage {
+    pub input: Vec<u8>,
+    /// The preimages whose digests are in `input`, with the byte offsets of their
+    /// digests.
+    pub children: Vec<(usize, HashPreimage)>,
+}
+
+impl HashPreimage {
+    fn leaf(input: Vec<u8>) -> Self {
+        Self {
+            input,
+            children: vec![],
+        }
+    }
+
+    /// Builds the preimage of the concatenation of `encodings`.
+    fn concat(encodings: Vec<Encoding>) -> Self {
+        let mut preimage = Self::leaf(vec![]);
+        for encoding in encodings {
+            let word = match encoding {
+                Encoding::Word(word) => word,
+                Encoding::Hash(child) => {
+                    let digest = child.digest();
+                    preimage.children.push((preimage.input.len(), child));
+                    digest
+                }
+            };
+            preimage.input.extend(word);
+        }
+        preimage
+    }
+
+    pub fn digest(&self) -> [u8; DIGEST_WIDTH] {
+        Keccak256Hash.hash_iter(self.input.iter().copied())
+    }
+
+    /// Number of preimages on the longest path down from this one, which is 0 for a
+    /// preimage without children.
+    pub fn height(&self) -> usize {
+        self.children
+            .iter()
+            .map(|(_, child)| child.height() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// The encoding of a value: a word of its own, or the hash of a preimage.
+enum Encoding {
+    Word([u8; DIGEST_WIDTH]),
+    Hash(HashPreimage),
+}
+
+impl TypedData {
+    pub fn from_json(json: &str) -> Result<Self, TypedDataError> {
+        serde_json::from_str(json).map_err(TypedDataError::Json)
+    }
+
+    /// Encodes the struct type `type_name` followed by the struct types it references,
+    /// sorted by name.
+    pub fn encode_type(&self, type_name: &str) -> Result<String, TypedDataError> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut dependencies)?;
+        dependencies.remove(type_name);
+
+        let encode = |name: &str| {
+            let fields = self.types[name]
+                .iter()
+                .map(|field| format!("{} {}", field.ty, field.name))
+                .join(",");
+            format!("{name}({fields})")
+        };
+        Ok(core::iter::once(type_name)
+            .chain(dependencies.iter().map(String::as_str))
+            .map(encode)
+            .collect())
+    }
+
+    fn collect_dependencies(
+        &self,
+        type_name: &str,
+        dependencies: &mut BTreeSet<String>,
+    ) -> Result<(), TypedDataError> {
+        if dependencies.contains(type_name) {
+            return Ok(());
+        }
+        let fields = self
+            .types
+            .get(type_name)
+            .ok_or_else(|| TypedDataError::UnknownType(type_name.into()))?;
+        dependencies.insert(type_name.into());
+        for field in fields {
+            let base_type = field.ty.split('[').next().unwrap();
+            if self.types.contains_key(base_type) {
+                self.collect_dependencies(base_type, dependencies)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The preimage of `hashStruct(domain)`, the domain separator.
+    pub fn domain_preimage(&self) -> Result<HashPreimage, TypedDataError> {
+        self.struct_preimage(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// The preimage of `hashStruct(message)`.
+    pub fn message_preimage(&self) -> Result<HashPreimage, TypedDataError> {
+        self.struct_preimage(&self.primary_type, &self.message)
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; DIGEST_WIDTH], TypedDataError> {
+        Ok(self.domain_preimage()?.digest())
+    }
+
+    pub fn message_hash(&self) -> Result<[u8; DIGEST_WIDTH], TypedDataError> {
+        Ok(self.message_preimage()?.digest())
+    }
+
+    /// The digest that gets signed, `keccak256(0x1901 || domainSeparator ||
+    /// hashStruct(message))`.
+    pub fn digest(&self) -> Result<[u8; DIGEST_WIDTH], TypedDataError> {
+        let input = EIP712_PREFIX
+            .into_iter()
+            .chain(self.domain_separator()?)
+            .chain(self.message_hash()?);
+        Ok(Keccak256Hash.hash_iter(input))
+    }
+
+    /// The preimage of `hashStruct(value)`: the hash of the type string, followed by
+    /// the encoded members.
+    fn struct_preimage(
+        &self,
+        type_name: &str,
+        value: &Value,
+    ) -> Result<HashPreimage, TypedDataError> {
+        let fields = self
+            .types
+            .get(type_name)
+            .ok_or_else(|| TypedDataError::UnknownType(type_name.into()))?;
+        let members = value
+            .as_object()
+            .ok_or_else(|| TypedDataError::InvalidValue {
+                ty: type_name.into(),
+                value: value.clone(),
+            })?;
+
+        let type_string = self.encode_type(type_name)?;
+        let mut encodings = vec![Encoding::Hash(HashPreimage::leaf(type_string.into_bytes()))];
+        for field in fields {
+            let member = members
+                .get(&field.name)
+                .ok_or_else(|| TypedDataError::MissingField {
+                    type_name: type_name.into(),
+                    field: field.name.clone(),
+                })?;
+            encodings.push(self.encode_value(&field.ty, member)?);
+        }
+        Ok(HashPreimage::concat(encodings))
+    }
+
+    fn encode_value(&self, ty: &str, value: &Value) -> Result<Encoding, TypedDataError> {
+        let invalid = || TypedDataError::InvalidValue {
+            ty: ty.into(),
+            value: value.clone(),
+        };
+
+        // Arrays hash the concatenation of their encoded elements.
+        if let Some(element_type) = ty.strip_suffix(']') {
+            let (element_type, len) = element_type.rsplit_once('[').ok_or_else(invalid)?;
+            let elements = value.as_array().ok_or_else(invalid)?;
+            if !len.is_empty() && len.parse::<usize>().ok() != Some(elements.len()) {
+                return Err(invalid());
+            }
+            let encodings = elements
+                .iter()
+                .map(|element| self.encode_value(element_type, element))
+                .collect::<Result<_, _>>()?;
+            return Ok(Encoding::Hash(HashPreimage::concat(encodings)));
+        }
+        if self.types.contains_key(ty) {
+            return Ok(Encoding::Hash(self.struct_preimage(ty, value)?));
+        }
+
+        let mut word = [0; DIGEST_WIDTH];
+        match ty {
+            "string" => {
+                let string = value.as_str().ok_or_else(invalid)?;
+                return Ok(Encoding::Hash(HashPreimage::leaf(
+                    string.as_bytes().to_vec(),
+                )));
+            }
+            "bytes" => {
+                let bytes = value.as_str().and_then(decode_hex).ok_or_else(invalid)?;
+                return Ok(Encoding::Hash(HashPreimage::leaf(bytes)));
+            }
+            "bool" => word[DIGEST_WIDTH - 1] = value.as_bool().ok_or_else(invalid)? as u8,
+            "address" => {
+                let address = value.as_str().and_then(decode_hex).ok_or_else(invalid)?;
+                if address.len() != ETH_ADDRESS_BYTES {
+                    return Err(invalid());
+                }
+                word[DIGEST_WIDTH - ETH_ADDRESS_BYTES..].copy_from_slice(&address);
+            }
+            _ => {
+                let unknown = || TypedDataError::UnknownType(ty.into());
+                if let Some(len) = ty.strip_prefix("bytes") {
+                    let len = len.parse::<usize>().map_err(|_| unknown())?;
+                    if !(1..=DIGEST_WIDTH).contains(&len) {
+                        return Err(unknown());
+                    }
+                    let bytes = value.as_str().and_then(decode_hex).ok_or_else(invalid)?;
+                    if bytes.len() != len {
+                        return Err(invalid());
+                    }
+                    word[..len].copy_from_slice(&bytes);
+                } else {
+                    let (is_signed, bits) = if let Some(bits) = ty.strip_prefix("uint") {
+                        (false, bits)
+                    } else if let Some(bits) = ty.strip_prefix("int") {
+                        (true, bits)
+                    } else {
+                        return Err(unknown());
+                    };
+                    let bits = bits.parse::<usize>().map_err(|_| unknown())?;
+                    if bits % 8 != 0 || !(8..=8 * DIGEST_WIDTH).contains(&bits) {
+                        return Err(unknown());
+                    }
+                    word = encode_integer(value, is_signed, bits).ok_or_else(invalid)?;
+                }
+            }
+        }
+        Ok(Encoding::Word(word))
+    }
+}
+
+/// Decodes a `0x`-prefixed hex string.
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x")?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Encodes an integer of `bits` bits as a big-endian two's complement word. The
+/// value is a JSON number, or a decimal or `0x`-prefixed hex string for values that
+/// don't fit in one.
+fn encode_integer(value: &Value, is_signed: bool, bits: usize) -> Option<[u8; DIGEST_WIDTH]> {
+    let (is_negative, magnitude) = match value {
+        Value::Number(number) => {
+            let number = number
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| number.as_u64().map(i128::from))?;
+            (number < 0, number.unsigned_abs().to_string())
+        }
+        Value::String(string) => match string.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude.to_string()),
+            None => (false, string.clone()),
+        },
+        _ => return None,
+    };
+    let (radix, digits) = match magnitude.strip_prefix("0x") {
+        Some(digits) => (16, digits),
+        None => (10, magnitude.as_str()),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut word = [0u8; DIGEST_WIDTH];
+    for digit in digits.chars() {
+        let mut carry = digit.to_digit(radix)?;
+        for byte in word.iter_mut().rev() {
+            let acc = *byte as u32 * radix + carry;
+            *byte = acc as u8;
+            carry = acc >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    if is_negative {
+        // Two's complement: invert and add one.
+        let mut carry = 1;
+        for byte in word.iter_mut().rev() {
+            let acc = !*byte as u32 + carry;
+            *byte = acc as u8;
+            carry = acc >> 8;
+        }
+    }
+
+    // The bits above the value must all copy its sign: those from `bits - 1` on for a
+    // signed integer, and those from `bits` on, which must be zero, for an unsigned one.
+    let is_sign_set = is_negative && word.iter().any(|&byte| byte != 0);
+    if is_sign_set && !is_signed {
+        return None;
+    }
+    let first_sign_bit = if is_signed { bits - 1 } else { bits };
+    let fits = (first_sign_bit..8 * DIGEST_WIDTH)
+        .all(|bit| ((word[DIGEST_WIDTH - 1 - bit / 8] >> (bit % 8)) & 1 == 1) == is_sign_set);
+    fits.then_some(word)
+}
@@ -0,0 +1,22 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_matrix::Matrix;
+
+use super::{columns::EthAddressCols, EthAddressChip};
+
+impl<F> BaseAir<F> for EthAddressChip {
+    fn width(&self) -> usize {
+        EthAddressCols::<F>::num_cols()
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for EthAddressChip {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &EthAddressCols<AB::Var> = (*local).borrow();
+
+        // The key is tied to its address by the sponge and memory buses alone.
+        builder.assert_bool(local.is_real);
+    }
+}
@@ -0,0 +1,29 @@
+use p3_derive::Columnar;
+
+use crate::chips::{DIGEST_WIDTH, PUBLIC_KEY_BYTES};
+
+/// A row per public key, which is hashed into the digest whose last
+/// `ETH_ADDRESS_BYTES` bytes are the address.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct EthAddressCols<T> {
+    pub is_real: T,
+
+    /// Timestamp of the public key read. The address is written at the next
+    /// timestamp.
+    pub timestamp: T,
+
+    /// Word address of the public key.
+    pub key_addr: T,
+
+    /// Word address the address is written to, left-padded with zeros to a digest.
+    pub address_addr: T,
+
+    /// Id of the sponge operation that hashes `public_key`.
+    pub hash_id: T,
+
+    /// The uncompressed public key `x || y`, without the `0x04` prefix.
+    pub public_key: [T; PUBLIC_KEY_BYTES],
+
+    pub digest: [T; DIGEST_WIDTH],
+}
@@ -0,0 +1,107 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::EthAddressCols, EthAddressChip};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    DIGEST_WIDTH, ETH_ADDRESS_BYTES, MEMORY_WORD_BYTES,
+};
+
+impl<F: Field> BaseInteractionAir<F> for EthAddressChip {
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = EthAddressCols::from_slice(main_indices);
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
+                .digest
+                .into_iter()
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(col_map.is_real),
+            self.bus_hasher_output,
+        )]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = EthAddressCols::from_slice(main_indices);
+
+        // Accesses the words `bytes` from `addr` on. Reads happen at `timestamp` and
+        // writes at the next timestamp.
+        let memory_accesses = |addr: usize, bytes: Vec<VirtualPairCol<F>>, is_write: bool| {
+            bytes
+                .chunks(MEMORY_WORD_BYTES)
+                .enumerate()
+                .map(|(k, word)| Interaction {
+                    fields: once(VirtualPairCol::new_main(
+                        vec![(col_map.timestamp, F::one())],
+                        F::from_bool(is_write),
+                    ))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(addr, F::one())],
+                        F::from_canonical_usize(k),
+                    )))
+                    .chain(word.iter().cloned())
+                    .chain(once(VirtualPairCol::constant(F::from_bool(is_write))))
+                    .collect(),
+                    count: VirtualPairCol::single_main(col_map.is_real),
+                    argument_index: self.bus_memory,
+                })
+                .collect_vec()
+        };
+
+        let public_key = col_map
+            .public_key
+            .into_iter()
+            .map(VirtualPairCol::single_main)
+            .collect_vec();
+
+        // The address is the last `ETH_ADDRESS_BYTES` bytes of the digest, written
+        // left-padded with zeros as a 32-byte ABI word.
+        let address = (0..DIGEST_WIDTH - ETH_ADDRESS_BYTES)
+            .map(|_| VirtualPairCol::constant(F::zero()))
+            .chain(
+                col_map.digest[DIGEST_WIDTH - ETH_ADDRESS_BYTES..]
+                    .iter()
+                    .map(|&byte| VirtualPairCol::single_main(byte)),
+            )
+            .collect_vec();
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                public_key.clone(),
+                VirtualPairCol::single_main(col_map.is_real),
+                self.bus_hasher_input,
+            ),
+            memory_accesses(col_map.key_addr, public_key, false),
+            memory_accesses(col_map.address_addr, address, true),
+        ]
+        .concat()
+    }
+}
+
+impl<F: Field> InteractionAir<F> for EthAddressChip {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = EthAddressCols::<F>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = EthAddressCols::<F>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB: InteractionAirBuilder> Rap<AB> for EthAddressChip {}
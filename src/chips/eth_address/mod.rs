@@ -0,0 +1,114 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+
+pub use trace::EthAddressOp;
+
+/// Derives the Ethereum addresses of uncompressed public keys read from memory. Each
+/// key is a row that hashes it over the sponge buses, and writes the last
+/// `ETH_ADDRESS_BYTES` bytes of the digest back to memory.
+#[derive(Default, Clone, Debug)]
+pub struct EthAddressChip {
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl p3_air_util::AirLogger for EthAddressChip {
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::EthAddressCols::<usize>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::EthAddressCols::<usize>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chips::PUBLIC_KEY_BYTES,
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::EthAddressCols;
+
+    use itertools::Itertools;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+        (0..N)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect_vec()
+            .try_into()
+            .unwrap()
+    }
+
+    fn random_ops(num_ops: usize, seed: u64) -> Vec<EthAddressOp> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..num_ops)
+            .map(|i| {
+                let mut public_key = [0; PUBLIC_KEY_BYTES];
+                rng.fill(&mut public_key[..]);
+                EthAddressOp {
+                    timestamp: 0,
+                    key_addr: (i * 8) as u32,
+                    address_addr: (num_ops * 8 + i * 4) as u32,
+                    hash_id: i,
+                    public_key,
+                }
+            })
+            .collect()
+    }
+
+    fn generate_ops_trace(ops: &[EthAddressOp]) -> RowMajorMatrix<Val> {
+        EthAddressChip::generate_trace(ops)
+    }
+
+    #[test]
+    fn test_eth_address_known_key() {
+        // The public key of the secret key 1, the secp256k1 generator.
+        let op = EthAddressOp {
+            timestamp: 0,
+            key_addr: 0,
+            address_addr: 8,
+            hash_id: 0,
+            public_key: from_hex(concat!(
+                "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+            )),
+        };
+        assert_eq!(
+            op.address(),
+            from_hex("7e5f4552091a69125d5dfcb7b8c2659029395bdf")
+        );
+        assert_eq!(op.address_word()[..12], [0; 12]);
+    }
+
+    #[test]
+    fn test_eth_address_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_ops_trace(&random_ops(5, RANDOM_SEED));
+        prove_and_verify(&EthAddressChip::default(), trace, vec![])
+    }
+
+    #[test]
+    fn test_eth_address_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let trace = generate_ops_trace(&random_ops(4, RANDOM_SEED));
+
+        let cols = (0..EthAddressCols::<Val>::num_cols()).collect_vec();
+        let mutations = random_mutations(0..4, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&EthAddressChip::default(), &trace, &mutations);
+    }
+}
@@ -0,0 +1,114 @@
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use tracing::instrument;
+
+use super::{columns::EthAddressCols, EthAddressChip};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    DIGEST_WIDTH, ETH_ADDRESS_BYTES, MEMORY_WORD_BYTES, PUBLIC_KEY_BYTES,
+};
+
+#[derive(Clone, Debug)]
+pub struct EthAddressOp {
+    /// Timestamp of the public key read. The address is written at the next
+    /// timestamp.
+    pub timestamp: u32,
+    /// Word address of the public key.
+    pub key_addr: u32,
+    /// Word address the address is written to.
+    pub address_addr: u32,
+    /// Id of the sponge operation hashing the public key.
+    pub hash_id: usize,
+    /// The uncompressed public key `x || y`, without the `0x04` prefix.
+    pub public_key: [u8; PUBLIC_KEY_BYTES],
+}
+
+impl EthAddressOp {
+    pub fn digest(&self) -> [u8; DIGEST_WIDTH] {
+        Keccak256Hash.hash_iter(self.public_key)
+    }
+
+    /// The Ethereum address of the public key, the last `ETH_ADDRESS_BYTES` bytes of
+    /// its Keccak256 hash.
+    pub fn address(&self) -> [u8; ETH_ADDRESS_BYTES] {
+        self.digest()[DIGEST_WIDTH - ETH_ADDRESS_BYTES..]
+            .try_into()
+            .unwrap()
+    }
+
+    /// The address left-padded with zeros to a 32-byte word, as the ABI encodes it.
+    pub fn address_word(&self) -> [u8; DIGEST_WIDTH] {
+        let mut word = [0; DIGEST_WIDTH];
+        word[DIGEST_WIDTH - ETH_ADDRESS_BYTES..].copy_from_slice(&self.address());
+        word
+    }
+
+    pub fn sponge_op(&self) -> KeccakSpongeOp {
+        KeccakSpongeOp {
+            input: self.public_key.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the memory accesses of the operation: the public key is read at
+    /// `timestamp`, and the padded address is written at `timestamp + 1`.
+    pub fn memory_ops(&self) -> Vec<MemoryOp<MEMORY_WORD_BYTES>> {
+        let reads = (0..PUBLIC_KEY_BYTES / MEMORY_WORD_BYTES).map(|k| MemoryOp {
+            addr: self.key_addr + k as u32,
+            timestamp: self.timestamp,
+            value: image_word(&self.public_key, k),
+            kind: OperationKind::Read,
+        });
+        let address_word = self.address_word();
+        let writes = (0..DIGEST_WIDTH / MEMORY_WORD_BYTES).map(|k| MemoryOp {
+            addr: self.address_addr + k as u32,
+            timestamp: self.timestamp + 1,
+            value: image_word(&address_word, k),
+            kind: OperationKind::Write,
+        });
+        reads.chain(writes).collect()
+    }
+}
+
+impl EthAddressChip {
+    #[instrument(name = "generate EthAddress trace", skip_all)]
+    pub fn generate_trace<F: PrimeField64>(operations: &[EthAddressOp]) -> RowMajorMatrix<F> {
+        let num_cols = EthAddressCols::<F>::num_cols();
+
+        let num_real_rows = operations.len();
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<EthAddressCols<F>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations);
+
+        trace
+    }
+
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut EthAddressCols<F>],
+        ops: &[EthAddressOp],
+    ) {
+        for (row, op) in rows.iter_mut().zip(ops.iter()) {
+            Self::populate_row(row, op);
+        }
+    }
+
+    pub fn populate_row<F: PrimeField64>(row: &mut EthAddressCols<F>, op: &EthAddressOp) {
+        row.is_real = F::one();
+        row.timestamp = F::from_canonical_u32(op.timestamp);
+        row.key_addr = F::from_canonical_u32(op.key_addr);
+        row.address_addr = F::from_canonical_u32(op.address_addr);
+        row.hash_id = F::from_canonical_usize(op.hash_id);
+        row.public_key = op.public_key.map(F::from_canonical_u8);
+        row.digest = op.digest().map(F::from_canonical_u8);
+    }
+}
@@ -0,0 +1,165 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_air_util::builders::SubRangeAirBuilder;
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use crate::airs::step_flags::StepFlagsAir;
+
+use super::{columns::IncrementalMerkleTreeCols, IncrementalMerkleTreeChip};
+
+impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize> BaseAir<F>
+    for IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH>
+{
+    fn width(&self) -> usize {
+        IncrementalMerkleTreeCols::<F, DEPTH, DIGEST_WIDTH>::num_cols()
+    }
+}
+
+impl<AB, const DEPTH: usize, const DIGEST_WIDTH: usize> Air<AB>
+    for IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH>
+where
+    AB: AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let col_map = IncrementalMerkleTreeCols::<AB::Var, DEPTH, DIGEST_WIDTH>::col_map();
+
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &IncrementalMerkleTreeCols<AB::Var, DEPTH, DIGEST_WIDTH> = (*local).borrow();
+        let next: &IncrementalMerkleTreeCols<AB::Var, DEPTH, DIGEST_WIDTH> = (*next).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_insert);
+        builder.assert_bool(local.is_root);
+        builder.assert_bool(local.has_node);
+        builder.assert_bool(local.bit);
+        builder.assert_bool(local.new_bit);
+        builder.assert_bool(local.is_right_child);
+        builder.assert_bool(local.hashes);
+        builder.assert_eq(local.is_insert + local.is_root, local.is_real);
+        builder.when(local.has_node).assert_one(local.is_real);
+        builder.when(local.hashes).assert_one(local.has_node);
+
+        let step_flags_air = StepFlagsAir::<DEPTH>;
+        let mut sub_builder = SubRangeAirBuilder::new_main(builder, col_map.step_flags.as_range());
+        step_flags_air.eval(&mut sub_builder);
+
+        let is_first_step = local.step_flags.flags[0];
+        let is_final_step = local.step_flags.flags[DEPTH - 1];
+
+        // An operation spans the rows of all the levels.
+        for (local_col, next_col) in [
+            (local.is_insert, next.is_insert),
+            (local.is_root, next.is_root),
+            (local.timestamp, next.timestamp),
+            (local.tree_addr, next.tree_addr),
+            (local.addr, next.addr),
+        ] {
+            builder
+                .when_transition()
+                .when_ne(is_final_step, AB::Expr::one())
+                .assert_eq(local_col, next_col);
+        }
+
+        // An insert starts from the leaf it reads, and a root from the zero leaf. An
+        // insert can't go past the top level.
+        builder
+            .when(is_first_step)
+            .assert_eq(local.has_node, local.is_real);
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(is_first_step)
+                .when(local.is_root)
+                .assert_zero(local.node[i]);
+        }
+        builder.assert_eq(local.reads_node, is_first_step * local.is_insert);
+        builder
+            .when(is_final_step)
+            .when(local.is_insert)
+            .assert_zero(local.hashes);
+        builder.assert_eq(local.writes_root, is_final_step * local.is_root);
+
+        // An insert merges its node with the left sibling of every level whose bit is
+        // set, up to the first one that isn't.
+        builder
+            .when(local.is_insert)
+            .assert_eq(local.hashes, local.has_node * local.bit);
+        builder
+            .when(local.is_insert)
+            .assert_one(local.is_right_child);
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(local.is_insert)
+                .assert_eq(local.sibling[i], local.branch[i]);
+        }
+
+        // A root hashes every level, with the left sibling where the bit is set and
+        // with the zero hash of the level otherwise. The zero hashes are constants of
+        // the levels, selected by the step flags.
+        builder.when(local.is_root).assert_one(local.hashes);
+        builder
+            .when(local.is_root)
+            .assert_eq(local.is_right_child, local.bit);
+        for i in 0..DIGEST_WIDTH {
+            let zero_hash: AB::Expr = local
+                .step_flags
+                .flags
+                .iter()
+                .zip(self.zero_hashes.iter())
+                .map(|(&flag, zero_hash)| flag * AB::Expr::from_canonical_u8(zero_hash[i]))
+                .sum();
+            builder.when(local.is_root).assert_eq(
+                local.sibling[i],
+                zero_hash.clone() + local.bit * (local.branch[i] - zero_hash),
+            );
+        }
+
+        // Left and right nodes are selected correctly, and a level that doesn't hash
+        // outputs its node.
+        for i in 0..DIGEST_WIDTH {
+            let diff = local.node[i] - local.sibling[i];
+            builder.assert_eq(
+                local.node[i] - local.is_right_child * diff.clone(),
+                local.left[i],
+            );
+            builder.assert_eq(
+                local.sibling[i] + local.is_right_child * diff,
+                local.right[i],
+            );
+
+            builder
+                .when_ne(local.hashes, AB::Expr::one())
+                .assert_eq(local.output[i], local.node[i]);
+        }
+
+        // An insert stores its node in the slot of the first level whose bit is unset,
+        // and flips the bits up to it, as adding one to the number of leaves does. The
+        // left siblings it merges stay in their slots.
+        builder.when(local.is_insert).assert_eq(
+            local.new_bit,
+            local.bit + local.has_node - local.has_node * local.bit * AB::Expr::two(),
+        );
+        for i in 0..DIGEST_WIDTH {
+            builder.when(local.is_insert).assert_eq(
+                local.new_branch[i],
+                local.branch[i]
+                    + (local.has_node - local.hashes) * (local.node[i] - local.branch[i]),
+            );
+        }
+
+        // The node is carried up while it's hashed.
+        builder
+            .when_transition()
+            .when_ne(is_final_step, AB::Expr::one())
+            .assert_eq(next.has_node, local.hashes);
+
+        // Output is copied to the next row.
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when_transition()
+                .when_ne(is_final_step, AB::Expr::one())
+                .assert_eq(local.output[i], next.node[i]);
+        }
+    }
+}
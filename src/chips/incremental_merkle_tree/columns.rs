@@ -0,0 +1,69 @@
+use p3_derive::Columnar;
+
+use crate::airs::step_flags::StepFlagsCols;
+
+/// A row per level of an operation, from the leaves up. Row `h` reads the slot of
+/// level `h`: bit `h` of the number of leaves and the left sibling `branch[h]`.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct IncrementalMerkleTreeCols<T, const DEPTH: usize, const DIGEST_WIDTH: usize> {
+    pub is_real: T,
+
+    pub step_flags: StepFlagsCols<T, DEPTH>,
+
+    /// Whether the operation inserts the leaf at `addr`.
+    pub is_insert: T,
+
+    /// Whether the operation computes the root written to `addr`.
+    pub is_root: T,
+
+    /// Timestamp of the reads. The slots and the root are written at the next
+    /// timestamp.
+    pub timestamp: T,
+
+    /// Word address of the slot of level 0.
+    pub tree_addr: T,
+
+    /// Word address of the inserted leaf or of the root.
+    pub addr: T,
+
+    /// Whether this row reads `node` from `addr`, which the first row of an insert
+    /// does.
+    pub reads_node: T,
+
+    /// Whether this row writes `output` to `addr`, which the last row of a root does.
+    pub writes_root: T,
+
+    /// Whether `node` is carried up this level. An insert carries its leaf up to the
+    /// first level whose bit is unset, and a root carries its node up to the top.
+    pub has_node: T,
+
+    pub node: [T; DIGEST_WIDTH],
+
+    /// Bit `h` of the number of leaves.
+    pub bit: T,
+
+    pub branch: [T; DIGEST_WIDTH],
+
+    /// The slot written by an insert.
+    pub new_bit: T,
+
+    pub new_branch: [T; DIGEST_WIDTH],
+
+    /// `branch[h]`, or the zero hash of the level for a root whose bit is unset.
+    pub sibling: [T; DIGEST_WIDTH],
+
+    pub is_right_child: T,
+
+    /// Whether this row hashes `left || right`.
+    pub hashes: T,
+
+    /// Id of the sponge operation that hashes `left || right`.
+    pub hash_id: T,
+
+    pub left: [T; DIGEST_WIDTH],
+
+    pub right: [T; DIGEST_WIDTH],
+
+    pub output: [T; DIGEST_WIDTH],
+}
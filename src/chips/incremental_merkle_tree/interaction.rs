@@ -0,0 +1,141 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::IncrementalMerkleTreeCols, IncrementalMerkleTreeChip};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    MEMORY_WORD_BYTES,
+};
+
+impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize> BaseInteractionAir<F>
+    for IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH>
+where
+    F: Field,
+{
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = IncrementalMerkleTreeCols::<_, DEPTH, DIGEST_WIDTH>::from_slice(main_indices);
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
+                .output
+                .into_iter()
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(col_map.hashes),
+            self.bus_hasher_output,
+        )]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = IncrementalMerkleTreeCols::<_, DEPTH, DIGEST_WIDTH>::from_slice(main_indices);
+        let bytes = |cols: &[usize]| {
+            cols.iter()
+                .map(|&col| VirtualPairCol::single_main(col))
+                .collect_vec()
+        };
+
+        // Accesses the words `bytes` from `addr` on. Reads happen at `timestamp` and
+        // writes at the next timestamp.
+        let memory_accesses =
+            |addr: &[(usize, F)], bytes: Vec<VirtualPairCol<F>>, is_write: bool, count: usize| {
+                bytes
+                    .chunks(MEMORY_WORD_BYTES)
+                    .enumerate()
+                    .map(|(k, word)| Interaction {
+                        fields: once(VirtualPairCol::new_main(
+                            vec![(col_map.timestamp, F::one())],
+                            F::from_bool(is_write),
+                        ))
+                        .chain(once(VirtualPairCol::new_main(
+                            addr.to_vec(),
+                            F::from_canonical_usize(k),
+                        )))
+                        .chain(word.iter().cloned())
+                        .chain(once(VirtualPairCol::constant(F::from_bool(is_write))))
+                        .collect(),
+                        count: VirtualPairCol::single_main(count),
+                        argument_index: self.bus_memory,
+                    })
+                    .collect_vec()
+            };
+
+        // The slot of level `h` is at `tree_addr + h * SLOT_WORDS`: a word whose first
+        // byte is bit `h` of the number of leaves, followed by `branch[h]`.
+        let slot_addr = once((col_map.tree_addr, F::one()))
+            .chain(
+                col_map
+                    .step_flags
+                    .flags
+                    .iter()
+                    .enumerate()
+                    .map(|(h, &flag)| (flag, F::from_canonical_usize(h * Self::SLOT_WORDS))),
+            )
+            .collect_vec();
+        let slot = |bit: usize, branch: &[usize]| {
+            once(VirtualPairCol::single_main(bit))
+                .chain((1..MEMORY_WORD_BYTES).map(|_| VirtualPairCol::constant(F::zero())))
+                .chain(bytes(branch))
+                .collect_vec()
+        };
+        let addr = [(col_map.addr, F::one())];
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                [bytes(&col_map.left), bytes(&col_map.right)].concat(),
+                VirtualPairCol::single_main(col_map.hashes),
+                self.bus_hasher_input,
+            ),
+            memory_accesses(
+                &slot_addr,
+                slot(col_map.bit, &col_map.branch),
+                false,
+                col_map.is_real,
+            ),
+            memory_accesses(
+                &slot_addr,
+                slot(col_map.new_bit, &col_map.new_branch),
+                true,
+                col_map.is_insert,
+            ),
+            memory_accesses(&addr, bytes(&col_map.node), false, col_map.reads_node),
+            memory_accesses(&addr, bytes(&col_map.output), true, col_map.writes_root),
+        ]
+        .concat()
+    }
+}
+
+impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize> InteractionAir<F>
+    for IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH>
+where
+    F: Field,
+{
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = IncrementalMerkleTreeCols::<F, DEPTH, DIGEST_WIDTH>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = IncrementalMerkleTreeCols::<F, DEPTH, DIGEST_WIDTH>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB, const DEPTH: usize, const DIGEST_WIDTH: usize> Rap<AB>
+    for IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH>
+where
+    AB: InteractionAirBuilder,
+{
+}
@@ -0,0 +1,219 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+
+pub use trace::{zero_hashes, IncrementalTreeOp, IncrementalTreeOpKind, IncrementalTreeState};
+
+use super::MEMORY_WORD_BYTES;
+
+/// Proves operations on an incremental Merkle tree of depth `DEPTH`, whose state is
+/// stored in memory as the deposit contract keeps it, one slot per level: inserting a
+/// leaf, and computing the root, whose leaves past the inserted ones are zero. An
+/// operation takes a row per level, and hashes over the sponge buses.
+#[derive(Default, Clone, Debug)]
+pub struct IncrementalMerkleTreeChip<const DEPTH: usize, const DIGEST_WIDTH: usize> {
+    /// The zero hashes of the levels, as computed by `zero_hashes`, which the roots
+    /// are built with.
+    pub zero_hashes: Vec<[u8; DIGEST_WIDTH]>,
+
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH> {
+    /// Number of words in the slot of a level: a word holding the bit of the level in
+    /// the number of leaves, followed by the left sibling.
+    pub const SLOT_WORDS: usize = 1 + DIGEST_WIDTH / MEMORY_WORD_BYTES;
+}
+
+#[cfg(feature = "air-logger")]
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> p3_air_util::AirLogger
+    for IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH>
+{
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::IncrementalMerkleTreeCols::<usize, DEPTH, DIGEST_WIDTH>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::IncrementalMerkleTreeCols::<usize, DEPTH, DIGEST_WIDTH>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::IncrementalMerkleTreeCols;
+
+    use itertools::Itertools;
+    use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher};
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const DEPTH: usize = 4;
+
+    type Hasher = CompressionFunctionFromHasher<u8, Keccak256Hash, 2, 32>;
+    type State = IncrementalTreeState<DEPTH, 32>;
+
+    fn hasher() -> Hasher {
+        CompressionFunctionFromHasher::new(Keccak256Hash)
+    }
+
+    fn chip() -> IncrementalMerkleTreeChip<DEPTH, 32> {
+        IncrementalMerkleTreeChip {
+            zero_hashes: zero_hashes(DEPTH, &hasher()),
+            ..Default::default()
+        }
+    }
+
+    fn random_leaves(num_leaves: usize, seed: u64) -> Vec<[u8; 32]> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..num_leaves).map(|_| rng.gen()).collect()
+    }
+
+    /// The root of the tree of `2^DEPTH` leaves starting with `leaves`, padded with
+    /// zero leaves.
+    fn naive_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let hasher = hasher();
+        let mut level = leaves.to_vec();
+        level.resize(1 << DEPTH, [0; 32]);
+        while level.len() > 1 {
+            level = level
+                .chunks_exact(2)
+                .map(|pair| hasher.compress([pair[0], pair[1]]))
+                .collect_vec();
+        }
+        level[0]
+    }
+
+    /// An insert and a root on a tree of 3 leaves, then a root after the insert.
+    fn generate_ops(seed: u64) -> Vec<IncrementalTreeOp<DEPTH, 32>> {
+        let hasher = hasher();
+        let leaves = random_leaves(4, seed);
+        let state = State::from_leaves(&leaves[..3], &hasher);
+
+        let insert = IncrementalTreeOp {
+            kind: IncrementalTreeOpKind::Insert { leaf: leaves[3] },
+            state,
+            ..Default::default()
+        };
+        let root = IncrementalTreeOp {
+            kind: IncrementalTreeOpKind::Root,
+            state,
+            first_hash_id: 2,
+            ..Default::default()
+        };
+        let next_root = IncrementalTreeOp {
+            kind: IncrementalTreeOpKind::Root,
+            state: insert.next_state(&hasher),
+            first_hash_id: 2 + DEPTH,
+            ..Default::default()
+        };
+        vec![insert, root, next_root]
+    }
+
+    fn generate_ops_trace(ops: &[IncrementalTreeOp<DEPTH, 32>]) -> RowMajorMatrix<Val> {
+        IncrementalMerkleTreeChip::generate_trace(ops, &hasher())
+    }
+
+    #[test]
+    fn test_incremental_tree_roots() {
+        const RANDOM_SEED: u64 = 0;
+
+        let hasher = hasher();
+        let leaves = random_leaves((1 << DEPTH) - 1, RANDOM_SEED);
+        let mut state = State::default();
+        assert_eq!(state.root(&hasher), naive_root(&[]));
+        for (i, &leaf) in leaves.iter().enumerate() {
+            state.insert(leaf, &hasher);
+            assert_eq!(state.count, i as u64 + 1);
+            assert_eq!(state.root(&hasher), naive_root(&leaves[..=i]));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the tree is full")]
+    fn test_incremental_tree_full() {
+        let hasher = hasher();
+        let mut state = State::from_leaves(&random_leaves((1 << DEPTH) - 1, 0), &hasher);
+        state.insert([0; 32], &hasher);
+    }
+
+    #[test]
+    fn test_incremental_tree_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_ops_trace(&generate_ops(RANDOM_SEED));
+        prove_and_verify(&chip(), trace, vec![])
+    }
+
+    #[test]
+    fn test_incremental_tree_op_hashes() {
+        const RANDOM_SEED: u64 = 0;
+
+        let hasher = hasher();
+        let ops = generate_ops(RANDOM_SEED);
+        assert_eq!(ops[0].sponge_ops(&hasher).len(), 2);
+        assert_eq!(ops[1].sponge_ops(&hasher).len(), DEPTH);
+        assert_eq!(ops[2].sponge_ops(&hasher).len(), DEPTH);
+    }
+
+    #[test]
+    fn test_incremental_tree_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let ops = generate_ops(RANDOM_SEED);
+        let chip = chip();
+
+        // The other columns of an insert are free where they aren't used.
+        let col_map = IncrementalMerkleTreeCols::<usize, DEPTH, 32>::col_map();
+        let cols = [
+            col_map.node.as_slice(),
+            col_map.branch.as_slice(),
+            col_map.new_branch.as_slice(),
+            col_map.sibling.as_slice(),
+            col_map.left.as_slice(),
+            col_map.right.as_slice(),
+            col_map.output.as_slice(),
+            &[
+                col_map.is_real,
+                col_map.is_insert,
+                col_map.is_root,
+                col_map.timestamp,
+                col_map.tree_addr,
+                col_map.addr,
+                col_map.reads_node,
+                col_map.writes_root,
+                col_map.has_node,
+                col_map.bit,
+                col_map.new_bit,
+                col_map.is_right_child,
+                col_map.hashes,
+            ],
+        ]
+        .concat();
+        let trace = generate_ops_trace(&ops[..1]);
+        let mutations = random_mutations(0..DEPTH, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+
+        // A root's siblings are fixed by the zero hashes.
+        let trace = generate_ops_trace(&ops[1..2]);
+        let mutations = random_mutations(
+            0..DEPTH,
+            col_map.sibling.as_slice(),
+            NUM_MUTATIONS,
+            RANDOM_SEED,
+        );
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+}
@@ -0,0 +1,377 @@
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CompressionFunction;
+use tracing::instrument;
+
+use super::{columns::IncrementalMerkleTreeCols, IncrementalMerkleTreeChip};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    MEMORY_WORD_BYTES,
+};
+
+/// The state of an incremental Merkle tree, as kept by the Ethereum deposit contract:
+/// the number of leaves, and the left sibling `branch[h]` of the last subtree
+/// completed at each level `h`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncrementalTreeState<const DEPTH: usize, const DIGEST_WIDTH: usize> {
+    pub count: u64,
+    pub branch: [[u8; DIGEST_WIDTH]; DEPTH],
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> Default
+    for IncrementalTreeState<DEPTH, DIGEST_WIDTH>
+{
+    fn default() -> Self {
+        Self {
+            count: 0,
+            branch: [[0; DIGEST_WIDTH]; DEPTH],
+        }
+    }
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> IncrementalTreeState<DEPTH, DIGEST_WIDTH> {
+    pub fn from_leaves<Compress>(leaves: &[[u8; DIGEST_WIDTH]], hasher: &Compress) -> Self
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let mut state = Self::default();
+        for &leaf in leaves {
+            state.insert(leaf, hasher);
+        }
+        state
+    }
+
+    /// Inserts `leaf`.
+    ///
+    /// Panics if the tree is full.
+    pub fn insert<Compress>(&mut self, leaf: [u8; DIGEST_WIDTH], hasher: &Compress)
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let op = IncrementalTreeOp {
+            kind: IncrementalTreeOpKind::Insert { leaf },
+            state: *self,
+            ..Default::default()
+        };
+        *self = op.next_state(hasher);
+    }
+
+    /// The root of the tree, whose leaves past `count` are zero.
+    pub fn root<Compress>(&self, hasher: &Compress) -> [u8; DIGEST_WIDTH]
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let op = IncrementalTreeOp {
+            kind: IncrementalTreeOpKind::Root,
+            state: *self,
+            ..Default::default()
+        };
+        op.levels(hasher).last().unwrap().output
+    }
+
+    /// The memory image of the slots, from level 0 up. A slot is a word whose first
+    /// byte is the bit of the level in `count`, followed by the left sibling.
+    pub fn image(&self) -> Vec<u8> {
+        self.branch
+            .iter()
+            .enumerate()
+            .flat_map(|(h, branch)| {
+                let mut slot = vec![0; MEMORY_WORD_BYTES];
+                slot[0] = ((self.count >> h) & 1) as u8;
+                slot.extend(branch);
+                slot
+            })
+            .collect()
+    }
+}
+
+/// The zero hashes of the levels of a tree: the roots of the subtrees of zero leaves.
+pub fn zero_hashes<Compress, const DIGEST_WIDTH: usize>(
+    depth: usize,
+    hasher: &Compress,
+) -> Vec<[u8; DIGEST_WIDTH]>
+where
+    Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+{
+    let mut zero_hashes = vec![[0; DIGEST_WIDTH]];
+    for h in 1..depth {
+        zero_hashes.push(hasher.compress([zero_hashes[h - 1]; 2]));
+    }
+    zero_hashes
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum IncrementalTreeOpKind<const DIGEST_WIDTH: usize> {
+    /// Inserts `leaf`, read from `addr`.
+    Insert { leaf: [u8; DIGEST_WIDTH] },
+    /// Computes the root, written to `addr`.
+    #[default]
+    Root,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct IncrementalTreeOp<const DEPTH: usize, const DIGEST_WIDTH: usize> {
+    pub kind: IncrementalTreeOpKind<DIGEST_WIDTH>,
+    /// The state before the operation, whose slots start at `tree_addr`.
+    pub state: IncrementalTreeState<DEPTH, DIGEST_WIDTH>,
+    /// Timestamp of the reads. An insert writes the slots, and a root the root, at
+    /// the next timestamp.
+    pub timestamp: u32,
+    /// Word address of the slot of level 0.
+    pub tree_addr: u32,
+    /// Word address of the inserted leaf or of the root.
+    pub addr: u32,
+    /// Id of the sponge operation of the first hash. The following hashes, from the
+    /// lowest level up, take the following ids.
+    pub first_hash_id: usize,
+}
+
+/// The values of an operation at one level.
+struct Level<const DIGEST_WIDTH: usize> {
+    has_node: bool,
+    node: [u8; DIGEST_WIDTH],
+    sibling: [u8; DIGEST_WIDTH],
+    is_right_child: bool,
+    hashes: bool,
+    output: [u8; DIGEST_WIDTH],
+    new_branch: [u8; DIGEST_WIDTH],
+}
+
+impl<const DIGEST_WIDTH: usize> Level<DIGEST_WIDTH> {
+    fn pair(&self) -> ([u8; DIGEST_WIDTH], [u8; DIGEST_WIDTH]) {
+        if self.is_right_child {
+            (self.sibling, self.node)
+        } else {
+            (self.node, self.sibling)
+        }
+    }
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> IncrementalTreeOp<DEPTH, DIGEST_WIDTH> {
+    /// Returns the values of the operation at each level, from the leaves up.
+    ///
+    /// Panics if an insert goes past the top level, which it does when the tree is
+    /// full.
+    fn levels<Compress>(&self, hasher: &Compress) -> Vec<Level<DIGEST_WIDTH>>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let zero_hashes = zero_hashes(DEPTH, hasher);
+        let (is_insert, mut node) = match self.kind {
+            IncrementalTreeOpKind::Insert { leaf } => (true, leaf),
+            IncrementalTreeOpKind::Root => (false, [0; DIGEST_WIDTH]),
+        };
+
+        let mut has_node = true;
+        (0..DEPTH)
+            .map(|h| {
+                let bit = (self.state.count >> h) & 1 == 1;
+                let branch = self.state.branch[h];
+                let (sibling, is_right_child, hashes) = if is_insert {
+                    (branch, true, has_node && bit)
+                } else if bit {
+                    (branch, true, true)
+                } else {
+                    (zero_hashes[h], false, true)
+                };
+                assert!(!(is_insert && hashes && h == DEPTH - 1), "the tree is full");
+
+                let mut level = Level {
+                    has_node,
+                    node,
+                    sibling,
+                    is_right_child,
+                    hashes,
+                    output: node,
+                    new_branch: if has_node && !hashes { node } else { branch },
+                };
+                if hashes {
+                    let (left, right) = level.pair();
+                    level.output = hasher.compress([left, right]);
+                }
+
+                has_node = hashes;
+                node = level.output;
+                level
+            })
+            .collect()
+    }
+
+    /// The state after the operation, which only an insert changes.
+    pub fn next_state<Compress>(
+        &self,
+        hasher: &Compress,
+    ) -> IncrementalTreeState<DEPTH, DIGEST_WIDTH>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        match self.kind {
+            IncrementalTreeOpKind::Insert { .. } => IncrementalTreeState {
+                count: self.state.count + 1,
+                branch: self
+                    .levels(hasher)
+                    .iter()
+                    .map(|level| level.new_branch)
+                    .collect_vec()
+                    .try_into()
+                    .unwrap(),
+            },
+            IncrementalTreeOpKind::Root => self.state,
+        }
+    }
+
+    /// Returns the sponge operations of the hashes, in the order of their ids.
+    pub fn sponge_ops<Compress>(&self, hasher: &Compress) -> Vec<KeccakSpongeOp>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        self.levels(hasher)
+            .iter()
+            .filter(|level| level.hashes)
+            .map(|level| {
+                let (left, right) = level.pair();
+                KeccakSpongeOp {
+                    input: left.into_iter().chain(right).collect(),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the memory accesses of the operation: the slots are read at
+    /// `timestamp`, along with the inserted leaf, and an insert writes the slots, and
+    /// a root the root, at `timestamp + 1`.
+    pub fn memory_ops<Compress>(&self, hasher: &Compress) -> Vec<MemoryOp<MEMORY_WORD_BYTES>>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let words = |addr: u32, bytes: &[u8], timestamp: u32, kind: OperationKind| {
+            (0..bytes.len() / MEMORY_WORD_BYTES)
+                .map(|k| MemoryOp {
+                    addr: addr + k as u32,
+                    timestamp,
+                    value: image_word(bytes, k),
+                    kind: kind.clone(),
+                })
+                .collect_vec()
+        };
+
+        let mut ops = words(
+            self.tree_addr,
+            &self.state.image(),
+            self.timestamp,
+            OperationKind::Read,
+        );
+        match &self.kind {
+            IncrementalTreeOpKind::Insert { leaf } => {
+                ops.extend(words(self.addr, leaf, self.timestamp, OperationKind::Read));
+                ops.extend(words(
+                    self.tree_addr,
+                    &self.next_state(hasher).image(),
+                    self.timestamp + 1,
+                    OperationKind::Write,
+                ));
+            }
+            IncrementalTreeOpKind::Root => ops.extend(words(
+                self.addr,
+                &self.state.root(hasher),
+                self.timestamp + 1,
+                OperationKind::Write,
+            )),
+        }
+        ops
+    }
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> IncrementalMerkleTreeChip<DEPTH, DIGEST_WIDTH> {
+    #[instrument(name = "generate IncrementalMerkleTree trace", skip_all)]
+    pub fn generate_trace<F, Compress>(
+        operations: &[IncrementalTreeOp<DEPTH, DIGEST_WIDTH>],
+        hasher: &Compress,
+    ) -> RowMajorMatrix<F>
+    where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let num_cols = IncrementalMerkleTreeCols::<F, DEPTH, DIGEST_WIDTH>::num_cols();
+
+        let num_real_rows = operations.len() * DEPTH;
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<IncrementalMerkleTreeCols<F, DEPTH, DIGEST_WIDTH>>()
+        };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        // The step flags cycle through the padding rows too, which are otherwise zero.
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.step_flags.flags[i % DEPTH] = F::one();
+        }
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations, hasher);
+
+        trace
+    }
+
+    pub fn populate_rows_for_ops<F, Compress>(
+        rows: &mut [&mut IncrementalMerkleTreeCols<F, DEPTH, DIGEST_WIDTH>],
+        ops: &[IncrementalTreeOp<DEPTH, DIGEST_WIDTH>],
+        hasher: &Compress,
+    ) where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        for (op_rows, op) in rows.chunks_mut(DEPTH).zip(ops.iter()) {
+            Self::populate_rows_for_op(op_rows, op, hasher);
+        }
+    }
+
+    pub fn populate_rows_for_op<F, Compress>(
+        rows: &mut [&mut IncrementalMerkleTreeCols<F, DEPTH, DIGEST_WIDTH>],
+        op: &IncrementalTreeOp<DEPTH, DIGEST_WIDTH>,
+        hasher: &Compress,
+    ) where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let bytes = |bytes: [u8; DIGEST_WIDTH]| bytes.map(F::from_canonical_u8);
+        let is_insert = matches!(op.kind, IncrementalTreeOpKind::Insert { .. });
+
+        let mut hash_id = op.first_hash_id;
+        for (h, (row, level)) in rows.iter_mut().zip(op.levels(hasher)).enumerate() {
+            let bit = (op.state.count >> h) & 1 == 1;
+            let (left, right) = level.pair();
+
+            row.is_real = F::one();
+            row.is_insert = F::from_bool(is_insert);
+            row.is_root = F::from_bool(!is_insert);
+            row.timestamp = F::from_canonical_u32(op.timestamp);
+            row.tree_addr = F::from_canonical_u32(op.tree_addr);
+            row.addr = F::from_canonical_u32(op.addr);
+            row.reads_node = F::from_bool(h == 0 && is_insert);
+            row.writes_root = F::from_bool(h == DEPTH - 1 && !is_insert);
+            row.has_node = F::from_bool(level.has_node);
+            row.node = bytes(level.node);
+            row.bit = F::from_bool(bit);
+            row.branch = bytes(op.state.branch[h]);
+            row.new_bit = F::from_bool(bit ^ (is_insert && level.has_node));
+            row.new_branch = bytes(level.new_branch);
+            row.sibling = bytes(level.sibling);
+            row.is_right_child = F::from_bool(level.is_right_child);
+            row.hashes = F::from_bool(level.hashes);
+            row.hash_id = F::from_canonical_usize(hash_id);
+            row.left = bytes(left);
+            row.right = bytes(right);
+            row.output = bytes(level.output);
+
+            hash_id += level.hashes as usize;
+        }
+    }
+}
@@ -7,19 +7,19 @@ use super::columns::KeccakPermuteCols;
 use super::KeccakPermuteChip;
 use crate::airs::keccak::{KeccakAir, NUM_ROUNDS};
 
-impl<F> BaseAir<F> for KeccakPermuteChip {
+impl<F, const U64_LIMBS: usize> BaseAir<F> for KeccakPermuteChip<U64_LIMBS> {
     fn width(&self) -> usize {
-        KeccakPermuteCols::<F>::num_cols()
+        KeccakPermuteCols::<F, U64_LIMBS>::num_cols()
     }
 }
 
-impl<AB: AirBuilder> Air<AB> for KeccakPermuteChip {
+impl<AB: AirBuilder, const U64_LIMBS: usize> Air<AB> for KeccakPermuteChip<U64_LIMBS> {
     fn eval(&self, builder: &mut AB) {
         let main = builder.main();
         let local = main.row_slice(0);
-        let local: &KeccakPermuteCols<AB::Var> = (*local).borrow();
+        let local: &KeccakPermuteCols<AB::Var, U64_LIMBS> = (*local).borrow();
 
-        let col_map = KeccakPermuteCols::<AB::Var>::col_map();
+        let col_map = KeccakPermuteCols::<AB::Var, U64_LIMBS>::col_map();
 
         builder.assert_bool(local.is_real);
         builder.assert_eq(
@@ -31,7 +31,7 @@ impl<AB: AirBuilder> Air<AB> for KeccakPermuteChip {
             local.is_real_output,
         );
 
-        let keccak_air = KeccakAir {};
+        let keccak_air = KeccakAir::<U64_LIMBS> {};
         let mut sub_builder = SubRangeAirBuilder::new_main(builder, col_map.keccak.as_range());
         keccak_air.eval(&mut sub_builder);
     }
@@ -4,8 +4,8 @@ use crate::airs::keccak::KeccakCols;
 
 #[repr(C)]
 #[derive(Columnar)]
-pub struct KeccakPermuteCols<T> {
-    pub keccak: KeccakCols<T>,
+pub struct KeccakPermuteCols<T, const U64_LIMBS: usize> {
+    pub keccak: KeccakCols<T, U64_LIMBS>,
 
     pub is_real: T,
 
@@ -3,15 +3,14 @@ use p3_field::Field;
 use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
 
 use super::{columns::KeccakPermuteCols, KeccakPermuteChip};
-use crate::airs::keccak::U64_LIMBS;
 
-impl<F: Field> BaseInteractionAir<F> for KeccakPermuteChip {
+impl<F: Field, const U64_LIMBS: usize> BaseInteractionAir<F> for KeccakPermuteChip<U64_LIMBS> {
     fn receives_from_indices(
         &self,
         _preprocessed_indices: &[usize],
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
-        let col_map = KeccakPermuteCols::from_slice(main_indices);
+        let col_map = KeccakPermuteCols::<_, U64_LIMBS>::from_slice(main_indices);
 
         vec![Interaction {
             fields: col_map
@@ -32,7 +31,7 @@ impl<F: Field> BaseInteractionAir<F> for KeccakPermuteChip {
         _preprocessed_indices: &[usize],
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
-        let col_map = KeccakPermuteCols::from_slice(main_indices);
+        let col_map = KeccakPermuteCols::<_, U64_LIMBS>::from_slice(main_indices);
 
         vec![Interaction {
             fields: (0..25)
@@ -53,16 +52,16 @@ impl<F: Field> BaseInteractionAir<F> for KeccakPermuteChip {
     }
 }
 
-impl<F: Field> InteractionAir<F> for KeccakPermuteChip {
+impl<F: Field, const U64_LIMBS: usize> InteractionAir<F> for KeccakPermuteChip<U64_LIMBS> {
     fn receives(&self) -> Vec<Interaction<F>> {
-        let col_map = KeccakPermuteCols::<F>::col_map();
+        let col_map = KeccakPermuteCols::<F, U64_LIMBS>::col_map();
         self.receives_from_main_indices(col_map.as_slice())
     }
 
     fn sends(&self) -> Vec<Interaction<F>> {
-        let col_map = KeccakPermuteCols::<F>::col_map();
+        let col_map = KeccakPermuteCols::<F, U64_LIMBS>::col_map();
         self.sends_from_main_indices(col_map.as_slice())
     }
 }
 
-impl<AB: InteractionAirBuilder> Rap<AB> for KeccakPermuteChip {}
+impl<AB: InteractionAirBuilder, const U64_LIMBS: usize> Rap<AB> for KeccakPermuteChip<U64_LIMBS> {}
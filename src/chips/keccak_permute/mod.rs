@@ -5,48 +5,131 @@ pub mod trace;
 
 pub const NUM_U64_HASH_ELEMS: usize = 4;
 
-/// Assumes the field size is at least 16 bits.
+/// Splits each lane into `U64_LIMBS` limbs, which must fit in the field. Use
+/// [`u64_limbs`](crate::airs::keccak::u64_limbs) to get the widest limbs for a field:
+/// 4 limbs on BabyBear and Mersenne31, and 2 limbs on Goldilocks.
 #[derive(Clone, Debug)]
-pub struct KeccakPermuteChip {
+pub struct KeccakPermuteChip<const U64_LIMBS: usize> {
     pub bus_input: usize,
     pub bus_output: usize,
 }
 
 #[cfg(feature = "air-logger")]
-impl p3_air_util::AirLogger for KeccakPermuteChip {
+impl<const U64_LIMBS: usize> p3_air_util::AirLogger for KeccakPermuteChip<U64_LIMBS> {
     fn main_headers(&self) -> Vec<String> {
-        self::columns::KeccakPermuteCols::<usize>::headers()
+        self::columns::KeccakPermuteCols::<usize, U64_LIMBS>::headers()
     }
 
     #[cfg(feature = "schema")]
     fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
-        self::columns::KeccakPermuteCols::<usize>::headers_and_types()
+        self::columns::KeccakPermuteCols::<usize, U64_LIMBS>::headers_and_types()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::prove_and_verify;
+    use crate::{
+        airs::keccak::{u64_limbs, NUM_ROUNDS},
+        config::{StarkConfigBuilder, Val},
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::KeccakPermuteCols;
 
     use itertools::Itertools;
-    use p3_uni_stark::VerificationError;
-    use rand::random;
+    use p3_field::Field;
+    use p3_goldilocks::Goldilocks;
+    use p3_uni_stark::{prove, verify, VerificationError};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
     use trace::KeccakPermuteOp;
 
+    fn random_ops(num_perms: usize, seed: u64) -> Vec<KeccakPermuteOp> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..num_perms)
+            .map(|_| KeccakPermuteOp { input: rng.gen() })
+            .collect_vec()
+    }
+
     #[test]
     fn test_keccak_permute_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
         const NUM_PERMS: usize = 10;
+        const U64_LIMBS: usize = u64_limbs(31);
 
-        let chip = KeccakPermuteChip {
+        let chip = KeccakPermuteChip::<U64_LIMBS> {
             bus_input: 0,
             bus_output: 0,
         };
-        let inputs = (0..NUM_PERMS)
-            .map(|_| KeccakPermuteOp { input: random() })
-            .collect_vec();
-        let trace = KeccakPermuteChip::generate_trace(inputs);
+        let trace =
+            KeccakPermuteChip::<U64_LIMBS>::generate_trace(random_ops(NUM_PERMS, RANDOM_SEED));
 
         prove_and_verify(&chip, trace, vec![])
     }
+
+    #[test]
+    fn test_keccak_permute_prove_goldilocks() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_PERMS: usize = 10;
+        const U64_LIMBS: usize = u64_limbs(64);
+        assert_eq!(U64_LIMBS, 2);
+
+        let chip = KeccakPermuteChip::<U64_LIMBS> {
+            bus_input: 0,
+            bus_output: 0,
+        };
+        let ops = random_ops(NUM_PERMS, RANDOM_SEED);
+        let trace = KeccakPermuteChip::<U64_LIMBS>::generate_trace::<Goldilocks>(ops);
+
+        let (config, challenger) = StarkConfigBuilder::new().goldilocks();
+        let public_values = vec![];
+        let proof = prove(
+            &config,
+            &chip,
+            &mut challenger.clone(),
+            trace,
+            &public_values,
+        );
+        verify(
+            &config,
+            &chip,
+            &mut challenger.clone(),
+            &proof,
+            &public_values,
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "don't fit")]
+    fn test_keccak_permute_limbs_too_wide() {
+        const RANDOM_SEED: u64 = 0;
+
+        assert_eq!(u64_limbs(Val::bits()), 4);
+
+        let chip = KeccakPermuteChip::<2> {
+            bus_input: 0,
+            bus_output: 0,
+        };
+        let trace = KeccakPermuteChip::<2>::generate_trace(random_ops(1, RANDOM_SEED));
+
+        let _ = prove_and_verify(&chip, trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_permute_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+        const U64_LIMBS: usize = u64_limbs(31);
+
+        let chip = KeccakPermuteChip::<U64_LIMBS> {
+            bus_input: 0,
+            bus_output: 1,
+        };
+        let trace = KeccakPermuteChip::<U64_LIMBS>::generate_trace(random_ops(1, RANDOM_SEED));
+
+        let cols = KeccakPermuteCols::<usize, U64_LIMBS>::col_map()
+            .as_slice()
+            .to_vec();
+        let mutations = random_mutations(0..NUM_ROUNDS, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
 }
@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
 use p3_matrix::dense::RowMajorMatrix;
 use tracing::instrument;
 
@@ -7,19 +7,23 @@ use super::columns::KeccakPermuteCols;
 use super::KeccakPermuteChip;
 use crate::airs::keccak::{generate_trace_rows_for_perm, NUM_ROUNDS};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct KeccakPermuteOp {
     pub input: [u64; 25],
 }
 
-impl KeccakPermuteChip {
+impl<const U64_LIMBS: usize> KeccakPermuteChip<U64_LIMBS> {
     #[instrument(name = "generate KeccakPermute trace", skip_all)]
-    pub fn generate_trace<F: PrimeField32>(ops: Vec<KeccakPermuteOp>) -> RowMajorMatrix<F> {
-        let num_cols = KeccakPermuteCols::<F>::num_cols();
+    pub fn generate_trace<F: PrimeField64>(ops: Vec<KeccakPermuteOp>) -> RowMajorMatrix<F> {
+        let num_cols = KeccakPermuteCols::<F, U64_LIMBS>::num_cols();
         let num_real_rows = ops.len() * NUM_ROUNDS;
         let num_rows = num_real_rows.next_power_of_two();
         let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
-        let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<KeccakPermuteCols<F>>() };
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<KeccakPermuteCols<F, U64_LIMBS>>()
+        };
         assert!(prefix.is_empty(), "Alignment should match");
         assert!(suffix.is_empty(), "Alignment should match");
         assert_eq!(rows.len(), num_rows);
@@ -39,8 +43,8 @@ impl KeccakPermuteChip {
         trace
     }
 
-    pub fn populate_rows_for_ops<F: PrimeField32>(
-        rows: &mut [&mut KeccakPermuteCols<F>],
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut KeccakPermuteCols<F, U64_LIMBS>],
         ops: &[KeccakPermuteOp],
     ) {
         for (op, rows) in ops.iter().zip(rows.chunks_mut(NUM_ROUNDS)) {
@@ -48,8 +52,8 @@ impl KeccakPermuteChip {
         }
     }
 
-    pub fn populate_rows_for_op<F: PrimeField32>(
-        rows: &mut [&mut KeccakPermuteCols<F>],
+    pub fn populate_rows_for_op<F: PrimeField64>(
+        rows: &mut [&mut KeccakPermuteCols<F, U64_LIMBS>],
         op: &KeccakPermuteOp,
     ) {
         debug_assert!(rows.len() == NUM_ROUNDS, "Exptected {NUM_ROUNDS} rows");
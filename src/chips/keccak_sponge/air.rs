@@ -3,7 +3,9 @@ use p3_air::{Air, AirBuilder, BaseAir};
 use p3_field::AbstractField;
 use p3_matrix::Matrix;
 
-use super::columns::{KeccakSpongeCols, KECCAK_DIGEST_U16S, KECCAK_RATE_BYTES, KECCAK_RATE_U16S};
+use super::columns::{
+    KeccakSpongeCols, KECCAK_CHUNK_BYTES, KECCAK_DIGEST_U16S, KECCAK_RATE_BYTES, KECCAK_RATE_U16S,
+};
 use super::KeccakSpongeChip;
 
 impl<F> BaseAir<F> for KeccakSpongeChip {
@@ -146,16 +148,61 @@ impl<AB: AirBuilder> Air<AB> for KeccakSpongeChip {
                 AB::Expr::from_canonical_u8(0b10000000),
             );
 
-        // TODO: Add back
-        // // A dummy row is always followed by another dummy row, so the prover can't put
-        // // dummy rows "in between" to avoid the above checks.
-        // let is_dummy = AB::Expr::one() - is_full_input_block - is_final_block;
-        // let next_is_final_block = next
-        //     .is_final_input_len
-        //     .iter()
-        //     .fold(AB::Expr::zero(), |acc, &is_final_len| acc + is_final_len);
-        // builder
-        //     .when(is_dummy)
-        //     .assert_zero(next.is_full_input_block + next_is_final_block);
+        // A row is real iff it is either a full-input block or a final block.
+        builder.assert_bool(local.is_real);
+        builder.assert_eq(local.is_real, is_full_input_block + is_final_block);
+
+        // A dummy row is always followed by another dummy row, so the prover can't put
+        // dummy rows "in between" to avoid the above checks.
+        builder
+            .when_transition()
+            .when_ne(local.is_real, AB::Expr::one())
+            .assert_zero(next.is_real);
+
+        // A full-input block is always followed by the next block of the same input,
+        // so the last row can't be a full-input block.
+        builder
+            .when_transition()
+            .when(is_full_input_block)
+            .assert_one(next.is_real);
+        builder.when_last_row().assert_zero(is_full_input_block);
+
+        // Operations are numbered consecutively from 0, which makes their ids unique.
+        builder.when_first_row().assert_zero(local.id);
+        builder
+            .when_transition()
+            .when(is_full_input_block)
+            .assert_eq(next.id, local.id);
+        builder
+            .when_transition()
+            .when(is_final_block)
+            .when(next.is_real)
+            .assert_eq(next.id, local.id + AB::Expr::one());
+
+        // Only real rows can access memory.
+        builder.assert_bool(local.is_memory_op);
+        builder.when(local.is_memory_op).assert_one(local.is_real);
+        for (i, &is_memory_word) in local.is_memory_word.iter().enumerate() {
+            builder.assert_eq(
+                is_memory_word,
+                local.is_memory_op
+                    * (AB::Expr::one() - local.is_padding_byte[i * KECCAK_CHUNK_BYTES]),
+            );
+        }
+        builder.assert_eq(local.writes_digest, local.is_memory_op * is_final_block);
+
+        // All blocks of an operation read from the same memory region at the same
+        // timestamp, and write the digest to the same destination.
+        for (local_col, next_col) in [
+            (local.is_memory_op, next.is_memory_op),
+            (local.timestamp, next.timestamp),
+            (local.base_addr, next.base_addr),
+            (local.dst_addr, next.dst_addr),
+        ] {
+            builder
+                .when_transition()
+                .when(is_full_input_block)
+                .assert_eq(next_col, local_col);
+        }
     }
 }
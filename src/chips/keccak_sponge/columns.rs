@@ -1,6 +1,8 @@
 // TODO: Ideally separate padding into different air
 use p3_derive::Columnar;
 
+use crate::chips::MEMORY_WORD_BYTES;
+
 /// Total number of sponge bytes: number of rate bytes + number of capacity
 /// bytes.
 pub(crate) const KECCAK_WIDTH_BYTES: usize = 200;
@@ -21,14 +23,39 @@ pub(crate) const KECCAK_CAPACITY_U16S: usize = KECCAK_CAPACITY_BYTES / 2;
 pub(crate) const KECCAK_DIGEST_BYTES: usize = 32;
 /// Number of 16-bit digest limbs.
 pub(crate) const KECCAK_DIGEST_U16S: usize = KECCAK_DIGEST_BYTES / 2;
+/// Number of input bytes carried by a single message on the sponge input bus, or
+/// read from memory as a single word.
+pub const KECCAK_CHUNK_BYTES: usize = MEMORY_WORD_BYTES;
+/// Number of input chunks in a block.
+pub(crate) const KECCAK_RATE_CHUNKS: usize = KECCAK_RATE_BYTES / KECCAK_CHUNK_BYTES;
 
 #[repr(C)]
 #[derive(Columnar)]
 pub struct KeccakSpongeCols<T> {
+    /// Timestamp at which a memory operation reads its input. The digest is written
+    /// back at the next timestamp.
     pub timestamp: T,
 
+    /// Word address of the first input word of a memory operation.
     pub base_addr: T,
 
+    /// Word address the digest of a memory operation is written to.
+    pub dst_addr: T,
+
+    /// Index of the sponge operation this block belongs to. Operations are numbered
+    /// consecutively from 0, so other chips can refer to an operation by its id on
+    /// the input and output buses.
+    pub id: T,
+
+    /// 1 if this row absorbs a block of some sponge operation, 0 if it is a
+    /// dummy padding row. Always equal to `is_full_input_block` plus the final
+    /// block flag.
+    pub is_real: T,
+
+    /// 1 if the input of this operation is read from memory and its digest written
+    /// back to memory, 0 if they are carried over the sponge input and output buses.
+    pub is_memory_op: T,
+
     /// 1 if this row represents a full input block, i.e. one in which each byte
     /// is an input byte, not a padding byte; 0 otherwise.
     pub is_full_input_block: T,
@@ -42,6 +69,14 @@ pub struct KeccakSpongeCols<T> {
     /// If this row represents a full input block, this should contain all 0s.
     pub is_padding_byte: [T; KECCAK_RATE_BYTES],
 
+    /// Whether the current chunk is read from memory as a single word, i.e.
+    /// `is_memory_op` times 1 if the chunk contains an input byte.
+    pub is_memory_word: [T; KECCAK_RATE_CHUNKS],
+
+    /// Whether the digest is written to memory, i.e. `is_memory_op` times the final
+    /// block flag.
+    pub writes_digest: T,
+
     /// The initial rate part of the sponge, at the start of this step.
     pub original_rate_u16s: [T; KECCAK_RATE_U16S],
 
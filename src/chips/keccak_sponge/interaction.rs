@@ -6,7 +6,7 @@ use p3_field::Field;
 use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
 
 use super::{
-    columns::{KeccakSpongeCols, KECCAK_DIGEST_BYTES, KECCAK_RATE_BYTES},
+    columns::{KeccakSpongeCols, KECCAK_CHUNK_BYTES, KECCAK_RATE_BYTES, KECCAK_RATE_CHUNKS},
     KeccakSpongeChip,
 };
 
@@ -18,54 +18,41 @@ impl<F: Field> BaseInteractionAir<F> for KeccakSpongeChip {
     ) -> Vec<Interaction<F>> {
         let col_map = KeccakSpongeCols::from_slice(main_indices);
 
-        let is_real = VirtualPairCol::sum_main(vec![
-            col_map.is_padding_byte[KECCAK_RATE_BYTES - 1],
-            col_map.is_full_input_block,
-        ]);
+        let is_real = VirtualPairCol::single_main(col_map.is_real);
         [
-            // (0..KECCAK_RATE_BYTES)
-            //     .map(|i| {
-            //         let is_real = if i == KECCAK_RATE_BYTES - 1 {
-            //             VirtualPairCol::single_main(col_map.is_full_input_block)
-            //         } else {
-            //             VirtualPairCol::new_main(
-            //                 vec![
-            //                     (col_map.is_full_input_block, F::one()),
-            //                     (col_map.is_padding_byte[KECCAK_RATE_BYTES - 1], F::one()),
-            //                     (col_map.is_padding_byte[i], -F::one()),
-            //                 ],
-            //                 F::zero(),
-            //             )
-            //         };
-            //         Interaction {
-            //             fields: vec![
-            //                 VirtualPairCol::single_main(col_map.timestamp),
-            //                 VirtualPairCol::new_main(
-            //                     vec![
-            //                         (col_map.base_addr, F::one()),
-            //                         (col_map.already_absorbed_bytes, F::one()),
-            //                     ],
-            //                     F::from_canonical_usize(i),
-            //                 ),
-            //                 VirtualPairCol::single_main(col_map.block_bytes[i]),
-            //             ],
-            //             count: is_real,
-            //             argument_index: self.bus_input,
-            //         }
-            //     })
-            //     .collect_vec(),
-            // TODO: Only send non padding bytes. Interaction field should be
-            //       is_padding_byte[i] * block_bytes[i] but requires degree 2 fields
-            vec![Interaction {
-                fields: once(VirtualPairCol::single_main(col_map.is_full_input_block))
-                    .chain(
-                        (0..KECCAK_RATE_BYTES)
-                            .map(|i| VirtualPairCol::single_main(col_map.block_bytes[i])),
-                    )
-                    .collect_vec(),
-                count: is_real.clone(),
-                argument_index: self.bus_input,
-            }],
+            (0..KECCAK_RATE_CHUNKS)
+                .map(|k| {
+                    let start = k * KECCAK_CHUNK_BYTES;
+                    let len = VirtualPairCol::new_main(
+                        (start..start + KECCAK_CHUNK_BYTES)
+                            .map(|i| (col_map.is_padding_byte[i], -F::one()))
+                            .collect(),
+                        F::from_canonical_usize(KECCAK_CHUNK_BYTES),
+                    );
+                    Interaction {
+                        fields: once(VirtualPairCol::single_main(col_map.id))
+                            .chain(once(VirtualPairCol::new_main(
+                                vec![(col_map.already_absorbed_bytes, F::one())],
+                                F::from_canonical_usize(start),
+                            )))
+                            .chain(chunk_input_bytes(&col_map, k))
+                            .chain(once(len))
+                            .collect(),
+                        // Padding bytes are contiguous, so a chunk contains input bytes
+                        // iff its first byte isn't a padding byte. Memory operations read
+                        // their input from memory instead.
+                        count: VirtualPairCol::new_main(
+                            vec![
+                                (col_map.is_real, F::one()),
+                                (col_map.is_padding_byte[start], -F::one()),
+                                (col_map.is_memory_word[k], -F::one()),
+                            ],
+                            F::zero(),
+                        ),
+                        argument_index: self.bus_input,
+                    }
+                })
+                .collect_vec(),
             col_map
                 .xored_rate_u16s
                 .into_iter()
@@ -99,6 +86,30 @@ impl<F: Field> BaseInteractionAir<F> for KeccakSpongeChip {
                 count: is_real.clone(),
                 argument_index: self.bus_permute_output,
             }],
+            // Memory operations only run when called, which fixes the region they read
+            // and where the digest goes. The call is received on the final block, where
+            // the input length is the bytes absorbed so far plus the input bytes of the
+            // block, which are the ones that aren't padding.
+            vec![Interaction {
+                fields: vec![
+                    VirtualPairCol::single_main(col_map.timestamp),
+                    VirtualPairCol::single_main(col_map.base_addr),
+                    VirtualPairCol::new_main(
+                        once((col_map.already_absorbed_bytes, F::one()))
+                            .chain(
+                                col_map
+                                    .is_padding_byte
+                                    .into_iter()
+                                    .map(|is_padding_byte| (is_padding_byte, -F::one())),
+                            )
+                            .collect(),
+                        F::from_canonical_usize(KECCAK_RATE_BYTES),
+                    ),
+                    VirtualPairCol::single_main(col_map.dst_addr),
+                ],
+                count: VirtualPairCol::single_main(col_map.writes_digest),
+                argument_index: self.bus_call,
+            }],
         ]
         .concat()
     }
@@ -110,10 +121,7 @@ impl<F: Field> BaseInteractionAir<F> for KeccakSpongeChip {
     ) -> Vec<Interaction<F>> {
         let col_map = KeccakSpongeCols::from_slice(main_indices);
 
-        let is_real = VirtualPairCol::sum_main(vec![
-            col_map.is_padding_byte[KECCAK_RATE_BYTES - 1],
-            col_map.is_full_input_block,
-        ]);
+        let is_real = VirtualPairCol::single_main(col_map.is_real);
         [
             col_map
                 .block_bytes
@@ -147,17 +155,109 @@ impl<F: Field> BaseInteractionAir<F> for KeccakSpongeChip {
                 argument_index: self.bus_permute_input,
             }],
             vec![Interaction {
-                fields: (0..KECCAK_DIGEST_BYTES)
-                    .map(|i| VirtualPairCol::single_main(col_map.updated_digest_state_bytes[i]))
+                fields: once(col_map.id)
+                    .chain(col_map.updated_digest_state_bytes)
+                    .map(VirtualPairCol::single_main)
                     .collect_vec(),
-                count: is_real.clone(),
+                count: VirtualPairCol::new_main(
+                    vec![
+                        (col_map.is_padding_byte[KECCAK_RATE_BYTES - 1], F::one()),
+                        (col_map.writes_digest, -F::one()),
+                    ],
+                    F::zero(),
+                ),
                 argument_index: self.bus_output,
             }],
+            // Memory operations read the chunk `k` of a block as the word at
+            // `base_addr + already_absorbed_bytes / KECCAK_CHUNK_BYTES + k`, where
+            // `already_absorbed_bytes` is a multiple of `KECCAK_CHUNK_BYTES`. The bytes
+            // following the input in its last word must be zero.
+            (0..KECCAK_RATE_CHUNKS)
+                .map(|k| Interaction {
+                    fields: once(VirtualPairCol::single_main(col_map.timestamp))
+                        .chain(once(VirtualPairCol::new_main(
+                            vec![
+                                (col_map.base_addr, F::one()),
+                                (
+                                    col_map.already_absorbed_bytes,
+                                    F::from_canonical_usize(KECCAK_CHUNK_BYTES).inverse(),
+                                ),
+                            ],
+                            F::from_canonical_usize(k),
+                        )))
+                        .chain(chunk_input_bytes(&col_map, k))
+                        .chain(once(VirtualPairCol::constant(F::zero())))
+                        .collect(),
+                    count: VirtualPairCol::single_main(col_map.is_memory_word[k]),
+                    argument_index: self.bus_memory,
+                })
+                .collect_vec(),
+            // ... and write the digest to `dst_addr` at the next timestamp, so that it can
+            // overwrite the input.
+            col_map
+                .updated_digest_state_bytes
+                .chunks(KECCAK_CHUNK_BYTES)
+                .enumerate()
+                .map(|(k, word)| Interaction {
+                    fields: once(VirtualPairCol::new_main(
+                        vec![(col_map.timestamp, F::one())],
+                        F::one(),
+                    ))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(col_map.dst_addr, F::one())],
+                        F::from_canonical_usize(k),
+                    )))
+                    .chain(word.iter().map(|&byte| VirtualPairCol::single_main(byte)))
+                    .chain(once(VirtualPairCol::constant(F::one())))
+                    .collect(),
+                    count: VirtualPairCol::single_main(col_map.writes_digest),
+                    argument_index: self.bus_memory,
+                })
+                .collect_vec(),
+            // The bytes are only ever recombined into 16-bit limbs on the buses, so each
+            // of them has to be range checked on its own. The 16-bit limbs themselves are
+            // either zero, copied from the previous row or matched against the xor and
+            // permutation outputs, which are built from bits.
+            col_map
+                .block_bytes
+                .into_iter()
+                .chain(col_map.updated_digest_state_bytes)
+                .map(|byte| Interaction {
+                    fields: vec![VirtualPairCol::single_main(byte)],
+                    count: is_real.clone(),
+                    argument_index: self.bus_range_8,
+                })
+                .collect_vec(),
         ]
         .concat()
     }
 }
 
+/// The input bytes of the chunk `k` of a block. Padding bytes are fully determined
+/// by the padding flags, so we subtract them and only carry the input bytes, which
+/// are zero at padding positions.
+fn chunk_input_bytes<F: Field>(
+    col_map: &KeccakSpongeCols<usize>,
+    k: usize,
+) -> impl Iterator<Item = VirtualPairCol<F>> + '_ {
+    let start = k * KECCAK_CHUNK_BYTES;
+    (start..start + KECCAK_CHUNK_BYTES).map(|i| {
+        let mut column_weights = vec![(col_map.block_bytes[i], F::one())];
+        if i == KECCAK_RATE_BYTES - 1 {
+            column_weights.push((
+                col_map.is_padding_byte[i],
+                -F::from_canonical_u8(0b10000001),
+            ));
+        } else {
+            column_weights.push((col_map.is_padding_byte[i], -F::one()));
+        }
+        if i > 0 {
+            column_weights.push((col_map.is_padding_byte[i - 1], F::one()));
+        }
+        VirtualPairCol::new_main(column_weights, F::zero())
+    })
+}
+
 impl<F: Field> InteractionAir<F> for KeccakSpongeChip {
     fn receives(&self) -> Vec<Interaction<F>> {
         let col_map = KeccakSpongeCols::<F>::col_map();
@@ -14,6 +14,11 @@ pub struct KeccakSpongeChip {
 
     pub bus_permute_input: usize,
     pub bus_permute_output: usize,
+
+    pub bus_range_8: usize,
+
+    pub bus_memory: usize,
+    pub bus_call: usize,
 }
 
 #[cfg(feature = "air-logger")]
@@ -31,28 +36,385 @@ impl p3_air_util::AirLogger for KeccakSpongeChip {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::prove_and_verify;
+    use crate::{
+        bus::KeccakMachineBus,
+        chips::{
+            keccak_permute::KeccakPermuteChip, memory::MemoryChip, xor::XorChip, KECCAK_U64_LIMBS,
+        },
+        config::Val,
+        test_util::{
+            assert_constraints_hold, assert_mutations_rejected, assert_prove_and_verify_fails,
+            bus_messages, prove_and_verify, random_mutations,
+        },
+    };
 
+    use alloc::collections::BTreeMap;
+    use columns::{KeccakSpongeCols, KECCAK_CHUNK_BYTES, KECCAK_DIGEST_BYTES, KECCAK_RATE_BYTES};
     use itertools::Itertools;
+    use p3_field::{AbstractField, Field};
+    use p3_matrix::dense::RowMajorMatrix;
     use p3_uni_stark::VerificationError;
-    use rand::random;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use tiny_keccak::{Hasher, Keccak};
     use trace::KeccakSpongeOp;
 
+    fn generate_op(num_bytes: usize, is_memory_op: bool) -> KeccakSpongeOp {
+        let mut rng = StdRng::seed_from_u64(num_bytes as u64);
+        KeccakSpongeOp {
+            timestamp: 0,
+            addr: 0,
+            dst_addr: 0,
+            is_memory_op,
+            input: (0..num_bytes).map(|_| rng.gen()).collect_vec(),
+        }
+    }
+
+    fn generate_trace(num_bytes: usize) -> RowMajorMatrix<Val> {
+        KeccakSpongeChip::generate_trace(vec![generate_op(num_bytes, false)])
+    }
+
+    fn rows_mut(trace: &mut RowMajorMatrix<Val>) -> &mut [KeccakSpongeCols<Val>] {
+        let (prefix, rows, suffix) =
+            unsafe { trace.values.align_to_mut::<KeccakSpongeCols<Val>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        rows
+    }
+
     #[test]
     fn test_keccak_sponge_prove() -> Result<(), VerificationError> {
         const NUM_BYTES: usize = 400;
 
-        let op = KeccakSpongeOp {
-            timestamp: 0,
-            addr: 0,
-            input: (0..NUM_BYTES).map(|_| random()).collect_vec(),
+        let trace = generate_trace(NUM_BYTES);
+        let chip = KeccakSpongeChip {
+            ..Default::default()
         };
-        let inputs = vec![op];
-        let trace = KeccakSpongeChip::generate_trace(inputs);
+
+        prove_and_verify(&chip, trace, vec![])
+    }
+
+    #[test]
+    fn test_keccak_sponge_dummy_row_between_blocks() {
+        // Two full-input blocks and a final block, followed by a dummy row.
+        let mut trace = generate_trace(2 * KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows.swap(2, 3);
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_real_dummy_row() {
+        let mut trace = generate_trace(2 * KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows[3].is_real = Val::one();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_bad_padding_byte() {
+        let mut trace = generate_trace(KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows[1].block_bytes[64] = Val::two();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_non_contiguous_padding() {
+        let mut trace = generate_trace(KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows[1].is_padding_byte[65] = Val::zero();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_state_not_carried_over() {
+        let mut trace = generate_trace(KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows[1].original_capacity_u16s[0] += Val::one();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_wrong_absorbed_bytes() {
+        let mut trace = generate_trace(KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows[1].already_absorbed_bytes += Val::one();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_id_changes_within_op() {
+        let mut trace = generate_trace(KECCAK_RATE_BYTES + 64);
+        let rows = rows_mut(&mut trace);
+        rows[1].id = Val::one();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_memory_prove() -> Result<(), VerificationError> {
+        const NUM_BYTES: usize = 400;
+
+        let op = generate_op(NUM_BYTES, true);
+        let trace = KeccakSpongeChip::generate_trace(vec![op]);
         let chip = KeccakSpongeChip {
             ..Default::default()
         };
 
         prove_and_verify(&chip, trace, vec![])
     }
+
+    #[test]
+    fn test_keccak_sponge_memory_ops() -> Result<(), VerificationError> {
+        const NUM_BYTES: usize = 400;
+
+        // The digest overwrites the start of the input.
+        let op = generate_op(NUM_BYTES, true);
+        let memory_trace =
+            MemoryChip::<KECCAK_CHUNK_BYTES>::generate_trace(&op.input, op.memory_ops());
+
+        prove_and_verify(
+            &MemoryChip::<KECCAK_CHUNK_BYTES>::default(),
+            memory_trace,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_keccak_sponge_memory_base_addr_changes_within_op() {
+        let op = generate_op(KECCAK_RATE_BYTES + 64, true);
+        let mut trace = KeccakSpongeChip::generate_trace(vec![op]);
+        let rows = rows_mut(&mut trace);
+        rows[1].base_addr += Val::one();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_memory_padding_word_read() {
+        let op = generate_op(KECCAK_RATE_BYTES + 64, true);
+        let mut trace = KeccakSpongeChip::generate_trace(vec![op]);
+        let rows = rows_mut(&mut trace);
+        rows[1].is_memory_word[64 / KECCAK_CHUNK_BYTES] = Val::one();
+
+        assert_prove_and_verify_fails(&KeccakSpongeChip::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_keccak_sponge_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        // Two full-input blocks and a final block.
+        let trace = generate_trace(2 * KECCAK_RATE_BYTES + 13);
+        let chip = KeccakSpongeChip {
+            ..Default::default()
+        };
+
+        let col_map = KeccakSpongeCols::<usize>::col_map();
+        let cols = [
+            col_map.block_bytes.as_slice(),
+            col_map.is_padding_byte.as_slice(),
+            col_map.updated_digest_state_bytes.as_slice(),
+            col_map.xored_rate_u16s.as_slice(),
+            &[col_map.id, col_map.already_absorbed_bytes],
+        ]
+        .concat();
+        let mutations = random_mutations(0..3, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+
+    fn machine_sponge_chip() -> KeccakSpongeChip {
+        KeccakSpongeChip {
+            bus_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_xor_input: KeccakMachineBus::XorInput as usize,
+            bus_xor_output: KeccakMachineBus::XorOutput as usize,
+            bus_permute_input: KeccakMachineBus::KeccakPermuteInput as usize,
+            bus_permute_output: KeccakMachineBus::KeccakPermuteOutput as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_call: KeccakMachineBus::SpongeCall as usize,
+        }
+    }
+
+    fn tiny_keccak_digest(input: &[u8]) -> [u8; KECCAK_DIGEST_BYTES] {
+        let mut keccak = Keccak::v256();
+        keccak.update(input);
+        let mut digest = [0; KECCAK_DIGEST_BYTES];
+        keccak.finalize(&mut digest);
+        digest
+    }
+
+    /// Hashes every input with the sponge chip, checks the constraints of its trace
+    /// and returns the digests it sends on its output bus, in order.
+    fn sponge_digests(
+        chip: &KeccakSpongeChip,
+        inputs: &[Vec<u8>],
+    ) -> (RowMajorMatrix<Val>, Vec<[u8; KECCAK_DIGEST_BYTES]>) {
+        let ops = inputs
+            .iter()
+            .map(|input| KeccakSpongeOp {
+                input: input.clone(),
+                ..Default::default()
+            })
+            .collect_vec();
+        let trace = KeccakSpongeChip::generate_trace(ops);
+        assert_constraints_hold(chip, &trace);
+
+        let digests = bus_messages(chip, &trace)
+            .into_iter()
+            .filter(|((bus, _), _)| *bus == chip.bus_output)
+            .map(|((_, fields), count)| {
+                assert_eq!(count, Val::one());
+                let (id, digest) = fields.split_first().unwrap();
+                let digest = digest.iter().map(|&byte| byte as u8).collect_vec();
+                (*id, digest.try_into().unwrap())
+            })
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            digests.keys().copied().collect_vec(),
+            (0..inputs.len() as u64).collect_vec()
+        );
+        (trace, digests.into_values().collect())
+    }
+
+    #[test]
+    fn test_keccak_sponge_matches_tiny_keccak() {
+        // Covers empty inputs, inputs filling the last block but one byte, and exact
+        // multiples of the rate, which take a whole padding block.
+        const MAX_NUM_BYTES: usize = 1000;
+        const RANDOM_SEED: u64 = 0;
+
+        let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+        let chip = machine_sponge_chip();
+        let inputs = (0..=MAX_NUM_BYTES)
+            .map(|num_bytes| (0..num_bytes).map(|_| rng.gen()).collect_vec())
+            .collect_vec();
+        let (_, digests) = sponge_digests(&chip, &inputs);
+
+        for (input, digest) in inputs.iter().zip(digests) {
+            assert_eq!(
+                digest,
+                tiny_keccak_digest(input),
+                "wrong digest for {} bytes",
+                input.len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_keccak_sponge_matches_permute_and_xor() {
+        const NUM_BYTES: [usize; 11] = [0, 1, 134, 135, 136, 137, 270, 271, 272, 273, 1000];
+        const RANDOM_SEED: u64 = 0;
+
+        let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+        let chip = machine_sponge_chip();
+        let inputs = NUM_BYTES
+            .iter()
+            .map(|&num_bytes| (0..num_bytes).map(|_| rng.gen()).collect_vec())
+            .collect_vec();
+        let (trace, _) = sponge_digests(&chip, &inputs);
+
+        let (xor_ops, permute_ops) = KeccakSpongeChip::generate_dependent_ops(&trace);
+        let xor_chip = XorChip::<2> {
+            bus_input: chip.bus_xor_input,
+            bus_output: chip.bus_xor_output,
+        };
+        let xor_trace = XorChip::<2>::generate_trace(xor_ops);
+        assert_constraints_hold(&xor_chip, &xor_trace);
+        let permute_chip = KeccakPermuteChip::<KECCAK_U64_LIMBS> {
+            bus_input: chip.bus_permute_input,
+            bus_output: chip.bus_permute_output,
+        };
+        let permute_trace = KeccakPermuteChip::<KECCAK_U64_LIMBS>::generate_trace(permute_ops);
+        assert_constraints_hold(&permute_chip, &permute_trace);
+
+        // The xor and permutation chips must produce exactly the outputs the sponge
+        // receives.
+        let mut messages = bus_messages(&chip, &trace);
+        for (key, count) in bus_messages(&xor_chip, &xor_trace)
+            .into_iter()
+            .chain(bus_messages(&permute_chip, &permute_trace))
+        {
+            *messages.entry(key).or_insert(Val::zero()) += count;
+        }
+        let unmatched = messages
+            .into_iter()
+            .filter(|((bus, _), count)| {
+                !count.is_zero()
+                    && [
+                        chip.bus_xor_input,
+                        chip.bus_xor_output,
+                        chip.bus_permute_input,
+                        chip.bus_permute_output,
+                    ]
+                    .contains(bus)
+            })
+            .collect_vec();
+        assert!(unmatched.is_empty(), "unmatched messages: {unmatched:?}");
+    }
+
+    /// Parses the byte-aligned entries of a `ShortMsgKAT`/`LongMsgKAT` file into
+    /// `(message, digest)` pairs.
+    fn parse_kat(kat: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let from_hex = |hex: &str| {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect_vec()
+        };
+
+        let mut entries = vec![];
+        let (mut len, mut msg) = (None, None);
+        for line in kat.lines() {
+            let Some((key, value)) = line.split_once(" = ") else {
+                continue;
+            };
+            match key.trim() {
+                "Len" => len = Some(value.trim().parse::<usize>().unwrap()),
+                "Msg" => msg = Some(from_hex(value.trim())),
+                "MD" => {
+                    let (len, msg) = (len.take().unwrap(), msg.take().unwrap());
+                    if len % 8 == 0 {
+                        entries.push((msg[..len / 8].to_vec(), from_hex(value.trim())));
+                    }
+                }
+                _ => {}
+            }
+        }
+        entries
+    }
+
+    fn assert_known_answers(kat: &str) {
+        let entries = parse_kat(kat);
+        assert!(!entries.is_empty());
+
+        let chip = machine_sponge_chip();
+        let inputs = entries.iter().map(|(msg, _)| msg.clone()).collect_vec();
+        let (_, digests) = sponge_digests(&chip, &inputs);
+
+        for ((msg, md), digest) in entries.iter().zip(digests) {
+            assert_eq!(&digest[..], md, "wrong digest for {} bytes", msg.len());
+            assert_eq!(digest, tiny_keccak_digest(msg));
+        }
+    }
+
+    #[test]
+    fn test_keccak_sponge_known_answers() {
+        assert_known_answers(include_str!("testdata/keccak_256_kat.txt"));
+    }
+
+    /// The rate boundaries aren't covered by the official short messages, so their
+    /// answers come from an independent implementation.
+    #[test]
+    fn test_keccak_sponge_rate_boundaries() {
+        assert_known_answers(include_str!("testdata/keccak_256_boundaries.txt"));
+    }
 }
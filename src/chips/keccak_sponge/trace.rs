@@ -1,27 +1,94 @@
+use alloc::collections::BTreeMap;
+use core::borrow::Borrow;
+
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
 use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
 use tracing::instrument;
 
 use super::{
     columns::{
-        KeccakSpongeCols, KECCAK_DIGEST_U16S, KECCAK_RATE_BYTES, KECCAK_RATE_U16S,
-        KECCAK_WIDTH_U16S,
+        KeccakSpongeCols, KECCAK_CHUNK_BYTES, KECCAK_DIGEST_BYTES, KECCAK_DIGEST_U16S,
+        KECCAK_RATE_BYTES, KECCAK_RATE_U16S, KECCAK_WIDTH_U16S,
     },
     util::keccakf_u16s,
     KeccakSpongeChip,
 };
+use crate::chips::{
+    keccak_permute::trace::KeccakPermuteOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    xor::trace::XorOp,
+};
 
 #[derive(Default, Clone)]
 pub struct KeccakSpongeOp {
     pub timestamp: u32,
     pub addr: u32,
+    pub dst_addr: u32,
+    /// Whether `input` is read from memory at the word address `addr` and the digest
+    /// written back to the word address `dst_addr`, rather than sent over the sponge
+    /// input and output buses.
+    pub is_memory_op: bool,
     pub input: Vec<u8>,
 }
 
+/// A memory operation of the sponge, as called by the `MemoryImageChip`: it reads
+/// the `len` input bytes at the word address `addr` at `timestamp`, and writes the
+/// digest to the word address `dst_addr`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpongeCall {
+    pub timestamp: u32,
+    pub addr: u32,
+    pub len: u32,
+    pub dst_addr: u32,
+}
+
+impl KeccakSpongeOp {
+    /// Returns the call of a memory operation, or `None` for an operation over the
+    /// sponge input and output buses.
+    pub fn call(&self) -> Option<SpongeCall> {
+        self.is_memory_op.then(|| SpongeCall {
+            timestamp: self.timestamp,
+            addr: self.addr,
+            len: self.input.len() as u32,
+            dst_addr: self.dst_addr,
+        })
+    }
+
+    /// Returns the memory accesses of a memory operation, whose addresses are word
+    /// addresses: the input is read at `timestamp`, zero-padded to a whole number of
+    /// words, and the digest is written at `timestamp + 1`.
+    pub fn memory_ops(&self) -> Vec<MemoryOp<KECCAK_CHUNK_BYTES>> {
+        if !self.is_memory_op {
+            return vec![];
+        }
+
+        let reads = self
+            .input
+            .chunks(KECCAK_CHUNK_BYTES)
+            .enumerate()
+            .map(|(k, chunk)| MemoryOp {
+                addr: self.addr + k as u32,
+                timestamp: self.timestamp,
+                value: image_word(chunk, 0),
+                kind: OperationKind::Read,
+            });
+        let digest = Keccak256Hash.hash_iter(self.input.iter().copied());
+        let writes = (0..KECCAK_DIGEST_BYTES / KECCAK_CHUNK_BYTES).map(|k| MemoryOp {
+            addr: self.dst_addr + k as u32,
+            timestamp: self.timestamp + 1,
+            value: image_word(&digest, k),
+            kind: OperationKind::Write,
+        });
+        reads.chain(writes).collect()
+    }
+}
+
 impl KeccakSpongeChip {
     #[instrument(name = "generate KeccakSponge trace", skip_all)]
-    pub fn generate_trace<F: PrimeField32>(inputs: Vec<KeccakSpongeOp>) -> RowMajorMatrix<F> {
+    pub fn generate_trace<F: PrimeField64>(inputs: Vec<KeccakSpongeOp>) -> RowMajorMatrix<F> {
         let num_cols = KeccakSpongeCols::<F>::num_cols();
         let num_real_rows = inputs
             .iter()
@@ -38,24 +105,84 @@ impl KeccakSpongeChip {
         let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
         Self::populate_rows_for_ops(&mut real_rows, &inputs);
 
-        // Pad the trace.
-        for row in rows.chunks_mut(1).skip(num_real_rows) {
-            let mut row_ref = row.iter_mut().collect_vec();
-            Self::populate_rows_for_op(&mut row_ref, &KeccakSpongeOp::default());
+        // The remaining rows are dummy rows and are left zeroed.
+        trace
+    }
+
+    /// Counts the bytes that the real rows of a sponge trace send to the 8-bit range
+    /// bus.
+    pub fn generate_range_counts<F: PrimeField64>(trace: &RowMajorMatrix<F>) -> BTreeMap<u32, u32> {
+        let mut count = BTreeMap::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &KeccakSpongeCols<F> = row.borrow();
+            if row.is_real.is_zero() {
+                continue;
+            }
+            for byte in row
+                .block_bytes
+                .iter()
+                .chain(row.updated_digest_state_bytes.iter())
+            {
+                count
+                    .entry(byte.as_canonical_u64() as u32)
+                    .and_modify(|c| *c += 1)
+                    .or_insert(1);
+            }
         }
+        count
+    }
 
-        trace
+    /// Collects the xor and permutation operations that the real rows of a sponge
+    /// trace send to the xor and permutation buses.
+    pub fn generate_dependent_ops<F: PrimeField64>(
+        trace: &RowMajorMatrix<F>,
+    ) -> (Vec<XorOp>, Vec<KeccakPermuteOp>) {
+        let mut xor_ops = Vec::new();
+        let mut permute_ops = Vec::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &KeccakSpongeCols<F> = row.borrow();
+            if row.is_real.is_zero() {
+                continue;
+            }
+
+            for (block_bytes, rate_limb) in row
+                .block_bytes
+                .chunks_exact(2)
+                .zip(row.original_rate_u16s.iter())
+            {
+                xor_ops.push(XorOp {
+                    input1: block_bytes[0].as_canonical_u64() as u16
+                        + ((block_bytes[1].as_canonical_u64() as u16) << 8),
+                    input2: rate_limb.as_canonical_u64() as u16,
+                });
+            }
+
+            let state_u16s = row
+                .xored_rate_u16s
+                .iter()
+                .chain(row.original_capacity_u16s.iter())
+                .map(|x| x.as_canonical_u64())
+                .collect_vec();
+            let input = state_u16s
+                .chunks_exact(4)
+                .map(|limbs| limbs.iter().rev().fold(0, |acc, &x| (acc << 16) | x))
+                .collect_vec()
+                .try_into()
+                .unwrap();
+            permute_ops.push(KeccakPermuteOp { input });
+        }
+        (xor_ops, permute_ops)
     }
 
-    pub fn populate_rows_for_ops<F: PrimeField32>(
+    pub fn populate_rows_for_ops<F: PrimeField64>(
         rows: &mut [&mut KeccakSpongeCols<F>],
         ops: &[KeccakSpongeOp],
     ) {
         let mut offset = 0;
-        for op in ops.iter() {
+        for (id, op) in ops.iter().enumerate() {
             let len = op.input.len() / KECCAK_RATE_BYTES + 1;
             let input_rows = &mut rows[offset..offset + len];
-            Self::populate_rows_for_op(input_rows, op);
+            Self::populate_rows_for_op(input_rows, id, op);
             offset += len;
         }
     }
@@ -63,25 +190,22 @@ impl KeccakSpongeChip {
     /// Generates the rows associated to a given operation:
     /// Performs a Keccak sponge permutation and fills the STARK's rows
     /// accordingly. The number of rows is the number of input chunks of
-    /// size `KECCAK_RATE_BYTES`.
-    pub fn populate_rows_for_op<F: PrimeField32>(
+    /// size `KECCAK_RATE_BYTES`. `id` is the index of the operation in the trace.
+    pub fn populate_rows_for_op<F: PrimeField64>(
         rows: &mut [&mut KeccakSpongeCols<F>],
+        id: usize,
         op: &KeccakSpongeOp,
     ) {
         let mut sponge_state = [0u16; KECCAK_WIDTH_U16S];
 
-        let KeccakSpongeOp {
-            addr: _,
-            timestamp: _,
-            input,
-        } = op;
-
+        let input = &op.input;
         let mut input_blocks = input.chunks_exact(KECCAK_RATE_BYTES);
         let mut already_absorbed_bytes = 0;
         for (row, block) in rows.iter_mut().zip(input_blocks.by_ref()) {
             // We compute the updated state of the sponge.
             generate_full_input_row::<F>(
                 row,
+                id,
                 op,
                 already_absorbed_bytes,
                 sponge_state,
@@ -115,6 +239,7 @@ impl KeccakSpongeChip {
 
         generate_final_row(
             rows.last_mut().unwrap(),
+            id,
             op,
             already_absorbed_bytes,
             sponge_state,
@@ -125,8 +250,9 @@ impl KeccakSpongeChip {
 
 /// Generates a row where all bytes are input bytes, not padding bytes.
 /// This includes updating the state sponge with a single absorption.
-fn generate_full_input_row<F: PrimeField32>(
+fn generate_full_input_row<F: PrimeField64>(
     row: &mut KeccakSpongeCols<F>,
+    id: usize,
     op: &KeccakSpongeOp,
     already_absorbed_bytes: usize,
     sponge_state: [u16; KECCAK_WIDTH_U16S],
@@ -135,12 +261,13 @@ fn generate_full_input_row<F: PrimeField32>(
     row.is_full_input_block = F::one();
     row.block_bytes = block.map(F::from_canonical_u8);
 
-    generate_common_fields(row, op, already_absorbed_bytes, sponge_state);
+    generate_common_fields(row, id, op, already_absorbed_bytes, sponge_state);
 }
 
 /// Generates a row containing the last input bytes.
-fn generate_final_row<F: PrimeField32>(
+fn generate_final_row<F: PrimeField64>(
     row: &mut KeccakSpongeCols<F>,
+    id: usize,
     op: &KeccakSpongeOp,
     already_absorbed_bytes: usize,
     sponge_state: [u16; KECCAK_WIDTH_U16S],
@@ -165,7 +292,7 @@ fn generate_final_row<F: PrimeField32>(
         row.is_padding_byte[i] = F::one();
     }
 
-    generate_common_fields(row, op, already_absorbed_bytes, sponge_state)
+    generate_common_fields(row, id, op, already_absorbed_bytes, sponge_state)
 }
 
 /// Generate fields that are common to both full-input-block rows and
@@ -173,14 +300,25 @@ fn generate_final_row<F: PrimeField32>(
 /// absorption. Given a state S = R || C and a block input B,
 /// - R is updated with R XOR B,
 /// - S is replaced by keccakf_u16s(S).
-fn generate_common_fields<F: PrimeField32>(
+fn generate_common_fields<F: PrimeField64>(
     row: &mut KeccakSpongeCols<F>,
+    id: usize,
     op: &KeccakSpongeOp,
     already_absorbed_bytes: usize,
     mut sponge_state: [u16; KECCAK_WIDTH_U16S],
 ) {
+    row.is_real = F::one();
+    row.id = F::from_canonical_usize(id);
     row.timestamp = F::from_canonical_u32(op.timestamp);
     row.base_addr = F::from_canonical_u32(op.addr);
+    row.dst_addr = F::from_canonical_u32(op.dst_addr);
+    if op.is_memory_op {
+        row.is_memory_op = F::one();
+        for (k, is_memory_word) in row.is_memory_word.iter_mut().enumerate() {
+            *is_memory_word = F::one() - row.is_padding_byte[k * KECCAK_CHUNK_BYTES];
+        }
+        row.writes_digest = row.is_padding_byte[KECCAK_RATE_BYTES - 1];
+    }
     row.already_absorbed_bytes = F::from_canonical_usize(already_absorbed_bytes);
 
     row.original_rate_u16s = sponge_state[..KECCAK_RATE_U16S]
@@ -1,6 +1,11 @@
+use core::iter::once;
+
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::Interaction;
 use tiny_keccak::keccakf;
 
-use super::columns::KECCAK_WIDTH_U16S;
+use super::columns::{KECCAK_CHUNK_BYTES, KECCAK_WIDTH_U16S};
 
 /// Like tiny-keccak's `keccakf`, but deals with `u16` limbs instead of `u64`
 /// limbs.
@@ -18,3 +23,50 @@ pub(crate) fn keccakf_u16s(state_u16s: &mut [u16; KECCAK_WIDTH_U16S]) {
         (u64_limb >> shift) as u16
     });
 }
+
+/// Builds the messages a chip sends on the sponge input bus to hash `input` with
+/// the sponge operation `id`. The input is split into chunks of
+/// `KECCAK_CHUNK_BYTES` bytes, each sent as `(id, offset, bytes, len)` with the
+/// bytes of a partial chunk padded with zeros.
+pub fn sponge_input_interactions<F: Field>(
+    id: VirtualPairCol<F>,
+    input: Vec<VirtualPairCol<F>>,
+    count: VirtualPairCol<F>,
+    argument_index: usize,
+) -> Vec<Interaction<F>> {
+    input
+        .chunks(KECCAK_CHUNK_BYTES)
+        .enumerate()
+        .map(|(i, chunk)| Interaction {
+            fields: once(id.clone())
+                .chain(once(VirtualPairCol::constant(F::from_canonical_usize(
+                    i * KECCAK_CHUNK_BYTES,
+                ))))
+                .chain(chunk.iter().cloned())
+                .chain(
+                    (chunk.len()..KECCAK_CHUNK_BYTES).map(|_| VirtualPairCol::constant(F::zero())),
+                )
+                .chain(once(VirtualPairCol::constant(F::from_canonical_usize(
+                    chunk.len(),
+                ))))
+                .collect(),
+            count: count.clone(),
+            argument_index,
+        })
+        .collect()
+}
+
+/// Builds the message a chip receives on the sponge output bus for the digest of
+/// the sponge operation `id`.
+pub fn sponge_output_interaction<F: Field>(
+    id: VirtualPairCol<F>,
+    digest: Vec<VirtualPairCol<F>>,
+    count: VirtualPairCol<F>,
+    argument_index: usize,
+) -> Interaction<F> {
+    Interaction {
+        fields: once(id).chain(digest).collect(),
+        count,
+        argument_index,
+    }
+}
@@ -5,57 +5,80 @@ use p3_matrix::Matrix;
 
 use super::{columns::MemoryCols, MemoryChip};
 
-impl<F> BaseAir<F> for MemoryChip {
+impl<F, const WORD_BYTES: usize> BaseAir<F> for MemoryChip<WORD_BYTES> {
     fn width(&self) -> usize {
-        MemoryCols::<F>::num_cols()
+        MemoryCols::<F, WORD_BYTES>::num_cols()
     }
 }
 
-impl<AB: AirBuilder> Air<AB> for MemoryChip {
+impl<AB: AirBuilder, const WORD_BYTES: usize> Air<AB> for MemoryChip<WORD_BYTES> {
     fn eval(&self, builder: &mut AB) {
         let main = builder.main();
         let local = main.row_slice(0);
         let next = main.row_slice(1);
-        let local: &MemoryCols<AB::Var> = (*local).borrow();
-        let next: &MemoryCols<AB::Var> = (*next).borrow();
+        let local: &MemoryCols<AB::Var, WORD_BYTES> = (*local).borrow();
+        let next: &MemoryCols<AB::Var, WORD_BYTES> = (*next).borrow();
 
-        // TODO: Add more constraints.
+        builder.assert_bool(local.is_init);
         builder.assert_bool(local.is_read);
         builder.assert_bool(local.is_write);
+        builder.assert_bool(local.is_final);
 
-        builder.assert_zero(local.is_read * local.is_write);
+        // A row is either an initialization, a read, a write or a dummy row.
+        let is_real = local.is_init + local.is_read + local.is_write;
+        let next_is_real = next.is_init + next.is_read + next.is_write;
+        builder.assert_bool(is_real.clone());
 
-        builder.assert_bool(local.addr_unchanged);
+        // Dummy rows are only used for padding at the end of the trace.
+        builder
+            .when_transition()
+            .when_ne(is_real.clone(), AB::Expr::one())
+            .assert_zero(next_is_real.clone());
 
+        // Every address starts with an initialization row at timestamp 0, so a read
+        // can never happen before the address is initialized.
+        builder
+            .when_first_row()
+            .assert_eq(local.is_init, is_real.clone());
+        builder.when(local.is_init).assert_zero(local.timestamp);
+
+        // Reads and writes access the same address as the previous row.
+        let next_is_access = next.is_read + next.is_write;
         builder
             .when_transition()
-            .when(local.addr_unchanged)
+            .when(next_is_access.clone())
             .assert_eq(local.addr, next.addr);
 
+        // The accesses of an address are sorted by timestamp, and the addresses are
+        // strictly increasing. At the same timestamp, reads come before the write, if
+        // any, so which value they return doesn't depend on the order of the rows.
         let diff = next.diff_limb_lo
             + next.diff_limb_md * AB::Expr::from_canonical_u32(1 << 8)
             + next.diff_limb_hi * AB::Expr::from_canonical_u32(1 << 16);
         builder
             .when_transition()
-            .when(next.addr_unchanged)
-            .assert_eq(diff.clone(), next.timestamp - local.timestamp);
+            .when(next_is_access.clone())
+            .assert_eq(
+                diff.clone(),
+                next.timestamp - local.timestamp - local.is_write,
+            );
         builder
             .when_transition()
-            .when(next.is_read + next.is_write)
-            .when_ne(next.addr_unchanged, AB::Expr::one())
+            .when(next.is_init)
             .assert_eq(diff, next.addr - local.addr - AB::Expr::one());
 
-        // TODO: Do I need this?
+        // A read returns the last value written to the address.
+        for (&local_byte, &next_byte) in local.value.iter().zip(next.value.iter()) {
+            builder
+                .when_transition()
+                .when(next.is_read)
+                .assert_eq(local_byte, next_byte);
+        }
+
+        // The final row of an address is the one not followed by another access.
         builder
             .when_transition()
-            .when(next.addr_unchanged)
-            .when(next.is_read)
-            .assert_eq(local.value, next.value);
-
-        // TODO: Check memory is initialized properly
-        // builder
-        //     .when_ne(local.addr_unchanged, AB::Expr::one())
-        //     .when(local.is_read)
-        //     .assert_zero(local.value);
+            .assert_eq(local.is_final, is_real.clone() - next_is_access);
+        builder.when_last_row().assert_eq(local.is_final, is_real);
     }
 }
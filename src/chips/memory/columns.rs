@@ -2,22 +2,31 @@ use p3_derive::Columnar;
 
 #[repr(C)]
 #[derive(Columnar)]
-pub struct MemoryCols<T> {
+pub struct MemoryCols<T, const WORD_BYTES: usize> {
+    /// Word address, i.e. the address of the first byte of the word divided by
+    /// `WORD_BYTES`.
     pub addr: T,
 
     pub timestamp: T,
 
-    pub value: T,
+    /// The bytes of the word, in little-endian order.
+    pub value: [T; WORD_BYTES],
+
+    /// Whether this row loads `addr` from the initial memory image. This is always
+    /// the first row of an address, at timestamp 0.
+    pub is_init: T,
 
     pub is_read: T,
 
     pub is_write: T,
 
-    // TODO: Do I need a column for this?
-    pub addr_unchanged: T,
+    /// Whether this is the last row of `addr`, in which case `value` is the final
+    /// value of the address.
+    pub is_final: T,
 
-    /// Either addr' - addr - 1 (if address changed), or timestamp' - timestamp (if address is not changed)
-    // No -1 in timestamp because can read and write in same cycle
+    /// Either addr' - addr - 1 (if address changed), or timestamp' - timestamp - is_write
+    /// (if address is not changed). An address can be read and written at the same
+    /// timestamp, but the write comes last, so the reads return the previous value.
     pub diff_limb_lo: T,
     pub diff_limb_md: T,
     pub diff_limb_hi: T,
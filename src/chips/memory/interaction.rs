@@ -1,24 +1,27 @@
+use core::iter::once;
+
 use p3_air::VirtualPairCol;
 use p3_field::Field;
 use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
 
 use super::{columns::MemoryCols, MemoryChip};
 
-impl<F: Field> BaseInteractionAir<F> for MemoryChip {
+impl<F: Field, const WORD_BYTES: usize> BaseInteractionAir<F> for MemoryChip<WORD_BYTES> {
     fn receives_from_indices(
         &self,
         _preprocessed_indices: &[usize],
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
-        let col_map = MemoryCols::from_slice(main_indices);
+        let col_map = MemoryCols::<_, WORD_BYTES>::from_slice(main_indices);
 
         vec![Interaction {
-            fields: vec![
-                VirtualPairCol::single_main(col_map.timestamp),
-                VirtualPairCol::single_main(col_map.addr),
-                VirtualPairCol::single_main(col_map.value),
-            ],
-            count: VirtualPairCol::single_main(col_map.is_write),
+            fields: [col_map.timestamp, col_map.addr]
+                .into_iter()
+                .chain(col_map.value)
+                .chain(once(col_map.is_write))
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            count: VirtualPairCol::sum_main(vec![col_map.is_read, col_map.is_write]),
             argument_index: self.bus_memory,
         }]
     }
@@ -28,48 +31,65 @@ impl<F: Field> BaseInteractionAir<F> for MemoryChip {
         _preprocessed_indices: &[usize],
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
-        let col_map = MemoryCols::from_slice(main_indices);
+        let col_map = MemoryCols::<_, WORD_BYTES>::from_slice(main_indices);
 
+        let is_real =
+            VirtualPairCol::sum_main(vec![col_map.is_init, col_map.is_read, col_map.is_write]);
         vec![
-            // TODO: Combine with is_write?
             Interaction {
-                fields: vec![
-                    VirtualPairCol::single_main(col_map.timestamp),
-                    VirtualPairCol::single_main(col_map.addr),
-                    VirtualPairCol::single_main(col_map.value),
-                ],
-                count: VirtualPairCol::single_main(col_map.is_read),
-                argument_index: self.bus_memory,
+                fields: once(col_map.addr)
+                    .chain(col_map.value)
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                count: VirtualPairCol::single_main(col_map.is_init),
+                argument_index: self.bus_memory_init,
+            },
+            Interaction {
+                fields: once(col_map.addr)
+                    .chain(col_map.value)
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                count: VirtualPairCol::single_main(col_map.is_final),
+                argument_index: self.bus_memory_final,
             },
             Interaction {
                 fields: vec![VirtualPairCol::single_main(col_map.diff_limb_lo)],
-                count: VirtualPairCol::sum_main(vec![col_map.is_read, col_map.is_write]),
+                count: is_real.clone(),
                 argument_index: self.bus_range_8,
             },
             Interaction {
                 fields: vec![VirtualPairCol::single_main(col_map.diff_limb_md)],
-                count: VirtualPairCol::sum_main(vec![col_map.is_read, col_map.is_write]),
+                count: is_real.clone(),
                 argument_index: self.bus_range_8,
             },
             Interaction {
                 fields: vec![VirtualPairCol::single_main(col_map.diff_limb_hi)],
-                count: VirtualPairCol::sum_main(vec![col_map.is_read, col_map.is_write]),
+                count: is_real,
                 argument_index: self.bus_range_8,
             },
         ]
+        .into_iter()
+        // Writes are the only way new values enter memory: initial values are bytes
+        // of the preprocessed image, and reads repeat the previous value.
+        .chain(col_map.value.map(|byte| Interaction {
+            fields: vec![VirtualPairCol::single_main(byte)],
+            count: VirtualPairCol::single_main(col_map.is_write),
+            argument_index: self.bus_range_8,
+        }))
+        .collect()
     }
 }
 
-impl<F: Field> InteractionAir<F> for MemoryChip {
+impl<F: Field, const WORD_BYTES: usize> InteractionAir<F> for MemoryChip<WORD_BYTES> {
     fn receives(&self) -> Vec<Interaction<F>> {
-        let col_map = MemoryCols::<F>::col_map();
+        let col_map = MemoryCols::<F, WORD_BYTES>::col_map();
         self.receives_from_main_indices(col_map.as_slice())
     }
 
     fn sends(&self) -> Vec<Interaction<F>> {
-        let col_map = MemoryCols::<F>::col_map();
+        let col_map = MemoryCols::<F, WORD_BYTES>::col_map();
         self.sends_from_main_indices(col_map.as_slice())
     }
 }
 
-impl<AB: InteractionAirBuilder> Rap<AB> for MemoryChip {}
+impl<AB: InteractionAirBuilder, const WORD_BYTES: usize> Rap<AB> for MemoryChip<WORD_BYTES> {}
@@ -1,56 +1,208 @@
 mod air;
 mod columns;
 mod interaction;
-mod trace;
+pub mod trace;
 
+/// Offline memory checking over a sorted list of accesses. Every accessed address
+/// is loaded from the initial image held by the `MemoryImageChip`, and its last
+/// value is sent back to it as the final image.
+///
+/// Memory is word-addressed, each word holding `WORD_BYTES` bytes. Chips that need
+/// byte access go through the `ByteMemoryChip` adapter.
 #[derive(Default, Clone, Debug)]
-pub struct MemoryChip {
+pub struct MemoryChip<const WORD_BYTES: usize> {
     pub bus_memory: usize,
+    pub bus_memory_init: usize,
+    pub bus_memory_final: usize,
     pub bus_range_8: usize,
 }
 
 #[cfg(feature = "air-logger")]
-impl p3_air_util::AirLogger for MemoryChip {
+impl<const WORD_BYTES: usize> p3_air_util::AirLogger for MemoryChip<WORD_BYTES> {
     fn main_headers(&self) -> Vec<String> {
-        self::columns::MemoryCols::<usize>::headers()
+        self::columns::MemoryCols::<usize, WORD_BYTES>::headers()
     }
 
     #[cfg(feature = "schema")]
     fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
-        self::columns::MemoryCols::<usize>::headers_and_types()
+        self::columns::MemoryCols::<usize, WORD_BYTES>::headers_and_types()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::prove_and_verify;
+    use crate::{
+        config::Val,
+        test_util::{
+            assert_mutations_rejected, assert_prove_and_verify_fails, prove_and_verify,
+            random_mutations,
+        },
+    };
 
+    use columns::MemoryCols;
     use itertools::Itertools;
+    use p3_field::AbstractField;
+    use p3_matrix::dense::RowMajorMatrix;
     use p3_uni_stark::VerificationError;
-    use rand::random;
-    use trace::{MemoryOp, OperationKind};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use trace::{image_word, MemoryOp, OperationKind};
+
+    const NUM_WORDS: usize = 16;
+    const NUM_OPS: usize = 400;
+
+    /// Generates a random image and random interleaved reads and writes over it.
+    fn generate_ops<const WORD_BYTES: usize>(seed: u64) -> (Vec<u8>, Vec<MemoryOp<WORD_BYTES>>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let image: Vec<u8> = (0..NUM_WORDS * WORD_BYTES).map(|_| rng.gen()).collect();
+        let mut memory = (0..NUM_WORDS)
+            .map(|addr| image_word::<WORD_BYTES>(&image, addr))
+            .collect_vec();
+        let operations = (0..NUM_OPS)
+            .map(|i| {
+                let addr = rng.gen_range(0..NUM_WORDS);
+                let kind = if rng.gen() {
+                    memory[addr] = core::array::from_fn(|_| rng.gen());
+                    OperationKind::Write
+                } else {
+                    OperationKind::Read
+                };
+                MemoryOp {
+                    addr: addr as u32,
+                    timestamp: i as u32,
+                    value: memory[addr],
+                    kind,
+                }
+            })
+            .collect();
+
+        (image, operations)
+    }
+
+    fn rows_mut<const WORD_BYTES: usize>(
+        trace: &mut RowMajorMatrix<Val>,
+    ) -> &mut [MemoryCols<Val, WORD_BYTES>] {
+        let (prefix, rows, suffix) =
+            unsafe { trace.values.align_to_mut::<MemoryCols<Val, WORD_BYTES>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        rows
+    }
 
     #[test]
     fn test_memory_prove() -> Result<(), VerificationError> {
-        const NUM_BYTES: usize = 400;
-
-        let bytes = (0..NUM_BYTES).map(|_| random()).collect_vec();
-        let operations = bytes
-            .into_iter()
-            .enumerate()
-            .map(|(i, b)| MemoryOp {
-                addr: i as u32,
-                timestamp: i as u32,
-                value: b,
-                kind: OperationKind::Read,
-            })
-            .collect_vec();
-        let trace = MemoryChip::generate_trace(operations);
-        let chip = MemoryChip {
+        let (image, operations) = generate_ops::<4>(0);
+        let trace = MemoryChip::<4>::generate_trace(&image, operations);
+        let chip = MemoryChip::<4> {
             ..Default::default()
         };
 
         prove_and_verify(&chip, trace, vec![])
     }
+
+    #[test]
+    fn test_memory_prove_u64_words() -> Result<(), VerificationError> {
+        let (image, operations) = generate_ops::<8>(0);
+        let trace = MemoryChip::<8>::generate_trace(&image, operations);
+        let chip = MemoryChip::<8> {
+            ..Default::default()
+        };
+
+        prove_and_verify(&chip, trace, vec![])
+    }
+
+    #[test]
+    fn test_memory_inconsistent_read() {
+        let (image, operations) = generate_ops::<4>(0);
+        let mut trace = MemoryChip::<4>::generate_trace(&image, operations);
+        let rows = rows_mut::<4>(&mut trace);
+        let row = rows.iter_mut().find(|row| row.is_read.is_one()).unwrap();
+        row.value[3] += Val::one();
+
+        assert_prove_and_verify_fails(&MemoryChip::<4>::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_memory_read_before_init() {
+        let (image, operations) = generate_ops::<4>(0);
+        let mut trace = MemoryChip::<4>::generate_trace(&image, operations);
+        let rows = rows_mut::<4>(&mut trace);
+        rows[0].is_init = Val::zero();
+        rows[0].is_read = Val::one();
+
+        assert_prove_and_verify_fails(&MemoryChip::<4>::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_memory_init_after_access() {
+        let (image, operations) = generate_ops::<4>(0);
+        let mut trace = MemoryChip::<4>::generate_trace(&image, operations);
+        let rows = rows_mut::<4>(&mut trace);
+        rows[1].timestamp += Val::one();
+
+        assert_prove_and_verify_fails(&MemoryChip::<4>::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_memory_read_after_write_at_same_timestamp() {
+        let image = vec![1, 2, 3, 4];
+        let operations = vec![
+            MemoryOp {
+                addr: 0,
+                timestamp: 1,
+                value: [1, 2, 3, 4],
+                kind: OperationKind::Read,
+            },
+            MemoryOp {
+                addr: 0,
+                timestamp: 1,
+                value: [5, 6, 7, 8],
+                kind: OperationKind::Write,
+            },
+        ];
+        let mut trace = MemoryChip::<4>::generate_trace(&image, operations);
+        prove_and_verify(&MemoryChip::<4>::default(), trace.clone(), vec![]).unwrap();
+
+        // Swapping the read and the write makes the read return the written value.
+        let rows = rows_mut::<4>(&mut trace);
+        assert!(rows[1].is_read.is_one() && rows[2].is_write.is_one());
+        rows[1].is_read = Val::zero();
+        rows[1].is_write = Val::one();
+        rows[1].value = rows[2].value;
+        rows[2].is_read = Val::one();
+        rows[2].is_write = Val::zero();
+
+        assert_prove_and_verify_fails(&MemoryChip::<4>::default(), trace, vec![]);
+    }
+
+    #[test]
+    fn test_memory_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let (image, operations) = generate_ops::<4>(RANDOM_SEED);
+        let trace = MemoryChip::<4>::generate_trace(&image, operations);
+        let chip = MemoryChip::<4> {
+            ..Default::default()
+        };
+
+        // Every address adds an initialization row, so the first `NUM_OPS` rows are real.
+        let col_map = MemoryCols::<usize, 4>::col_map();
+        let cols = [
+            col_map.value.as_slice(),
+            &[
+                col_map.addr,
+                col_map.timestamp,
+                col_map.is_read,
+                col_map.is_write,
+                col_map.diff_limb_lo,
+                col_map.diff_limb_md,
+                col_map.diff_limb_hi,
+            ],
+        ]
+        .concat();
+        let mutations = random_mutations(0..NUM_OPS, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
 }
@@ -1,5 +1,8 @@
+use alloc::collections::BTreeMap;
+use core::borrow::Borrow;
+
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
 use p3_matrix::dense::RowMajorMatrix;
 use tracing::instrument;
 
@@ -11,63 +14,164 @@ pub enum OperationKind {
     Write,
 }
 
+impl OperationKind {
+    pub fn is_write(&self) -> bool {
+        matches!(self, OperationKind::Write)
+    }
+}
+
 #[derive(Clone)]
-pub struct MemoryOp {
+pub struct MemoryOp<const WORD_BYTES: usize> {
+    /// Word address.
     pub addr: u32,
     pub timestamp: u32,
-    pub value: u8,
+    pub value: [u8; WORD_BYTES],
     pub kind: OperationKind,
 }
 
-impl MemoryChip {
+/// Returns the word at word address `addr` of the byte `image`. Bytes past the end
+/// of the image are zero.
+pub fn image_word<const WORD_BYTES: usize>(image: &[u8], addr: usize) -> [u8; WORD_BYTES] {
+    core::array::from_fn(|i| {
+        image
+            .get(addr * WORD_BYTES + i)
+            .copied()
+            .unwrap_or_default()
+    })
+}
+
+/// Number of words needed to hold the byte `image`.
+pub fn image_num_words<const WORD_BYTES: usize>(image: &[u8]) -> usize {
+    image.len().div_ceil(WORD_BYTES)
+}
+
+impl<const WORD_BYTES: usize> MemoryChip<WORD_BYTES> {
+    /// Generates the memory trace for the accesses in `operations`, starting from the
+    /// initial memory `image`, whose bytes are grouped into words of `WORD_BYTES`.
     #[instrument(name = "generate Memory trace", skip_all)]
-    pub fn generate_trace<F: PrimeField32>(operations: Vec<MemoryOp>) -> RowMajorMatrix<F> {
-        let num_cols = MemoryCols::<F>::num_cols();
-        let num_real_rows = operations.len();
+    pub fn generate_trace<F: PrimeField64>(
+        image: &[u8],
+        operations: Vec<MemoryOp<WORD_BYTES>>,
+    ) -> RowMajorMatrix<F> {
+        // Accesses are sorted by address and then by timestamp, with the reads at a
+        // timestamp before the write.
+        let operations = operations
+            .into_iter()
+            .sorted_by_key(|op| (op.addr, op.timestamp, op.kind.is_write()))
+            .collect_vec();
+        let num_addrs = operations.iter().map(|op| op.addr).dedup().count();
+
+        let num_cols = MemoryCols::<F, WORD_BYTES>::num_cols();
+        let num_real_rows = num_addrs + operations.len();
         let num_rows = num_real_rows.next_power_of_two();
         let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
 
-        let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MemoryCols<F>>() };
+        let (prefix, rows, suffix) =
+            unsafe { trace.values.align_to_mut::<MemoryCols<F, WORD_BYTES>>() };
         assert!(prefix.is_empty(), "Alignment should match");
         assert!(suffix.is_empty(), "Alignment should match");
         assert_eq!(rows.len(), num_rows);
 
         let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
-        Self::populate_rows_for_ops(&mut real_rows, &operations);
+        Self::populate_rows_for_ops(&mut real_rows, image, &operations);
 
         trace
     }
 
-    pub fn populate_rows_for_ops<F: PrimeField32>(
-        rows: &mut [&mut MemoryCols<F>],
-        ops: &[MemoryOp],
+    /// Counts the address and timestamp difference limbs that the real rows of a
+    /// memory trace send to the 8-bit range bus, along with the bytes written.
+    pub fn generate_range_counts<F: PrimeField64>(trace: &RowMajorMatrix<F>) -> BTreeMap<u32, u32> {
+        let mut count = BTreeMap::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &MemoryCols<F, WORD_BYTES> = row.borrow();
+            if (row.is_init + row.is_read + row.is_write).is_zero() {
+                continue;
+            }
+            let written = if row.is_write.is_one() {
+                &row.value[..]
+            } else {
+                &[]
+            };
+            for &limb in [row.diff_limb_lo, row.diff_limb_md, row.diff_limb_hi]
+                .iter()
+                .chain(written)
+            {
+                count
+                    .entry(limb.as_canonical_u64() as u32)
+                    .and_modify(|c| *c += 1)
+                    .or_insert(1);
+            }
+        }
+        count
+    }
+
+    /// Populates the rows for `ops`, which must be sorted by address and timestamp,
+    /// with the reads at a timestamp before the write. Each address gets an
+    /// initialization row followed by one row per access.
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut MemoryCols<F, WORD_BYTES>],
+        image: &[u8],
+        ops: &[MemoryOp<WORD_BYTES>],
     ) {
-        for (i, (row, op)) in rows.iter_mut().zip(ops.iter()).enumerate() {
-            row.addr = F::from_canonical_u32(op.addr);
-            row.timestamp = F::from_canonical_u32(op.timestamp);
-            row.value = F::from_canonical_u8(op.value);
-
-            match op.kind {
-                OperationKind::Read => {
-                    row.is_read = F::one();
-                }
-                OperationKind::Write => {
-                    row.is_write = F::one();
-                }
+        let mut i = 0;
+        let mut prev_addr = None;
+        for (addr, addr_ops) in &ops.iter().group_by(|op| op.addr) {
+            assert!(
+                (addr as usize) < image_num_words::<WORD_BYTES>(image),
+                "Address {addr} is outside of the memory image"
+            );
+
+            let row = &mut rows[i];
+            row.addr = F::from_canonical_u32(addr);
+            row.value = image_word(image, addr as usize).map(F::from_canonical_u8);
+            row.is_init = F::one();
+            if let Some(prev_addr) = prev_addr {
+                populate_diff(row, addr - prev_addr - 1);
             }
+            prev_addr = Some(addr);
+            i += 1;
+
+            let mut prev_timestamp = 0;
+            let mut prev_is_write = false;
+            for op in addr_ops {
+                let row = &mut rows[i];
+                row.addr = F::from_canonical_u32(op.addr);
+                row.timestamp = F::from_canonical_u32(op.timestamp);
+                row.value = op.value.map(F::from_canonical_u8);
 
-            if i > 0 {
-                let op_prev = &ops[i - 1];
-                let diff = if op.addr == op_prev.addr {
-                    row.addr_unchanged = F::one();
-                    op.timestamp - op_prev.timestamp
-                } else {
-                    op.addr - op_prev.addr - 1
-                };
-                row.diff_limb_lo = F::from_canonical_u32(diff % (1 << 8));
-                row.diff_limb_md = F::from_canonical_u32((diff >> 8) % (1 << 8));
-                row.diff_limb_hi = F::from_canonical_u32((diff >> 16) % (1 << 8));
+                match op.kind {
+                    OperationKind::Read => {
+                        row.is_read = F::one();
+                    }
+                    OperationKind::Write => {
+                        row.is_write = F::one();
+                    }
+                }
+
+                let diff = (op.timestamp - prev_timestamp)
+                    .checked_sub(prev_is_write as u32)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Address {addr} is accessed after its write at timestamp {}",
+                            op.timestamp
+                        )
+                    });
+                populate_diff(row, diff);
+                prev_timestamp = op.timestamp;
+                prev_is_write = op.kind.is_write();
+                i += 1;
             }
+
+            rows[i - 1].is_final = F::one();
         }
     }
 }
+
+fn populate_diff<F: PrimeField64, const WORD_BYTES: usize>(
+    row: &mut MemoryCols<F, WORD_BYTES>,
+    diff: u32,
+) {
+    row.diff_limb_lo = F::from_canonical_u32(diff % (1 << 8));
+    row.diff_limb_md = F::from_canonical_u32((diff >> 8) % (1 << 8));
+    row.diff_limb_hi = F::from_canonical_u32((diff >> 16) % (1 << 8));
+}
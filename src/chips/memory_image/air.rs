@@ -0,0 +1,82 @@
+use core::{borrow::Borrow, iter::once};
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::Field;
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+
+use super::{
+    columns::{MemoryImageCols, MemoryImagePreprocessedCols},
+    MemoryImageChip,
+};
+use crate::chips::{
+    keccak_sponge::columns::KECCAK_DIGEST_BYTES,
+    memory::trace::{image_num_words, image_word},
+};
+
+impl<F: Field, const WORD_BYTES: usize> BaseAir<F> for MemoryImageChip<WORD_BYTES> {
+    fn width(&self) -> usize {
+        MemoryImageCols::<F, WORD_BYTES>::num_cols()
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let num_cols = MemoryImagePreprocessedCols::<F, WORD_BYTES>::num_cols();
+        let num_words = image_num_words::<WORD_BYTES>(&self.image);
+        assert!(
+            self.output.end <= num_words,
+            "the output words are out of the image"
+        );
+        let num_rows = Self::num_rows(&self.image, self.calls.len());
+        let values = (0..num_rows)
+            .flat_map(|addr| {
+                let call = self.calls.get(addr);
+                let initial_value = image_word::<WORD_BYTES>(&self.image, addr);
+                let is_output = self.output.contains(&addr);
+                let output_offset = if is_output {
+                    (addr - self.output.start) * WORD_BYTES
+                } else {
+                    0
+                };
+                once(F::from_canonical_usize(addr))
+                    .chain(initial_value.map(F::from_canonical_u8))
+                    .chain([
+                        F::from_bool(is_output),
+                        F::from_canonical_usize(output_offset),
+                        F::from_bool(addr == 0),
+                        F::from_bool(call.is_some()),
+                    ])
+                    .chain(
+                        call.map(|call| [call.timestamp, call.addr, call.len, call.dst_addr])
+                            .unwrap_or_default()
+                            .map(F::from_canonical_u32),
+                    )
+            })
+            .collect();
+        Some(RowMajorMatrix::new(values, num_cols))
+    }
+}
+
+impl<AB, const WORD_BYTES: usize> Air<AB> for MemoryImageChip<WORD_BYTES>
+where
+    AB: AirBuilderWithPublicValues,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MemoryImageCols<AB::Var, WORD_BYTES> = (*local).borrow();
+        let next: &MemoryImageCols<AB::Var, WORD_BYTES> = (*next).borrow();
+
+        builder.assert_bool(local.is_touched);
+
+        // The output words are all hashed by the same sponge operation, whose digest
+        // is received on the first row.
+        builder
+            .when_transition()
+            .assert_eq(next.output_hash_id, local.output_hash_id);
+        let public_values: [AB::PublicVar; KECCAK_DIGEST_BYTES] =
+            core::array::from_fn(|i| builder.public_values()[i]);
+        for (&digest_byte, public_value) in local.output_digest.iter().zip(public_values) {
+            builder
+                .when_first_row()
+                .assert_eq(digest_byte, public_value);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use p3_derive::Columnar;
+
+use crate::chips::keccak_sponge::columns::KECCAK_DIGEST_BYTES;
+
+#[repr(C)]
+#[derive(Columnar)]
+pub struct MemoryImageCols<T, const WORD_BYTES: usize> {
+    /// The value of the address once all accesses are done.
+    pub final_value: [T; WORD_BYTES],
+
+    /// Whether the address is accessed by the memory chip.
+    pub is_touched: T,
+
+    /// Id of the sponge operation hashing the output words, the same on every row.
+    pub output_hash_id: T,
+
+    /// Hash of the output words, equal to the public values on the first row.
+    pub output_digest: [T; KECCAK_DIGEST_BYTES],
+}
+
+#[repr(C)]
+#[derive(Columnar)]
+pub struct MemoryImagePreprocessedCols<T, const WORD_BYTES: usize> {
+    pub addr: T,
+
+    pub initial_value: [T; WORD_BYTES],
+
+    /// Whether the address is one of the output words.
+    pub is_output: T,
+
+    /// Offset of the word in the output, in bytes.
+    pub output_offset: T,
+
+    pub is_first_row: T,
+
+    /// Whether a memory operation of the sponge is called on this row.
+    pub is_call: T,
+
+    /// Timestamp at which the called operation reads its input.
+    pub call_timestamp: T,
+
+    /// Word address of the input of the called operation.
+    pub call_addr: T,
+
+    /// Length of the input of the called operation, in bytes.
+    pub call_len: T,
+
+    /// Word address the digest of the called operation is written to.
+    pub call_dst_addr: T,
+}
@@ -0,0 +1,146 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::once;
+
+use p3_air::{AirBuilderWithPublicValues, VirtualPairCol};
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{
+    columns::{MemoryImageCols, MemoryImagePreprocessedCols},
+    MemoryImageChip,
+};
+use crate::chips::keccak_sponge::util::sponge_output_interaction;
+
+impl<F: Field, const WORD_BYTES: usize> BaseInteractionAir<F> for MemoryImageChip<WORD_BYTES> {
+    fn receives_from_indices(
+        &self,
+        preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let preprocessed_col_map =
+            MemoryImagePreprocessedCols::<_, WORD_BYTES>::from_slice(preprocessed_indices);
+        let main_col_map = MemoryImageCols::<_, WORD_BYTES>::from_slice(main_indices);
+
+        vec![
+            // Touched addresses are initialized by the memory chip exactly once.
+            Interaction {
+                fields: once(preprocessed_col_map.addr)
+                    .chain(preprocessed_col_map.initial_value)
+                    .map(VirtualPairCol::single_preprocessed)
+                    .collect(),
+                count: VirtualPairCol::single_main(main_col_map.is_touched),
+                argument_index: self.bus_memory_init,
+            },
+            // Every address has exactly one final value.
+            Interaction {
+                fields: once(VirtualPairCol::single_preprocessed(
+                    preprocessed_col_map.addr,
+                ))
+                .chain(
+                    main_col_map
+                        .final_value
+                        .into_iter()
+                        .map(VirtualPairCol::single_main),
+                )
+                .collect(),
+                count: VirtualPairCol::constant(F::one()),
+                argument_index: self.bus_memory_final,
+            },
+            sponge_output_interaction(
+                VirtualPairCol::single_main(main_col_map.output_hash_id),
+                main_col_map
+                    .output_digest
+                    .into_iter()
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                VirtualPairCol::single_preprocessed(preprocessed_col_map.is_first_row),
+                self.bus_hasher_output,
+            ),
+        ]
+    }
+
+    fn sends_from_indices(
+        &self,
+        preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let preprocessed_col_map =
+            MemoryImagePreprocessedCols::<_, WORD_BYTES>::from_slice(preprocessed_indices);
+        let main_col_map = MemoryImageCols::<_, WORD_BYTES>::from_slice(main_indices);
+
+        vec![
+            // The final value of an untouched address is its initial value. The memory
+            // chip sends the final value of the touched addresses.
+            Interaction {
+                fields: once(preprocessed_col_map.addr)
+                    .chain(preprocessed_col_map.initial_value)
+                    .map(VirtualPairCol::single_preprocessed)
+                    .collect(),
+                count: VirtualPairCol::new_main(
+                    vec![(main_col_map.is_touched, -F::one())],
+                    F::one(),
+                ),
+                argument_index: self.bus_memory_final,
+            },
+            // Each output word is a whole chunk of the sponge input, at its offset in
+            // the output.
+            Interaction {
+                fields: once(VirtualPairCol::single_main(main_col_map.output_hash_id))
+                    .chain(once(VirtualPairCol::single_preprocessed(
+                        preprocessed_col_map.output_offset,
+                    )))
+                    .chain(
+                        main_col_map
+                            .final_value
+                            .into_iter()
+                            .map(VirtualPairCol::single_main),
+                    )
+                    .chain(once(VirtualPairCol::constant(F::from_canonical_usize(
+                        WORD_BYTES,
+                    ))))
+                    .collect(),
+                count: VirtualPairCol::single_preprocessed(preprocessed_col_map.is_output),
+                argument_index: self.bus_hasher_input,
+            },
+            Interaction {
+                fields: [
+                    preprocessed_col_map.call_timestamp,
+                    preprocessed_col_map.call_addr,
+                    preprocessed_col_map.call_len,
+                    preprocessed_col_map.call_dst_addr,
+                ]
+                .into_iter()
+                .map(VirtualPairCol::single_preprocessed)
+                .collect(),
+                count: VirtualPairCol::single_preprocessed(preprocessed_col_map.is_call),
+                argument_index: self.bus_call,
+            },
+        ]
+    }
+}
+
+impl<F: Field, const WORD_BYTES: usize> InteractionAir<F> for MemoryImageChip<WORD_BYTES> {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let preprocessed_col_map = MemoryImagePreprocessedCols::<F, WORD_BYTES>::col_map();
+        let main_col_map = MemoryImageCols::<F, WORD_BYTES>::col_map();
+
+        self.receives_from_indices(preprocessed_col_map.as_slice(), main_col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let preprocessed_col_map = MemoryImagePreprocessedCols::<F, WORD_BYTES>::col_map();
+        let main_col_map = MemoryImageCols::<F, WORD_BYTES>::col_map();
+
+        self.sends_from_indices(preprocessed_col_map.as_slice(), main_col_map.as_slice())
+    }
+}
+
+impl<AB, const WORD_BYTES: usize> Rap<AB> for MemoryImageChip<WORD_BYTES>
+where
+    AB: InteractionAirBuilder + AirBuilderWithPublicValues,
+{
+    fn preprocessed_width(&self) -> usize {
+        MemoryImagePreprocessedCols::<AB::F, WORD_BYTES>::num_cols()
+    }
+}
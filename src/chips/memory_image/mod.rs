@@ -0,0 +1,161 @@
+use core::ops::Range;
+
+use crate::chips::keccak_sponge::trace::SpongeCall;
+
+mod air;
+mod columns;
+mod interaction;
+pub mod trace;
+
+/// Holds the initial memory image as a preprocessed table, and the final memory
+/// image as committed columns. Word address `i` is stored in row `i`.
+///
+/// The final values of the output words are hashed by the sponge, and the public
+/// values are the bytes of the digest. Each word is a chunk of the sponge input, so
+/// `WORD_BYTES` must be `KECCAK_CHUNK_BYTES` for a chip with output words.
+///
+/// The memory operations of the sponge are called from a preprocessed table too, so
+/// the regions they hash and where their digests go are fixed by the verifying key.
+/// Call `i` is stored in row `i`.
+#[derive(Default, Clone, Debug)]
+pub struct MemoryImageChip<const WORD_BYTES: usize> {
+    /// The initial memory as bytes. The last word is padded with zeros.
+    pub image: Vec<u8>,
+
+    /// Word addresses of the output words.
+    pub output: Range<usize>,
+
+    /// Memory operations of the sponge, each of which must run exactly once.
+    pub calls: Vec<SpongeCall>,
+
+    pub bus_memory_init: usize,
+    pub bus_memory_final: usize,
+
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_call: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl<const WORD_BYTES: usize> p3_air_util::AirLogger for MemoryImageChip<WORD_BYTES> {
+    fn preprocessed_headers(&self) -> Vec<String> {
+        self::columns::MemoryImagePreprocessedCols::<usize, WORD_BYTES>::headers()
+    }
+
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::MemoryImageCols::<usize, WORD_BYTES>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn preprocessed_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::MemoryImagePreprocessedCols::<usize, WORD_BYTES>::headers_and_types()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::MemoryImageCols::<usize, WORD_BYTES>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chips::memory::trace::{MemoryOp, OperationKind},
+        config::Val,
+        test_util::{
+            assert_mutations_rejected_with_public_values, assert_prove_and_verify_fails,
+            prove_and_verify, random_mutations,
+        },
+    };
+    use columns::MemoryImageCols;
+
+    use p3_field::AbstractField;
+    use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_symmetric::CryptographicHasher;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const RANDOM_SEED: u64 = 0;
+    const WORD_BYTES: usize = 4;
+
+    /// A chip over a random image whose even words are overwritten, along with its
+    /// trace and public values.
+    fn setup(
+        image_size: usize,
+        output: Range<usize>,
+    ) -> (MemoryImageChip<WORD_BYTES>, RowMajorMatrix<Val>, Vec<Val>) {
+        let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+        let image = (0..image_size).map(|_| rng.gen()).collect::<Vec<u8>>();
+        let operations = (0..image_size / WORD_BYTES / 2)
+            .map(|i| MemoryOp {
+                addr: 2 * i as u32,
+                timestamp: i as u32,
+                value: rng.gen(),
+                kind: OperationKind::Write,
+            })
+            .collect::<Vec<_>>();
+        let trace = MemoryImageChip::<WORD_BYTES>::generate_trace(
+            &image,
+            output.clone(),
+            0,
+            0,
+            &operations,
+        );
+        let public_values = Keccak256Hash
+            .hash_iter(MemoryImageChip::<WORD_BYTES>::output(
+                &image,
+                &operations,
+                output.clone(),
+            ))
+            .map(Val::from_canonical_u8)
+            .to_vec();
+        let chip = MemoryImageChip::<WORD_BYTES> {
+            image,
+            output,
+            ..Default::default()
+        };
+        (chip, trace, public_values)
+    }
+
+    #[test]
+    fn test_memory_image_prove() -> Result<(), VerificationError> {
+        let (chip, trace, public_values) = setup(102, 5..12);
+        prove_and_verify(&chip, trace, public_values)
+    }
+
+    #[test]
+    fn test_memory_image_wrong_public_values() {
+        let (chip, trace, mut public_values) = setup(102, 5..12);
+        public_values[0] += Val::one();
+        assert_prove_and_verify_fails(&chip, trace, public_values);
+    }
+
+    #[test]
+    fn test_memory_image_mutations() {
+        const NUM_MUTATIONS: usize = 100;
+        const IMAGE_SIZE: usize = 64;
+
+        let (chip, trace, public_values) = setup(IMAGE_SIZE, 3..9);
+
+        let col_map = MemoryImageCols::<usize, WORD_BYTES>::col_map();
+        let cols = [
+            col_map.final_value.as_slice(),
+            &[col_map.is_touched, col_map.output_hash_id],
+        ]
+        .concat();
+        let mutations = random_mutations(
+            0..IMAGE_SIZE / WORD_BYTES,
+            &cols,
+            NUM_MUTATIONS,
+            RANDOM_SEED,
+        );
+        assert_mutations_rejected_with_public_values(&chip, &trace, &public_values, &mutations);
+
+        // Only the digest of the first row is checked against the public values.
+        let mutations = random_mutations(0..1, &col_map.output_digest, 10, RANDOM_SEED);
+        assert_mutations_rejected_with_public_values(&chip, &trace, &public_values, &mutations);
+    }
+}
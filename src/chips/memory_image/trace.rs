@@ -0,0 +1,92 @@
+use core::ops::Range;
+
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use tracing::instrument;
+
+use super::{columns::MemoryImageCols, MemoryImageChip};
+use crate::chips::memory::trace::{image_num_words, image_word, MemoryOp, OperationKind};
+
+impl<const WORD_BYTES: usize> MemoryImageChip<WORD_BYTES> {
+    /// Number of rows of a chip with the initial memory `image` and `num_calls`
+    /// calls, enough for a row per word and per call.
+    pub fn num_rows(image: &[u8], num_calls: usize) -> usize {
+        image_num_words::<WORD_BYTES>(image)
+            .max(num_calls)
+            .next_power_of_two()
+    }
+
+    /// Generates the final memory image after applying `operations` to the initial
+    /// memory `image`, whose bytes are grouped into words of `WORD_BYTES`, for a chip
+    /// with `num_calls` calls. The `output` words are hashed by the sponge operation
+    /// `output_hash_id`.
+    #[instrument(name = "generate MemoryImage trace", skip_all)]
+    pub fn generate_trace<F: PrimeField64>(
+        image: &[u8],
+        output: Range<usize>,
+        num_calls: usize,
+        output_hash_id: usize,
+        operations: &[MemoryOp<WORD_BYTES>],
+    ) -> RowMajorMatrix<F> {
+        let num_cols = MemoryImageCols::<F, WORD_BYTES>::num_cols();
+        let num_real_rows = image_num_words::<WORD_BYTES>(image);
+        let num_rows = Self::num_rows(image, num_calls);
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<MemoryImageCols<F, WORD_BYTES>>()
+        };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, image, operations);
+
+        for row in rows.iter_mut() {
+            row.output_hash_id = F::from_canonical_usize(output_hash_id);
+        }
+        let output_digest = Keccak256Hash.hash_iter(Self::output(image, operations, output));
+        rows[0].output_digest = output_digest.map(F::from_canonical_u8);
+
+        trace
+    }
+
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut MemoryImageCols<F, WORD_BYTES>],
+        image: &[u8],
+        ops: &[MemoryOp<WORD_BYTES>],
+    ) {
+        let final_image = Self::final_image(image, ops);
+        for (row, &value) in rows.iter_mut().zip(final_image.iter()) {
+            row.final_value = value.map(F::from_canonical_u8);
+        }
+        for op in ops.iter() {
+            rows[op.addr as usize].is_touched = F::one();
+        }
+    }
+
+    /// Returns the bytes of the `output` words once the writes in `ops` are applied
+    /// to the initial memory `image`.
+    pub fn output(image: &[u8], ops: &[MemoryOp<WORD_BYTES>], output: Range<usize>) -> Vec<u8> {
+        Self::final_image(image, ops)[output].concat()
+    }
+
+    /// Applies the writes in `ops`, in timestamp order, to the initial memory `image`,
+    /// and returns the final value of each word.
+    pub fn final_image(image: &[u8], ops: &[MemoryOp<WORD_BYTES>]) -> Vec<[u8; WORD_BYTES]> {
+        let mut final_image = (0..image_num_words::<WORD_BYTES>(image))
+            .map(|addr| image_word(image, addr))
+            .collect_vec();
+        for op in ops.iter().sorted_by_key(|op| op.timestamp) {
+            if let OperationKind::Write = op.kind {
+                final_image[op.addr as usize] = op.value;
+            }
+        }
+        final_image
+    }
+}
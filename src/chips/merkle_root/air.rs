@@ -6,7 +6,10 @@ use p3_matrix::Matrix;
 
 use crate::airs::step_flags::StepFlagsAir;
 
-use super::{columns::MerkleRootCols, MerkleRootChip};
+use super::{
+    columns::{MerkleRootCols, LEAF_INDEX_BYTES},
+    MerkleRootChip,
+};
 
 impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize> BaseAir<F>
     for MerkleRootChip<DEPTH, DIGEST_WIDTH>
@@ -31,6 +34,8 @@ where
 
         builder.assert_bool(local.is_real);
         builder.assert_bool(local.is_right_child);
+        builder.assert_bool(local.reads_leaf);
+        builder.when(local.reads_leaf).assert_one(local.is_real);
 
         let step_flags_air = StepFlagsAir::<DEPTH>;
         let mut sub_builder = SubRangeAirBuilder::new_main(builder, col_map.step_flags.as_range());
@@ -39,6 +44,14 @@ where
         let is_first_step = local.step_flags.flags[0];
         let is_final_step = local.step_flags.flags[DEPTH - 1];
 
+        // Only the leaf, on the first row of a path, can be read from memory.
+        builder.when(local.reads_leaf).assert_one(is_first_step);
+
+        // Only the root, on the final row of a path, can be written to memory.
+        builder.assert_bool(local.writes_root);
+        builder.when(local.writes_root).assert_one(local.is_real);
+        builder.when(local.writes_root).assert_one(is_final_step);
+
         // Accumulated index is computed correctly
         builder
             .when(is_first_step)
@@ -55,6 +68,26 @@ where
             bit_factor * next.is_right_child + local.accumulated_index,
         );
 
+        // The final row holds the bytes of the leaf index, which the memory chip range
+        // checks when they are written.
+        let index: AB::Expr = (0..LEAF_INDEX_BYTES)
+            .map(|i| local.index_bytes[i] * AB::Expr::from_canonical_u32(1 << (8 * i)))
+            .sum();
+        builder
+            .when(is_final_step)
+            .assert_eq(local.accumulated_index, index);
+
+        // The leaf is copied down the path.
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(is_first_step)
+                .assert_eq(local.leaf[i], local.node[i]);
+            builder
+                .when_transition()
+                .when_ne(is_final_step, AB::Expr::one())
+                .assert_eq(local.leaf[i], next.leaf[i]);
+        }
+
         // Left and right nodes are selected correctly.
         for i in 0..DIGEST_WIDTH {
             let diff = local.node[i] - local.sibling[i];
@@ -65,6 +98,40 @@ where
             builder.assert_eq(right, local.right_node[i]);
         }
 
+        // Compares `node` and `sibling` lexicographically. The bytes before the marked
+        // one are equal, and the marked one differs by `abs_diff`, which is range
+        // checked to be non-zero. Without a marker, all the bytes are equal.
+        let mut is_past_marker = AB::Expr::zero();
+        let mut diff = AB::Expr::zero();
+        for i in 0..DIGEST_WIDTH {
+            builder.assert_bool(local.diff_marker[i]);
+            is_past_marker += local.diff_marker[i].into();
+            builder
+                .when_ne(is_past_marker.clone(), AB::Expr::one())
+                .assert_eq(local.node[i], local.sibling[i]);
+            diff += local.diff_marker[i] * (local.node[i] - local.sibling[i]);
+        }
+        let has_marker = is_past_marker;
+        builder.assert_bool(has_marker.clone());
+        builder.assert_bool(local.node_is_greater);
+        builder
+            .when_ne(has_marker, AB::Expr::one())
+            .assert_zero(local.node_is_greater);
+        builder.assert_eq(
+            local.abs_diff,
+            (local.node_is_greater * AB::Expr::two() - AB::Expr::one()) * diff,
+        );
+
+        // Sorted pairs put the greater node on the right.
+        builder.assert_bool(local.sorts_pair);
+        builder
+            .when(local.sorts_pair)
+            .assert_eq(local.is_right_child, local.node_is_greater);
+        builder
+            .when_transition()
+            .when_ne(is_final_step, AB::Expr::one())
+            .assert_eq(local.sorts_pair, next.sorts_pair);
+
         // Output is copied to the next row.
         for i in 0..DIGEST_WIDTH {
             builder
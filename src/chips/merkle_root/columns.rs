@@ -2,6 +2,10 @@ use p3_derive::Columnar;
 
 use crate::airs::step_flags::StepFlagsCols;
 
+/// Number of bytes of the leaf index written next to the root, enough for paths of
+/// depth up to 32.
+pub const LEAF_INDEX_BYTES: usize = 4;
+
 #[repr(C)]
 #[derive(Columnar)]
 pub struct MerkleRootCols<T, const DEPTH: usize, const DIGEST_WIDTH: usize> {
@@ -9,14 +13,58 @@ pub struct MerkleRootCols<T, const DEPTH: usize, const DIGEST_WIDTH: usize> {
 
     pub step_flags: StepFlagsCols<T, DEPTH>,
 
+    /// Id of the sponge operation that hashes `left_node || right_node`.
+    pub hash_id: T,
+
+    /// Whether the leaf hash, the `node` of the first row, is read from memory,
+    /// where it was written by hashing its preimage.
+    pub reads_leaf: T,
+
+    /// Timestamp of the leaf hash read.
+    pub leaf_timestamp: T,
+
+    /// Word address of the leaf hash.
+    pub leaf_addr: T,
+
+    /// Whether the root, the `output` of the final row of a path, is written to
+    /// memory, followed by the leaf hash and the leaf index.
+    pub writes_root: T,
+
+    /// Timestamp of the root write.
+    pub root_timestamp: T,
+
+    /// Word address the root is written to.
+    pub root_addr: T,
+
     pub node: [T; DIGEST_WIDTH],
 
     pub sibling: [T; DIGEST_WIDTH],
 
     pub is_right_child: T,
 
+    /// Whether the path hashes sorted pairs, as OpenZeppelin's `MerkleProof` does,
+    /// rather than ordering them by the bits of the leaf index.
+    pub sorts_pair: T,
+
+    /// Marks the first byte at which `node` and `sibling` differ, if any.
+    pub diff_marker: [T; DIGEST_WIDTH],
+
+    /// Whether `node` is lexicographically greater than `sibling`.
+    pub node_is_greater: T,
+
+    /// Absolute difference of the marked bytes, which is non-zero.
+    pub abs_diff: T,
+
     pub accumulated_index: T,
 
+    /// The leaf hash, the `node` of the first row, copied down the path so that the
+    /// final row can write it next to the root.
+    pub leaf: [T; DIGEST_WIDTH],
+
+    /// Little-endian bytes of `accumulated_index` on the final row of a path, written
+    /// next to the leaf hash.
+    pub index_bytes: [T; LEAF_INDEX_BYTES],
+
     pub left_node: [T; DIGEST_WIDTH],
 
     pub right_node: [T; DIGEST_WIDTH],
@@ -1,11 +1,18 @@
-use std::iter::once;
+use core::iter::once;
 
+use itertools::Itertools;
 use p3_air::VirtualPairCol;
 use p3_field::Field;
 use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
 
-use super::{columns::MerkleRootCols, MerkleRootChip};
-use crate::chips::keccak_sponge::columns::KECCAK_RATE_BYTES;
+use super::{
+    columns::{MerkleRootCols, LEAF_INDEX_BYTES},
+    MerkleRootChip,
+};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    MEMORY_WORD_BYTES,
+};
 
 impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize> BaseInteractionAir<F>
     for MerkleRootChip<DEPTH, DIGEST_WIDTH>
@@ -18,15 +25,16 @@ where
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
         let col_map = MerkleRootCols::<_, DEPTH, DIGEST_WIDTH>::from_slice(main_indices);
-        vec![Interaction {
-            fields: col_map
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
                 .output
                 .into_iter()
-                .map(|elem| VirtualPairCol::single_main(elem))
+                .map(VirtualPairCol::single_main)
                 .collect(),
-            count: VirtualPairCol::single_main(col_map.is_real),
-            argument_index: self.bus_hasher_output,
-        }]
+            VirtualPairCol::single_main(col_map.is_real),
+            self.bus_hasher_output,
+        )]
     }
 
     fn sends_from_indices(
@@ -35,31 +43,95 @@ where
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
         let col_map = MerkleRootCols::<_, DEPTH, DIGEST_WIDTH>::from_slice(main_indices);
-        vec![Interaction {
-            fields: once(VirtualPairCol::constant(F::zero()))
-                .chain(
-                    col_map
-                        .left_node
-                        .into_iter()
-                        .chain(col_map.right_node.into_iter())
-                        .map(|elem| VirtualPairCol::single_main(elem))
-                        // TODO: Don't send padding bytes
-                        .chain((2 * DIGEST_WIDTH..KECCAK_RATE_BYTES).map(|i| {
-                            VirtualPairCol::constant({
-                                if i == 2 * DIGEST_WIDTH {
-                                    F::one()
-                                } else if i == KECCAK_RATE_BYTES - 1 {
-                                    F::from_canonical_u8(0b10000000)
-                                } else {
-                                    F::zero()
-                                }
-                            })
-                        })),
-                )
-                .collect(),
+
+        // A leaf hashed from its preimage is read from the words at `leaf_addr`.
+        let leaf_reads = col_map
+            .node
+            .chunks(MEMORY_WORD_BYTES)
+            .enumerate()
+            .map(|(k, word)| Interaction {
+                fields: once(VirtualPairCol::single_main(col_map.leaf_timestamp))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(col_map.leaf_addr, F::one())],
+                        F::from_canonical_usize(k),
+                    )))
+                    .chain(word.iter().map(|&byte| VirtualPairCol::single_main(byte)))
+                    .chain(once(VirtualPairCol::constant(F::zero())))
+                    .collect(),
+                count: VirtualPairCol::single_main(col_map.reads_leaf),
+                argument_index: self.bus_memory,
+            })
+            .collect_vec();
+
+        // The root is written to the words at `root_addr`, followed by the leaf hash and
+        // by a word holding the leaf index, zero-padded.
+        let index_word = col_map
+            .index_bytes
+            .into_iter()
+            .map(VirtualPairCol::single_main)
+            .chain(
+                (LEAF_INDEX_BYTES..MEMORY_WORD_BYTES).map(|_| VirtualPairCol::constant(F::zero())),
+            )
+            .collect_vec();
+        let root_writes = col_map
+            .output
+            .chunks(MEMORY_WORD_BYTES)
+            .chain(col_map.leaf.chunks(MEMORY_WORD_BYTES))
+            .map(|word| {
+                word.iter()
+                    .map(|&byte| VirtualPairCol::single_main(byte))
+                    .collect_vec()
+            })
+            .chain(once(index_word))
+            .enumerate()
+            .map(|(k, word)| Interaction {
+                fields: once(VirtualPairCol::single_main(col_map.root_timestamp))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(col_map.root_addr, F::one())],
+                        F::from_canonical_usize(k),
+                    )))
+                    .chain(word)
+                    .chain(once(VirtualPairCol::constant(F::one())))
+                    .collect(),
+                count: VirtualPairCol::single_main(col_map.writes_root),
+                argument_index: self.bus_memory,
+            })
+            .collect_vec();
+
+        // `abs_diff - 1` is a byte when `node` and `sibling` differ, and zero otherwise.
+        let abs_diff_range_check = Interaction {
+            fields: vec![VirtualPairCol::new_main(
+                once((col_map.abs_diff, F::one()))
+                    .chain(
+                        col_map
+                            .diff_marker
+                            .into_iter()
+                            .map(|marker| (marker, -F::one())),
+                    )
+                    .collect(),
+                F::zero(),
+            )],
             count: VirtualPairCol::single_main(col_map.is_real),
-            argument_index: self.bus_hasher_input,
-        }]
+            argument_index: self.bus_range_8,
+        };
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                col_map
+                    .left_node
+                    .into_iter()
+                    .chain(col_map.right_node)
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                VirtualPairCol::single_main(col_map.is_real),
+                self.bus_hasher_input,
+            ),
+            leaf_reads,
+            root_writes,
+            vec![abs_diff_range_check],
+        ]
+        .concat()
     }
 }
 
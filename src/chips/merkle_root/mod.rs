@@ -1,14 +1,20 @@
 mod air;
-mod columns;
+pub(crate) mod columns;
 mod interaction;
 mod trace;
 
-pub use trace::MerkleRootOp;
+pub use trace::{LeafPreimage, MerkleRootOp, RootWrite};
 
 #[derive(Default, Clone, Debug)]
 pub struct MerkleRootChip<const DEPTH: usize, const DIGEST_WIDTH: usize> {
     pub bus_hasher_input: usize,
     pub bus_hasher_output: usize,
+
+    /// Leaves hashed from their preimage are read from memory, and roots may be
+    /// written to it.
+    pub bus_memory: usize,
+
+    pub bus_range_8: usize,
 }
 
 #[cfg(feature = "air-logger")]
@@ -27,10 +33,15 @@ impl<const DEPTH: usize, const DIGEST_WIDTH: usize> p3_air_util::AirLogger
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::prove_and_verify;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::MerkleRootCols;
 
     use itertools::Itertools;
     use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
     use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher};
     use p3_uni_stark::VerificationError;
     use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -57,12 +68,11 @@ mod tests {
         digests
     }
 
-    #[test]
-    fn test_merkle_root_prove() -> Result<(), VerificationError> {
-        const RANDOM_SEED: u64 = 0;
-        let mut seeded_rng = StdRng::seed_from_u64(RANDOM_SEED);
+    const HEIGHT: usize = 3;
+
+    fn generate_op_trace(seed: u64, sorted_pair: bool) -> RowMajorMatrix<Val> {
+        let mut seeded_rng = StdRng::seed_from_u64(seed);
 
-        const HEIGHT: usize = 3;
         const NUM_LEAVES: usize = 1 << HEIGHT;
 
         let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
@@ -82,9 +92,20 @@ mod tests {
             leaf_index,
             leaf_hash,
             siblings,
+            first_hash_id: 0,
+            leaf_preimage: None,
+            root_write: None,
+            sorted_pair,
         };
 
-        let trace = MerkleRootChip::generate_trace(vec![op], &hasher);
+        MerkleRootChip::generate_trace(vec![op], &hasher)
+    }
+
+    #[test]
+    fn test_merkle_root_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_op_trace(RANDOM_SEED, false);
 
         let chip: MerkleRootChip<HEIGHT, 32> = MerkleRootChip {
             ..Default::default()
@@ -92,4 +113,67 @@ mod tests {
 
         prove_and_verify(&chip, trace, vec![])
     }
+
+    #[test]
+    fn test_merkle_root_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 100;
+
+        let trace = generate_op_trace(RANDOM_SEED, false);
+
+        let chip: MerkleRootChip<HEIGHT, 32> = MerkleRootChip {
+            ..Default::default()
+        };
+
+        let col_map = MerkleRootCols::<usize, HEIGHT, 32>::col_map();
+        let cols = [
+            col_map.output.as_slice(),
+            col_map.leaf.as_slice(),
+            col_map.left_node.as_slice(),
+            col_map.right_node.as_slice(),
+            &[col_map.is_right_child, col_map.hash_id, col_map.reads_leaf],
+        ]
+        .concat();
+        let mutations = random_mutations(0..HEIGHT, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+
+    #[test]
+    fn test_merkle_root_prove_sorted_pair() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_op_trace(RANDOM_SEED, true);
+
+        let chip: MerkleRootChip<HEIGHT, 32> = MerkleRootChip {
+            ..Default::default()
+        };
+
+        prove_and_verify(&chip, trace, vec![])
+    }
+
+    #[test]
+    fn test_merkle_root_sorted_pair_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 100;
+
+        let trace = generate_op_trace(RANDOM_SEED, true);
+
+        let chip: MerkleRootChip<HEIGHT, 32> = MerkleRootChip {
+            ..Default::default()
+        };
+
+        let col_map = MerkleRootCols::<usize, HEIGHT, 32>::col_map();
+        let cols = [
+            col_map.diff_marker.as_slice(),
+            &[
+                col_map.is_right_child,
+                col_map.sorts_pair,
+                col_map.node_is_greater,
+                col_map.abs_diff,
+            ],
+        ]
+        .concat();
+        let mutations = random_mutations(0..HEIGHT, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
 }
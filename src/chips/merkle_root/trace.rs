@@ -1,10 +1,98 @@
+use alloc::collections::BTreeMap;
+use core::borrow::Borrow;
+
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
 use p3_matrix::dense::RowMajorMatrix;
-use p3_symmetric::CompressionFunction;
+use p3_symmetric::{CompressionFunction, CryptographicHasher};
 use tracing::instrument;
 
-use super::{columns::MerkleRootCols, MerkleRootChip};
+use super::{
+    columns::{MerkleRootCols, LEAF_INDEX_BYTES},
+    MerkleRootChip,
+};
+use crate::chips::{
+    keccak_sponge::{columns::KECCAK_DIGEST_BYTES, trace::KeccakSpongeOp},
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    MEMORY_WORD_BYTES,
+};
+
+/// The data a leaf is the hash of. The data is read from memory and hashed by
+/// memory operations of the sponge, which write the leaf back to memory for the
+/// path to read.
+#[derive(Clone, Debug, Default)]
+pub struct LeafPreimage {
+    /// Timestamp of the data read.
+    pub timestamp: u32,
+    /// Word address of the data.
+    pub addr: u32,
+    /// Word address the leaf is written to. With `double_hash`, the first hash is
+    /// written there too, and then hashed in place.
+    pub dst_addr: u32,
+    pub data: Vec<u8>,
+    /// Whether the leaf is `keccak(keccak(data))`, as in OpenZeppelin's
+    /// `StandardMerkleTree`, rather than `keccak(data)`.
+    pub double_hash: bool,
+}
+
+impl LeafPreimage {
+    pub fn leaf_hash(&self) -> [u8; KECCAK_DIGEST_BYTES] {
+        let hash = Keccak256Hash.hash_iter(self.data.iter().copied());
+        if self.double_hash {
+            Keccak256Hash.hash_iter(hash)
+        } else {
+            hash
+        }
+    }
+
+    /// Returns the sponge operations computing the leaf, one per hash. Each of them
+    /// reads its input at its timestamp and writes its digest at the next one.
+    pub fn sponge_ops(&self) -> Vec<KeccakSpongeOp> {
+        let first = KeccakSpongeOp {
+            timestamp: self.timestamp,
+            addr: self.addr,
+            dst_addr: self.dst_addr,
+            is_memory_op: true,
+            input: self.data.clone(),
+        };
+        if !self.double_hash {
+            return vec![first];
+        }
+
+        let second = KeccakSpongeOp {
+            timestamp: self.timestamp + 2,
+            addr: self.dst_addr,
+            dst_addr: self.dst_addr,
+            is_memory_op: true,
+            input: Keccak256Hash.hash_iter(self.data.iter().copied()).to_vec(),
+        };
+        vec![first, second]
+    }
+
+    /// Timestamp at which the leaf is read, after the last hash wrote it.
+    pub fn leaf_timestamp(&self) -> u32 {
+        self.timestamp + if self.double_hash { 4 } else { 2 }
+    }
+}
+
+/// Where the root of a path is written to memory, followed by the leaf hash and a
+/// word holding the leaf index, so that the output says which leaf the root is of.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RootWrite {
+    /// Timestamp of the write, after the leaf is read.
+    pub timestamp: u32,
+    /// Word address the root is written to.
+    pub addr: u32,
+}
+
+impl RootWrite {
+    /// Number of words written for digests of `digest_width` bytes: the root, the
+    /// leaf hash and the leaf index.
+    pub const fn num_words(digest_width: usize) -> usize {
+        2 * digest_width / MEMORY_WORD_BYTES + 1
+    }
+}
 
 #[derive(Clone)]
 pub struct MerkleRootOp<T, const DEPTH: usize, const DIGEST_WIDTH: usize>
@@ -14,6 +102,142 @@ where
     pub leaf_index: usize,
     pub leaf_hash: [T; DIGEST_WIDTH],
     pub siblings: [[T; DIGEST_WIDTH]; DEPTH],
+    /// Id of the sponge operation hashing the first level. The level `i` is hashed
+    /// by the operation `first_hash_id + i`.
+    pub first_hash_id: usize,
+    /// The preimage of `leaf_hash`, if the leaf is hashed by the machine rather than
+    /// given.
+    pub leaf_preimage: Option<LeafPreimage>,
+    /// Where the root is written to memory, if anywhere.
+    pub root_write: Option<RootWrite>,
+    /// Whether each pair is hashed in sorted order, as in OpenZeppelin's
+    /// `MerkleProof`, rather than in the order given by the bits of `leaf_index`,
+    /// which is then ignored.
+    pub sorted_pair: bool,
+}
+
+impl<T, const DEPTH: usize, const DIGEST_WIDTH: usize> MerkleRootOp<T, DEPTH, DIGEST_WIDTH>
+where
+    T: Default + Copy + Into<u32>,
+{
+    /// Whether `node` is the right child at the level `round`.
+    fn is_right_child(&self, round: usize, node: &[T; DIGEST_WIDTH]) -> usize {
+        if self.sorted_pair {
+            let node = node.iter().map(|&byte| byte.into());
+            let sibling = self.siblings[round].iter().map(|&byte| byte.into());
+            node.gt(sibling) as usize
+        } else {
+            (self.leaf_index >> round) & 1
+        }
+    }
+
+    /// Returns the `(left, right)` pair hashed at each level, from the leaf up.
+    pub fn pairs<Compress>(&self, hasher: &Compress) -> Vec<([T; DIGEST_WIDTH], [T; DIGEST_WIDTH])>
+    where
+        Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
+    {
+        let mut node = self.leaf_hash;
+        (0..DEPTH)
+            .map(|round| {
+                let sibling = self.siblings[round];
+                let pair = if self.is_right_child(round, &node) == 0 {
+                    (node, sibling)
+                } else {
+                    (sibling, node)
+                };
+                node = hasher.compress([pair.0, pair.1]);
+                pair
+            })
+            .collect()
+    }
+
+    pub fn root<Compress>(&self, hasher: &Compress) -> [T; DIGEST_WIDTH]
+    where
+        Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
+    {
+        let (left, right) = *self.pairs(hasher).last().unwrap();
+        hasher.compress([left, right])
+    }
+
+    /// Returns the index of the leaf along the path, whose bit `i` tells whether the
+    /// node at the level `i` is the right child. This is `leaf_index`, truncated to
+    /// `DEPTH` bits, unless the pairs are sorted.
+    pub fn accumulated_index<Compress>(&self, hasher: &Compress) -> usize
+    where
+        Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
+    {
+        let mut node = self.leaf_hash;
+        (0..DEPTH)
+            .map(|round| {
+                let sibling = self.siblings[round];
+                let is_right_child = self.is_right_child(round, &node);
+                node = if is_right_child == 0 {
+                    hasher.compress([node, sibling])
+                } else {
+                    hasher.compress([sibling, node])
+                };
+                is_right_child << round
+            })
+            .sum()
+    }
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize> MerkleRootOp<u8, DEPTH, DIGEST_WIDTH> {
+    /// Returns the sponge operations hashing the levels, in the order of their ids.
+    pub fn sponge_ops<Compress>(&self, hasher: &Compress) -> Vec<KeccakSpongeOp>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        self.pairs(hasher)
+            .into_iter()
+            .map(|(left, right)| KeccakSpongeOp {
+                input: left.into_iter().chain(right).collect(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Returns the bytes written by a `RootWrite`: the root, the leaf hash and the
+    /// word holding the little-endian leaf index.
+    pub fn output<Compress>(&self, hasher: &Compress) -> Vec<u8>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let index = self.accumulated_index(hasher) as u32;
+        let mut index_word = [0; MEMORY_WORD_BYTES];
+        index_word[..LEAF_INDEX_BYTES].copy_from_slice(&index.to_le_bytes());
+        [self.root(hasher).as_slice(), &self.leaf_hash, &index_word].concat()
+    }
+
+    /// Returns the memory reads of the leaf hash, if it has a preimage, and the
+    /// memory writes of the root, the leaf hash and the leaf index, if they have a
+    /// destination.
+    pub fn memory_ops<Compress>(&self, hasher: &Compress) -> Vec<MemoryOp<MEMORY_WORD_BYTES>>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+
+        let reads = self.leaf_preimage.iter().flat_map(|preimage| {
+            (0..words_per_digest).map(|k| MemoryOp {
+                addr: preimage.dst_addr + k as u32,
+                timestamp: preimage.leaf_timestamp(),
+                value: image_word(&self.leaf_hash, k),
+                kind: OperationKind::Read,
+            })
+        });
+        let output = self.output(hasher);
+        let writes = self.root_write.iter().flat_map(|root_write| {
+            let output = &output;
+            (0..RootWrite::num_words(DIGEST_WIDTH)).map(move |k| MemoryOp {
+                addr: root_write.addr + k as u32,
+                timestamp: root_write.timestamp,
+                value: image_word(output, k),
+                kind: OperationKind::Write,
+            })
+        });
+        reads.chain(writes).collect()
+    }
 }
 
 impl<T, const DEPTH: usize, const DIGEST_WIDTH: usize> Default
@@ -26,6 +250,10 @@ where
             leaf_index: 0,
             leaf_hash: [T::default(); DIGEST_WIDTH],
             siblings: [[T::default(); DIGEST_WIDTH]; DEPTH],
+            first_hash_id: 0,
+            leaf_preimage: None,
+            root_write: None,
+            sorted_pair: false,
         }
     }
 }
@@ -37,7 +265,7 @@ impl<const DEPTH: usize, const DIGEST_WIDTH: usize> MerkleRootChip<DEPTH, DIGEST
         hasher: &Compress,
     ) -> RowMajorMatrix<F>
     where
-        F: PrimeField32,
+        F: PrimeField64,
         T: Default + Copy + Into<u32>,
         Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
     {
@@ -68,12 +296,29 @@ impl<const DEPTH: usize, const DIGEST_WIDTH: usize> MerkleRootChip<DEPTH, DIGEST
         trace
     }
 
+    /// Returns the multiplicities of the bytes range checked by `trace`.
+    pub fn generate_range_counts<F: PrimeField64>(trace: &RowMajorMatrix<F>) -> BTreeMap<u32, u32> {
+        let mut count = BTreeMap::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &MerkleRootCols<F, DEPTH, DIGEST_WIDTH> = row.borrow();
+            if row.is_real.is_zero() {
+                continue;
+            }
+            let has_marker = row.diff_marker.iter().copied().sum::<F>();
+            count
+                .entry((row.abs_diff - has_marker).as_canonical_u64() as u32)
+                .and_modify(|c| *c += 1)
+                .or_insert(1);
+        }
+        count
+    }
+
     pub fn populate_rows_for_ops<F, T, Compress>(
         rows: &mut [&mut MerkleRootCols<F, DEPTH, DIGEST_WIDTH>],
         ops: &[MerkleRootOp<T, DEPTH, DIGEST_WIDTH>],
         hasher: &Compress,
     ) where
-        F: PrimeField32,
+        F: PrimeField64,
         T: Default + Copy + Into<u32>,
         Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
     {
@@ -87,13 +332,25 @@ impl<const DEPTH: usize, const DIGEST_WIDTH: usize> MerkleRootChip<DEPTH, DIGEST
         op: &MerkleRootOp<T, DEPTH, DIGEST_WIDTH>,
         hasher: &Compress,
     ) where
-        F: PrimeField32,
+        F: PrimeField64,
         T: Default + Copy + Into<u32>,
         Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
     {
         generate_rows_for_op(rows, op, hasher);
         for row in rows.iter_mut() {
             row.is_real = F::one();
+            row.sorts_pair = F::from_bool(op.sorted_pair);
+        }
+        if let Some(preimage) = &op.leaf_preimage {
+            rows[0].reads_leaf = F::one();
+            rows[0].leaf_timestamp = F::from_canonical_u32(preimage.leaf_timestamp());
+            rows[0].leaf_addr = F::from_canonical_u32(preimage.dst_addr);
+        }
+        if let Some(root_write) = &op.root_write {
+            let final_row = rows.last_mut().unwrap();
+            final_row.writes_root = F::one();
+            final_row.root_timestamp = F::from_canonical_u32(root_write.timestamp);
+            final_row.root_addr = F::from_canonical_u32(root_write.addr);
         }
     }
 }
@@ -103,48 +360,56 @@ pub fn generate_rows_for_op<F, T, Compress, const DEPTH: usize, const DIGEST_WID
     op: &MerkleRootOp<T, DEPTH, DIGEST_WIDTH>,
     hasher: &Compress,
 ) where
-    F: PrimeField32,
+    F: PrimeField64,
     T: Default + Copy + Into<u32>,
     Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
 {
     let MerkleRootOp {
-        leaf_index,
         leaf_hash,
         siblings,
+        first_hash_id,
+        ..
     } = op;
 
-    // Fill the first row with the leaf.
+    for (round, row) in rows.iter_mut().enumerate() {
+        row.hash_id = F::from_canonical_usize(first_hash_id + round);
+    }
+
+    // Fill the first row with the leaf, which every row keeps.
     for (node_byte, &leaf_hash_byte) in rows[0].node.iter_mut().zip(leaf_hash.iter()) {
         *node_byte = F::from_canonical_u32(leaf_hash_byte.into());
     }
+    for row in rows.iter_mut() {
+        row.leaf = leaf_hash.map(|byte| F::from_canonical_u32(byte.into()));
+    }
 
-    let mut node = generate_trace_row_for_round(
-        rows[0],
-        0,
-        leaf_index & 1,
-        leaf_index & 1,
-        leaf_hash,
-        &siblings[0],
-        hasher,
-    );
-
-    for round in 1..rows.len() {
-        // Copy previous row's output to next row's input.
-        for i in 0..DIGEST_WIDTH {
-            rows[round].node[i] = rows[round - 1].output[i];
+    let mut node = *leaf_hash;
+    let mut accumulated_index = 0;
+    for round in 0..rows.len() {
+        if round > 0 {
+            // Copy previous row's output to next row's input.
+            for i in 0..DIGEST_WIDTH {
+                rows[round].node[i] = rows[round - 1].output[i];
+            }
         }
 
-        let mask = (1 << (round + 1)) - 1;
+        let is_right_child = op.is_right_child(round, &node);
+        accumulated_index |= is_right_child << round;
         node = generate_trace_row_for_round(
             rows[round],
             round,
-            leaf_index & mask,
-            (leaf_index >> round) & 1,
+            accumulated_index,
+            is_right_child,
             &node,
             &siblings[round],
             hasher,
         );
     }
+
+    // Fill the final row with the bytes of the leaf index.
+    let final_row = rows.last_mut().unwrap();
+    final_row.index_bytes =
+        core::array::from_fn(|i| F::from_canonical_usize((accumulated_index >> (8 * i)) & 0xff));
 }
 
 pub fn generate_trace_row_for_round<F, T, Compress, const DEPTH: usize, const DIGEST_WIDTH: usize>(
@@ -157,7 +422,7 @@ pub fn generate_trace_row_for_round<F, T, Compress, const DEPTH: usize, const DI
     hasher: &Compress,
 ) -> [T; DIGEST_WIDTH]
 where
-    F: PrimeField32,
+    F: PrimeField64,
     T: Default + Copy + Into<u32>,
     Compress: CompressionFunction<[T; DIGEST_WIDTH], 2>,
 {
@@ -173,6 +438,14 @@ where
 
     row.is_right_child = F::from_canonical_usize(is_right_child);
     row.accumulated_index = F::from_canonical_usize(accumulated_index);
+
+    let first_diff = (0..DIGEST_WIDTH).find(|&i| node[i].into() != sibling[i].into());
+    if let Some(i) = first_diff {
+        let (node_byte, sibling_byte) = (node[i].into(), sibling[i].into());
+        row.diff_marker[i] = F::one();
+        row.node_is_greater = F::from_bool(node_byte > sibling_byte);
+        row.abs_diff = F::from_canonical_u32(node_byte.abs_diff(sibling_byte));
+    }
     for i in 0..DIGEST_WIDTH {
         row.sibling[i] = F::from_canonical_u32(sibling[i].into());
 
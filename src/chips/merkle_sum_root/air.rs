@@ -0,0 +1,103 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_air_util::builders::SubRangeAirBuilder;
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use crate::airs::step_flags::StepFlagsAir;
+
+use super::{columns::MerkleSumRootCols, MerkleSumRootChip};
+
+impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> BaseAir<F>
+    for MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+{
+    fn width(&self) -> usize {
+        MerkleSumRootCols::<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>::num_cols()
+    }
+}
+
+impl<AB, const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> Air<AB>
+    for MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+where
+    AB: AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let col_map = MerkleSumRootCols::<AB::Var, DEPTH, DIGEST_WIDTH, SUM_BYTES>::col_map();
+
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MerkleSumRootCols<AB::Var, DEPTH, DIGEST_WIDTH, SUM_BYTES> = (*local).borrow();
+        let next: &MerkleSumRootCols<AB::Var, DEPTH, DIGEST_WIDTH, SUM_BYTES> = (*next).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_right_child);
+        builder.assert_bool(local.writes_root);
+
+        let step_flags_air = StepFlagsAir::<DEPTH>;
+        let mut sub_builder = SubRangeAirBuilder::new_main(builder, col_map.step_flags.as_range());
+        step_flags_air.eval(&mut sub_builder);
+
+        let is_final_step = local.step_flags.flags[DEPTH - 1];
+
+        // A path is either real or padding as a whole, and only its root is written.
+        builder
+            .when_transition()
+            .when_ne(is_final_step, AB::Expr::one())
+            .assert_eq(local.is_real, next.is_real);
+        builder.assert_eq(local.writes_root, local.is_real * is_final_step);
+
+        // Left and right nodes and sums are selected correctly.
+        let pairs = (0..DIGEST_WIDTH)
+            .map(|i| {
+                (
+                    local.node[i],
+                    local.sibling[i],
+                    local.left[i],
+                    local.right[i],
+                )
+            })
+            .chain((0..SUM_BYTES).map(|i| {
+                (
+                    local.node_sum[i],
+                    local.sibling_sum[i],
+                    local.left_sum[i],
+                    local.right_sum[i],
+                )
+            }));
+        for (node, sibling, left, right) in pairs {
+            let diff = node - sibling;
+            builder.assert_eq(node - local.is_right_child * diff.clone(), left);
+            builder.assert_eq(sibling + local.is_right_child * diff, right);
+        }
+
+        // The output sum is the sum of the children, byte by byte from the least
+        // significant one. It doesn't overflow, so the most significant byte has no
+        // carry.
+        for i in 0..SUM_BYTES {
+            builder.assert_bool(local.sum_carry[i]);
+            let carry_in = if i + 1 < SUM_BYTES {
+                local.sum_carry[i + 1].into()
+            } else {
+                AB::Expr::zero()
+            };
+            builder.assert_eq(
+                local.node_sum[i] + local.sibling_sum[i] + carry_in,
+                local.output_sum[i] + local.sum_carry[i] * AB::Expr::from_canonical_u32(1 << 8),
+            );
+        }
+        builder.assert_zero(local.sum_carry[0]);
+
+        // Output is copied to the next row.
+        for (&output, &next_node) in local
+            .output
+            .iter()
+            .chain(local.output_sum.iter())
+            .zip(next.node.iter().chain(next.node_sum.iter()))
+        {
+            builder
+                .when_transition()
+                .when_ne(is_final_step, AB::Expr::one())
+                .assert_eq(output, next_node);
+        }
+    }
+}
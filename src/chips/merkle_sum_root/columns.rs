@@ -0,0 +1,57 @@
+use p3_derive::Columnar;
+
+use crate::airs::step_flags::StepFlagsCols;
+
+/// A row per level of a path, from the leaf up. Sums are big-endian bytes, as in
+/// `abi.encodePacked`.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct MerkleSumRootCols<
+    T,
+    const DEPTH: usize,
+    const DIGEST_WIDTH: usize,
+    const SUM_BYTES: usize,
+> {
+    pub is_real: T,
+
+    pub step_flags: StepFlagsCols<T, DEPTH>,
+
+    /// Id of the sponge operation that hashes `left || left_sum || right || right_sum`.
+    pub hash_id: T,
+
+    pub node: [T; DIGEST_WIDTH],
+
+    pub node_sum: [T; SUM_BYTES],
+
+    pub sibling: [T; DIGEST_WIDTH],
+
+    pub sibling_sum: [T; SUM_BYTES],
+
+    pub is_right_child: T,
+
+    pub left: [T; DIGEST_WIDTH],
+
+    pub left_sum: [T; SUM_BYTES],
+
+    pub right: [T; DIGEST_WIDTH],
+
+    pub right_sum: [T; SUM_BYTES],
+
+    pub output: [T; DIGEST_WIDTH],
+
+    pub output_sum: [T; SUM_BYTES],
+
+    /// Carry out of each byte of `node_sum + sibling_sum` into the more significant
+    /// one.
+    pub sum_carry: [T; SUM_BYTES],
+
+    /// Whether this row writes the root and its sum to memory, which the last level
+    /// of a real path does.
+    pub writes_root: T,
+
+    /// Timestamp of the root write.
+    pub timestamp: T,
+
+    /// Word address of the root, which is followed by its sum.
+    pub root_addr: T,
+}
@@ -0,0 +1,123 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::MerkleSumRootCols, MerkleSumRootChip};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    MEMORY_WORD_BYTES,
+};
+
+impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> BaseInteractionAir<F>
+    for MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+where
+    F: Field,
+{
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map =
+            MerkleSumRootCols::<_, DEPTH, DIGEST_WIDTH, SUM_BYTES>::from_slice(main_indices);
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
+                .output
+                .into_iter()
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(col_map.is_real),
+            self.bus_hasher_output,
+        )]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map =
+            MerkleSumRootCols::<_, DEPTH, DIGEST_WIDTH, SUM_BYTES>::from_slice(main_indices);
+        let is_real = VirtualPairCol::single_main(col_map.is_real);
+
+        // The sums of the children are hashed and range checked by the sponge, and the
+        // output sum is the child sum of the next level, except at the root.
+        let range_checks = col_map
+            .output_sum
+            .into_iter()
+            .map(|byte| Interaction {
+                fields: vec![VirtualPairCol::single_main(byte)],
+                count: is_real.clone(),
+                argument_index: self.bus_range_8,
+            })
+            .collect_vec();
+
+        // The root is written to `root_addr`, followed by its sum.
+        let root_writes = col_map
+            .output
+            .into_iter()
+            .chain(col_map.output_sum)
+            .collect_vec()
+            .chunks(MEMORY_WORD_BYTES)
+            .enumerate()
+            .map(|(k, word)| Interaction {
+                fields: once(VirtualPairCol::single_main(col_map.timestamp))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(col_map.root_addr, F::one())],
+                        F::from_canonical_usize(k),
+                    )))
+                    .chain(word.iter().map(|&byte| VirtualPairCol::single_main(byte)))
+                    .chain(once(VirtualPairCol::constant(F::one())))
+                    .collect(),
+                count: VirtualPairCol::single_main(col_map.writes_root),
+                argument_index: self.bus_memory,
+            })
+            .collect_vec();
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                col_map
+                    .left
+                    .into_iter()
+                    .chain(col_map.left_sum)
+                    .chain(col_map.right)
+                    .chain(col_map.right_sum)
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                is_real,
+                self.bus_hasher_input,
+            ),
+            range_checks,
+            root_writes,
+        ]
+        .concat()
+    }
+}
+
+impl<F, const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> InteractionAir<F>
+    for MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+where
+    F: Field,
+{
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = MerkleSumRootCols::<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = MerkleSumRootCols::<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB, const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> Rap<AB>
+    for MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+where
+    AB: InteractionAirBuilder,
+{
+}
@@ -0,0 +1,161 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+
+pub use trace::{MerkleSumRootOp, SumNode};
+
+/// Proves the path of a leaf in a Merkle sum tree, where each node is
+/// `keccak(left_hash || left_sum || right_hash || right_sum)` and carries the sum of
+/// its children's sums. The root and its sum, the total of all the leaves, are
+/// written to memory.
+#[derive(Default, Clone, Debug)]
+pub struct MerkleSumRootChip<const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize>
+{
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+
+    pub bus_range_8: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> p3_air_util::AirLogger
+    for MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+{
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::MerkleSumRootCols::<usize, DEPTH, DIGEST_WIDTH, SUM_BYTES>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::MerkleSumRootCols::<usize, DEPTH, DIGEST_WIDTH, SUM_BYTES>::headers_and_types(
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::MerkleSumRootCols;
+
+    use itertools::Itertools;
+    use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const HEIGHT: usize = 4;
+    const SUM_BYTES: usize = 8;
+
+    type Node = SumNode<32, SUM_BYTES>;
+
+    /// A random leaf with a sum small enough for the sums of the tree not to
+    /// overflow.
+    fn random_leaf(rng: &mut StdRng) -> Node {
+        let sum: u64 = rng.gen_range(0..u64::MAX >> HEIGHT);
+        Node {
+            hash: rng.gen(),
+            sum: sum.to_be_bytes(),
+        }
+    }
+
+    fn generate_op(seed: u64) -> MerkleSumRootOp<HEIGHT, 32, SUM_BYTES> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut level = (0..1 << HEIGHT)
+            .map(|_| random_leaf(&mut rng))
+            .collect_vec();
+        let leaf_index = rng.gen_range(0..level.len());
+        let leaf = level[leaf_index];
+
+        let mut siblings = vec![];
+        let mut index = leaf_index;
+        while level.len() > 1 {
+            siblings.push(level[index ^ 1]);
+            level = level
+                .chunks_exact(2)
+                .map(|pair| Node::parent(&pair[0], &pair[1], &Keccak256Hash).unwrap())
+                .collect();
+            index >>= 1;
+        }
+
+        MerkleSumRootOp {
+            leaf_index,
+            leaf,
+            siblings: siblings.try_into().unwrap(),
+            first_hash_id: 0,
+            timestamp: 0,
+            root_addr: 0,
+        }
+    }
+
+    fn generate_op_trace(seed: u64) -> RowMajorMatrix<Val> {
+        MerkleSumRootChip::generate_trace(&[generate_op(seed)], &Keccak256Hash)
+    }
+
+    #[test]
+    fn test_merkle_sum_root_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_op_trace(RANDOM_SEED);
+        let chip = MerkleSumRootChip::<HEIGHT, 32, SUM_BYTES>::default();
+        prove_and_verify(&chip, trace, vec![])
+    }
+
+    #[test]
+    fn test_merkle_sum_root_total() {
+        const RANDOM_SEED: u64 = 0;
+
+        let op = generate_op(RANDOM_SEED);
+        let total: u64 = op
+            .siblings
+            .iter()
+            .chain([&op.leaf])
+            .map(|node| u64::from_be_bytes(node.sum))
+            .sum();
+        assert_eq!(op.root(&Keccak256Hash).sum, total.to_be_bytes());
+    }
+
+    #[test]
+    fn test_merkle_sum_root_overflow() {
+        let max = Node {
+            hash: [0; 32],
+            sum: [0xff; SUM_BYTES],
+        };
+        let one = Node {
+            hash: [0; 32],
+            sum: 1u64.to_be_bytes(),
+        };
+        assert_eq!(Node::parent(&max, &one, &Keccak256Hash), None);
+        assert!(Node::parent(&max, &Node::default(), &Keccak256Hash).is_some());
+    }
+
+    #[test]
+    fn test_merkle_sum_root_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 100;
+
+        let trace = generate_op_trace(RANDOM_SEED);
+        let chip = MerkleSumRootChip::<HEIGHT, 32, SUM_BYTES>::default();
+
+        let col_map = MerkleSumRootCols::<usize, HEIGHT, 32, SUM_BYTES>::col_map();
+        let cols = [
+            col_map.node_sum.as_slice(),
+            col_map.sibling_sum.as_slice(),
+            col_map.left_sum.as_slice(),
+            col_map.right_sum.as_slice(),
+            col_map.output_sum.as_slice(),
+            col_map.sum_carry.as_slice(),
+            col_map.output.as_slice(),
+            &[col_map.is_right_child, col_map.writes_root, col_map.is_real],
+        ]
+        .concat();
+        let mutations = random_mutations(0..HEIGHT, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+}
@@ -0,0 +1,273 @@
+use alloc::collections::BTreeMap;
+use core::borrow::Borrow;
+
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use tracing::instrument;
+
+use super::{columns::MerkleSumRootCols, MerkleSumRootChip};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    MEMORY_WORD_BYTES,
+};
+
+/// A node of a sum tree: a hash and the sum of the leaves below it, as big-endian
+/// bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SumNode<const DIGEST_WIDTH: usize, const SUM_BYTES: usize> {
+    pub hash: [u8; DIGEST_WIDTH],
+    pub sum: [u8; SUM_BYTES],
+}
+
+impl<const DIGEST_WIDTH: usize, const SUM_BYTES: usize> Default
+    for SumNode<DIGEST_WIDTH, SUM_BYTES>
+{
+    fn default() -> Self {
+        Self {
+            hash: [0; DIGEST_WIDTH],
+            sum: [0; SUM_BYTES],
+        }
+    }
+}
+
+impl<const DIGEST_WIDTH: usize, const SUM_BYTES: usize> SumNode<DIGEST_WIDTH, SUM_BYTES> {
+    /// The bytes hashed by the parent of `left` and `right`.
+    pub fn hash_input(left: &Self, right: &Self) -> Vec<u8> {
+        [left.hash.as_slice(), &left.sum, &right.hash, &right.sum].concat()
+    }
+
+    /// Returns the parent of `left` and `right`, or `None` if their sum overflows.
+    pub fn parent<H>(left: &Self, right: &Self, hasher: &H) -> Option<Self>
+    where
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        let mut sum = [0; SUM_BYTES];
+        let mut carry = 0;
+        for i in (0..SUM_BYTES).rev() {
+            let byte_sum = left.sum[i] as u32 + right.sum[i] as u32 + carry;
+            sum[i] = byte_sum as u8;
+            carry = byte_sum >> 8;
+        }
+        (carry == 0).then(|| Self {
+            hash: hasher.hash_iter(Self::hash_input(left, right)),
+            sum,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct MerkleSumRootOp<const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize> {
+    pub leaf_index: usize,
+    pub leaf: SumNode<DIGEST_WIDTH, SUM_BYTES>,
+    pub siblings: [SumNode<DIGEST_WIDTH, SUM_BYTES>; DEPTH],
+    /// Id of the sponge operation hashing the first level. The level `i` is hashed
+    /// by the operation `first_hash_id + i`.
+    pub first_hash_id: usize,
+    /// Timestamp of the root write.
+    pub timestamp: u32,
+    /// Word address the root is written to, followed by its sum.
+    pub root_addr: u32,
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize>
+    MerkleSumRootOp<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+{
+    /// Returns the `(left, right)` pair hashed at each level, from the leaf up, and
+    /// the root.
+    ///
+    /// Panics if a sum overflows.
+    pub fn pairs<H>(
+        &self,
+        hasher: &H,
+    ) -> (
+        Vec<(
+            SumNode<DIGEST_WIDTH, SUM_BYTES>,
+            SumNode<DIGEST_WIDTH, SUM_BYTES>,
+        )>,
+        SumNode<DIGEST_WIDTH, SUM_BYTES>,
+    )
+    where
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        let mut node = self.leaf;
+        let pairs = self
+            .siblings
+            .iter()
+            .enumerate()
+            .map(|(round, sibling)| {
+                let pair = if (self.leaf_index >> round) & 1 == 0 {
+                    (node, *sibling)
+                } else {
+                    (*sibling, node)
+                };
+                node = SumNode::parent(&pair.0, &pair.1, hasher).expect("sum overflows");
+                pair
+            })
+            .collect();
+        (pairs, node)
+    }
+
+    pub fn root<H>(&self, hasher: &H) -> SumNode<DIGEST_WIDTH, SUM_BYTES>
+    where
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        self.pairs(hasher).1
+    }
+
+    /// Returns the sponge operations hashing the levels, in the order of their ids.
+    pub fn sponge_ops<H>(&self, hasher: &H) -> Vec<KeccakSpongeOp>
+    where
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        self.pairs(hasher)
+            .0
+            .iter()
+            .map(|(left, right)| KeccakSpongeOp {
+                input: SumNode::hash_input(left, right),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Returns the memory writes of the root and its sum.
+    pub fn memory_ops<H>(&self, hasher: &H) -> Vec<MemoryOp<MEMORY_WORD_BYTES>>
+    where
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        let root = self.root(hasher);
+        let bytes = [root.hash.as_slice(), &root.sum].concat();
+        (0..bytes.len() / MEMORY_WORD_BYTES)
+            .map(|k| MemoryOp {
+                addr: self.root_addr + k as u32,
+                timestamp: self.timestamp,
+                value: image_word(&bytes, k),
+                kind: OperationKind::Write,
+            })
+            .collect()
+    }
+}
+
+impl<const DEPTH: usize, const DIGEST_WIDTH: usize, const SUM_BYTES: usize>
+    MerkleSumRootChip<DEPTH, DIGEST_WIDTH, SUM_BYTES>
+{
+    #[instrument(name = "generate MerkleSumRoot trace", skip_all)]
+    pub fn generate_trace<F, H>(
+        operations: &[MerkleSumRootOp<DEPTH, DIGEST_WIDTH, SUM_BYTES>],
+        hasher: &H,
+    ) -> RowMajorMatrix<F>
+    where
+        F: PrimeField64,
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        let num_cols = MerkleSumRootCols::<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>::num_cols();
+
+        let num_real_rows = operations.len() * DEPTH;
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<MerkleSumRootCols<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>>()
+        };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        // The step flags cycle through the padding rows too, which are otherwise zero.
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.step_flags.flags[i % DEPTH] = F::one();
+        }
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations, hasher);
+
+        trace
+    }
+
+    /// Returns the multiplicities of the bytes range checked by `trace`.
+    pub fn generate_range_counts<F: PrimeField64>(trace: &RowMajorMatrix<F>) -> BTreeMap<u32, u32> {
+        let mut count = BTreeMap::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &MerkleSumRootCols<F, DEPTH, DIGEST_WIDTH, SUM_BYTES> = row.borrow();
+            if row.is_real.is_zero() {
+                continue;
+            }
+            for byte in row.output_sum {
+                count
+                    .entry(byte.as_canonical_u64() as u32)
+                    .and_modify(|c| *c += 1)
+                    .or_insert(1);
+            }
+        }
+        count
+    }
+
+    pub fn populate_rows_for_ops<F, H>(
+        rows: &mut [&mut MerkleSumRootCols<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>],
+        ops: &[MerkleSumRootOp<DEPTH, DIGEST_WIDTH, SUM_BYTES>],
+        hasher: &H,
+    ) where
+        F: PrimeField64,
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        for (leaf_rows, op) in rows.chunks_mut(DEPTH).zip(ops.iter()) {
+            Self::populate_rows_for_op(leaf_rows, op, hasher);
+        }
+    }
+
+    pub fn populate_rows_for_op<F, H>(
+        rows: &mut [&mut MerkleSumRootCols<F, DEPTH, DIGEST_WIDTH, SUM_BYTES>],
+        op: &MerkleSumRootOp<DEPTH, DIGEST_WIDTH, SUM_BYTES>,
+        hasher: &H,
+    ) where
+        F: PrimeField64,
+        H: CryptographicHasher<u8, [u8; DIGEST_WIDTH]>,
+    {
+        let bytes = |bytes: &[u8]| bytes.iter().map(|&byte| F::from_canonical_u8(byte));
+
+        let (pairs, _) = op.pairs(hasher);
+        let mut node = op.leaf;
+        for (round, (row, (left, right))) in rows.iter_mut().zip(pairs).enumerate() {
+            let is_right_child = (op.leaf_index >> round) & 1 == 1;
+            let sibling = op.siblings[round];
+            let output = SumNode::parent(&left, &right, hasher).unwrap();
+
+            row.is_real = F::one();
+            row.hash_id = F::from_canonical_usize(op.first_hash_id + round);
+            row.is_right_child = F::from_bool(is_right_child);
+            for (cols, values) in [
+                (row.node.as_mut_slice(), node.hash.as_slice()),
+                (row.sibling.as_mut_slice(), sibling.hash.as_slice()),
+                (row.left.as_mut_slice(), left.hash.as_slice()),
+                (row.right.as_mut_slice(), right.hash.as_slice()),
+                (row.output.as_mut_slice(), output.hash.as_slice()),
+                (row.node_sum.as_mut_slice(), node.sum.as_slice()),
+                (row.sibling_sum.as_mut_slice(), sibling.sum.as_slice()),
+                (row.left_sum.as_mut_slice(), left.sum.as_slice()),
+                (row.right_sum.as_mut_slice(), right.sum.as_slice()),
+                (row.output_sum.as_mut_slice(), output.sum.as_slice()),
+            ] {
+                for (col, value) in cols.iter_mut().zip(bytes(values)) {
+                    *col = value;
+                }
+            }
+
+            // The carry out of each byte, from the least significant one.
+            let mut carry = 0;
+            for i in (0..SUM_BYTES).rev() {
+                carry = (node.sum[i] as u32 + sibling.sum[i] as u32 + carry) >> 8;
+                row.sum_carry[i] = F::from_canonical_u32(carry);
+            }
+
+            node = output;
+        }
+
+        let root_row = &mut rows[DEPTH - 1];
+        root_row.writes_root = F::one();
+        root_row.timestamp = F::from_canonical_u32(op.timestamp);
+        root_row.root_addr = F::from_canonical_u32(op.root_addr);
+    }
+}
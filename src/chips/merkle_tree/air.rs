@@ -0,0 +1,125 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use super::{columns::MerkleTreeCols, MerkleTreeChip};
+
+impl<F, const DIGEST_WIDTH: usize> BaseAir<F> for MerkleTreeChip<DIGEST_WIDTH> {
+    fn width(&self) -> usize {
+        MerkleTreeCols::<F, DIGEST_WIDTH>::num_cols()
+    }
+}
+
+impl<AB: AirBuilder, const DIGEST_WIDTH: usize> Air<AB> for MerkleTreeChip<DIGEST_WIDTH> {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MerkleTreeCols<AB::Var, DIGEST_WIDTH> = (*local).borrow();
+        let next: &MerkleTreeCols<AB::Var, DIGEST_WIDTH> = (*next).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_root);
+        builder.assert_bool(local.is_bottom);
+        builder.assert_bool(local.is_level_start);
+        builder.when(local.is_root).assert_one(local.is_real);
+        builder.when(local.is_bottom).assert_one(local.is_real);
+
+        // Real rows come first, and the first of them is a root.
+        builder
+            .when_transition()
+            .when(next.is_real)
+            .assert_one(local.is_real);
+        builder
+            .when_first_row()
+            .assert_eq(local.is_root, local.is_real);
+        builder.when_first_row().assert_zero(local.tree_id);
+
+        // A tree starts at its root...
+        builder.when(local.is_root).assert_one(local.node_index);
+        builder
+            .when(local.is_root)
+            .when(local.is_bottom)
+            .assert_eq(local.num_leaves, AB::Expr::two());
+
+        // ... and every following row of the tree is the next node.
+        let next_is_same_tree = next.is_real - next.is_root;
+        builder
+            .when_transition()
+            .when(next_is_same_tree.clone())
+            .assert_eq(next.node_index, local.node_index + AB::Expr::one());
+        for (next_col, local_col) in [
+            (next.tree_id, local.tree_id),
+            (next.timestamp, local.timestamp),
+            (next.leaves_addr, local.leaves_addr),
+            (next.root_addr, local.root_addr),
+            (next.num_leaves, local.num_leaves),
+        ] {
+            builder
+                .when_transition()
+                .when(next_is_same_tree.clone())
+                .assert_eq(next_col, local_col);
+        }
+        builder
+            .when_transition()
+            .when(next.is_root)
+            .assert_eq(next.tree_id, local.tree_id + AB::Expr::one());
+
+        // Sponge operations are consecutive across trees.
+        builder
+            .when_transition()
+            .when(next.is_real)
+            .assert_eq(next.hash_id, local.hash_id + AB::Expr::one());
+
+        // Each level is twice the size of the one above it, and starts at the node
+        // whose index is its size. The levels are therefore powers of two.
+        builder.when(local.is_root).assert_one(local.level_size);
+        builder
+            .when_transition()
+            .when(next_is_same_tree.clone())
+            .assert_eq(
+                next.level_size,
+                local.level_size * (AB::Expr::one() + next.is_level_start),
+            );
+        builder
+            .when(local.is_level_start)
+            .assert_eq(local.node_index, local.level_size);
+
+        // The nodes whose children are leaves are the last `num_leaves / 2` ones, so
+        // the first of them is node `num_leaves / 2`, which starts a level so that
+        // `num_leaves` is a power of two...
+        builder
+            .when_transition()
+            .when(next_is_same_tree.clone())
+            .when(local.is_bottom)
+            .assert_one(next.is_bottom);
+        let is_first_bottom = (AB::Expr::one() - local.is_bottom) * next.is_bottom;
+        builder
+            .when_transition()
+            .when(is_first_bottom.clone())
+            .assert_eq(next.node_index * AB::Expr::two(), next.num_leaves);
+        builder
+            .when_transition()
+            .when(is_first_bottom)
+            .assert_one(next.is_level_start);
+
+        // ... and the last node of a tree is node `num_leaves - 1`.
+        let is_last_node = local.is_real - next_is_same_tree;
+        builder
+            .when_transition()
+            .when(is_last_node.clone())
+            .assert_one(local.is_bottom);
+        builder
+            .when_transition()
+            .when(is_last_node)
+            .assert_eq(local.node_index + AB::Expr::one(), local.num_leaves);
+        builder
+            .when_last_row()
+            .when(local.is_real)
+            .assert_one(local.is_bottom);
+        builder
+            .when_last_row()
+            .when(local.is_real)
+            .assert_eq(local.node_index + AB::Expr::one(), local.num_leaves);
+    }
+}
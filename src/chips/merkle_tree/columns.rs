@@ -0,0 +1,48 @@
+use p3_derive::Columnar;
+
+/// A row per internal node of a tree, in heap order: the root is node 1 and the
+/// children of node `i` are nodes `2i` and `2i + 1`. With `num_leaves` leaves, the
+/// internal nodes are `1..num_leaves` and the leaves are `num_leaves..2 * num_leaves`.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct MerkleTreeCols<T, const DIGEST_WIDTH: usize> {
+    pub is_real: T,
+
+    /// Whether this row is the root, which is the first row of its tree.
+    pub is_root: T,
+
+    /// Whether the children of this node are leaves, read from memory.
+    pub is_bottom: T,
+
+    /// Index of the tree in the trace, used to tell their nodes apart.
+    pub tree_id: T,
+
+    /// Timestamp of the leaf reads. The root is written at the next timestamp.
+    pub timestamp: T,
+
+    /// Word address of the first leaf. The leaves are stored contiguously.
+    pub leaves_addr: T,
+
+    /// Word address the root is written to.
+    pub root_addr: T,
+
+    pub num_leaves: T,
+
+    pub node_index: T,
+
+    /// Whether `node_index` is the first node of its level, a power of two.
+    pub is_level_start: T,
+
+    /// Number of nodes on the level of this node, the largest power of two that is
+    /// at most `node_index`.
+    pub level_size: T,
+
+    /// Id of the sponge operation that hashes `left || right`.
+    pub hash_id: T,
+
+    pub left: [T; DIGEST_WIDTH],
+
+    pub right: [T; DIGEST_WIDTH],
+
+    pub output: [T; DIGEST_WIDTH],
+}
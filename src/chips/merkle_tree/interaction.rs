@@ -0,0 +1,175 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::MerkleTreeCols, MerkleTreeChip};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    MEMORY_WORD_BYTES,
+};
+
+impl<F: Field, const DIGEST_WIDTH: usize> BaseInteractionAir<F> for MerkleTreeChip<DIGEST_WIDTH> {
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = MerkleTreeCols::<_, DIGEST_WIDTH>::from_slice(main_indices);
+
+        // Nodes whose children aren't leaves receive them from the rows of the
+        // children.
+        let is_inner = VirtualPairCol::new_main(
+            vec![(col_map.is_real, F::one()), (col_map.is_bottom, -F::one())],
+            F::zero(),
+        );
+        let children = [col_map.left, col_map.right]
+            .into_iter()
+            .enumerate()
+            .map(|(i, child)| Interaction {
+                fields: once(VirtualPairCol::single_main(col_map.tree_id))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(col_map.node_index, F::two())],
+                        F::from_canonical_usize(i),
+                    )))
+                    .chain(child.into_iter().map(VirtualPairCol::single_main))
+                    .collect(),
+                count: is_inner.clone(),
+                argument_index: self.bus_node,
+            })
+            .collect_vec();
+
+        [
+            children,
+            vec![sponge_output_interaction(
+                VirtualPairCol::single_main(col_map.hash_id),
+                col_map
+                    .output
+                    .into_iter()
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                VirtualPairCol::single_main(col_map.is_real),
+                self.bus_hasher_output,
+            )],
+        ]
+        .concat()
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = MerkleTreeCols::<_, DIGEST_WIDTH>::from_slice(main_indices);
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+
+        // Every node but the root sends its digest to its parent.
+        let output = Interaction {
+            fields: [col_map.tree_id, col_map.node_index]
+                .into_iter()
+                .chain(col_map.output)
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            count: VirtualPairCol::new_main(
+                vec![(col_map.is_real, F::one()), (col_map.is_root, -F::one())],
+                F::zero(),
+            ),
+            argument_index: self.bus_node,
+        };
+
+        // The children of node `i` are the leaves `2i - num_leaves` and
+        // `2i + 1 - num_leaves`, and the word `k` of leaf `j` is at
+        // `leaves_addr + j * words_per_digest + k`.
+        let leaf_reads = [col_map.left, col_map.right]
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, child)| {
+                child
+                    .chunks(MEMORY_WORD_BYTES)
+                    .enumerate()
+                    .map(|(k, word)| Interaction {
+                        fields: once(VirtualPairCol::single_main(col_map.timestamp))
+                            .chain(once(VirtualPairCol::new_main(
+                                vec![
+                                    (col_map.leaves_addr, F::one()),
+                                    (
+                                        col_map.node_index,
+                                        F::from_canonical_usize(2 * words_per_digest),
+                                    ),
+                                    (
+                                        col_map.num_leaves,
+                                        -F::from_canonical_usize(words_per_digest),
+                                    ),
+                                ],
+                                F::from_canonical_usize(i * words_per_digest + k),
+                            )))
+                            .chain(word.iter().map(|&byte| VirtualPairCol::single_main(byte)))
+                            .chain(once(VirtualPairCol::constant(F::zero())))
+                            .collect(),
+                        count: VirtualPairCol::single_main(col_map.is_bottom),
+                        argument_index: self.bus_memory,
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        // The root is written at the timestamp following the leaf reads.
+        let root_writes = col_map
+            .output
+            .chunks(MEMORY_WORD_BYTES)
+            .enumerate()
+            .map(|(k, word)| Interaction {
+                fields: once(VirtualPairCol::new_main(
+                    vec![(col_map.timestamp, F::one())],
+                    F::one(),
+                ))
+                .chain(once(VirtualPairCol::new_main(
+                    vec![(col_map.root_addr, F::one())],
+                    F::from_canonical_usize(k),
+                )))
+                .chain(word.iter().map(|&byte| VirtualPairCol::single_main(byte)))
+                .chain(once(VirtualPairCol::constant(F::one())))
+                .collect(),
+                count: VirtualPairCol::single_main(col_map.is_root),
+                argument_index: self.bus_memory,
+            })
+            .collect_vec();
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                col_map
+                    .left
+                    .into_iter()
+                    .chain(col_map.right)
+                    .map(VirtualPairCol::single_main)
+                    .collect(),
+                VirtualPairCol::single_main(col_map.is_real),
+                self.bus_hasher_input,
+            ),
+            vec![output],
+            leaf_reads,
+            root_writes,
+        ]
+        .concat()
+    }
+}
+
+impl<F: Field, const DIGEST_WIDTH: usize> InteractionAir<F> for MerkleTreeChip<DIGEST_WIDTH> {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = MerkleTreeCols::<F, DIGEST_WIDTH>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = MerkleTreeCols::<F, DIGEST_WIDTH>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB: InteractionAirBuilder, const DIGEST_WIDTH: usize> Rap<AB>
+    for MerkleTreeChip<DIGEST_WIDTH>
+{
+}
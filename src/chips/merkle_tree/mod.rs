@@ -0,0 +1,125 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+pub mod trace;
+
+pub use trace::MerkleTreeOp;
+
+/// Proves the roots of whole trees, built level by level from leaves stored in a
+/// memory region. Each internal node is a row that hashes its children over the
+/// sponge buses and passes its digest up to its parent over `bus_node`, and the
+/// root is written back to memory.
+#[derive(Default, Clone, Debug)]
+pub struct MerkleTreeChip<const DIGEST_WIDTH: usize> {
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_node: usize,
+
+    pub bus_memory: usize,
+}
+
+#[cfg(feature = "air-logger")]
+impl<const DIGEST_WIDTH: usize> p3_air_util::AirLogger for MerkleTreeChip<DIGEST_WIDTH> {
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::MerkleTreeCols::<usize, DIGEST_WIDTH>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::MerkleTreeCols::<usize, DIGEST_WIDTH>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, bus_messages, prove_and_verify, random_mutations},
+    };
+    use columns::MerkleTreeCols;
+
+    use itertools::Itertools;
+    use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_symmetric::CompressionFunctionFromHasher;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_op(num_leaves: usize, first_hash_id: usize, seed: u64) -> MerkleTreeOp<32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        MerkleTreeOp {
+            timestamp: 0,
+            leaves_addr: 0,
+            root_addr: (num_leaves * 4) as u32,
+            first_hash_id,
+            leaves: (0..num_leaves).map(|_| rng.gen()).collect_vec(),
+        }
+    }
+
+    fn generate_ops_trace(ops: &[MerkleTreeOp<32>]) -> RowMajorMatrix<Val> {
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        MerkleTreeChip::generate_trace(ops, &hasher)
+    }
+
+    fn chip() -> MerkleTreeChip<32> {
+        MerkleTreeChip {
+            bus_hasher_input: 0,
+            bus_hasher_output: 1,
+            bus_node: 2,
+            bus_memory: 3,
+        }
+    }
+
+    #[test]
+    fn test_merkle_tree_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_ops_trace(&[random_op(16, 0, RANDOM_SEED)]);
+        prove_and_verify(&chip(), trace, vec![])
+    }
+
+    #[test]
+    fn test_merkle_tree_prove_multiple_trees() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let ops = [
+            random_op(16, 0, RANDOM_SEED),
+            random_op(2, 15, RANDOM_SEED + 1),
+            random_op(4, 16, RANDOM_SEED + 2),
+        ];
+        let trace = generate_ops_trace(&ops);
+        prove_and_verify(&chip(), trace, vec![])
+    }
+
+    #[test]
+    fn test_merkle_tree_nodes_balance() {
+        const RANDOM_SEED: u64 = 0;
+
+        let ops = [random_op(16, 0, RANDOM_SEED), random_op(8, 15, RANDOM_SEED)];
+        let trace = generate_ops_trace(&ops);
+
+        // Every digest sent to a parent is received by it. The messages carry the tree
+        // id, so the trees can share leaves.
+        let chip = chip();
+        let unmatched = bus_messages(&chip, &trace)
+            .into_keys()
+            .filter(|(bus, _)| *bus == chip.bus_node)
+            .collect_vec();
+        assert!(unmatched.is_empty(), "Unmatched nodes: {unmatched:?}");
+    }
+
+    #[test]
+    fn test_merkle_tree_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let ops = [random_op(16, 0, RANDOM_SEED), random_op(4, 15, RANDOM_SEED)];
+        let trace = generate_ops_trace(&ops);
+
+        let cols = (0..MerkleTreeCols::<Val, 32>::num_cols()).collect_vec();
+        let mutations = random_mutations(0..18, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip(), &trace, &mutations);
+    }
+}
@@ -0,0 +1,179 @@
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CompressionFunction;
+use tracing::instrument;
+
+use super::{columns::MerkleTreeCols, MerkleTreeChip};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    MEMORY_WORD_BYTES, MERKLE_TREE_MAX_LEAVES,
+};
+
+#[derive(Clone, Default)]
+pub struct MerkleTreeOp<const DIGEST_WIDTH: usize> {
+    /// Timestamp of the leaf reads. The root is written at the next timestamp.
+    pub timestamp: u32,
+    /// Word address of the first leaf.
+    pub leaves_addr: u32,
+    /// Word address the root is written to.
+    pub root_addr: u32,
+    /// Id of the sponge operation hashing node 1, the root. Node `i` is hashed by
+    /// the operation `first_hash_id + i - 1`.
+    pub first_hash_id: usize,
+    /// The leaves, whose number must be a power of two.
+    pub leaves: Vec<[u8; DIGEST_WIDTH]>,
+}
+
+impl<const DIGEST_WIDTH: usize> MerkleTreeOp<DIGEST_WIDTH> {
+    /// Returns the digests of the tree in heap order: the root is at index 1, the
+    /// children of node `i` are at `2i` and `2i + 1`, and the leaves come last.
+    /// Index 0 is unused.
+    pub fn nodes<Compress>(&self, hasher: &Compress) -> Vec<[u8; DIGEST_WIDTH]>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let num_leaves = self.leaves.len();
+        assert!(
+            num_leaves.is_power_of_two() && (2..=MERKLE_TREE_MAX_LEAVES).contains(&num_leaves),
+            "a tree has a power of two of leaves between 2 and {MERKLE_TREE_MAX_LEAVES}"
+        );
+
+        let mut nodes = vec![[0; DIGEST_WIDTH]; num_leaves];
+        nodes.extend_from_slice(&self.leaves);
+        for i in (1..num_leaves).rev() {
+            nodes[i] = hasher.compress([nodes[2 * i], nodes[2 * i + 1]]);
+        }
+        nodes
+    }
+
+    pub fn root<Compress>(&self, hasher: &Compress) -> [u8; DIGEST_WIDTH]
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        self.nodes(hasher)[1]
+    }
+
+    /// Returns the sponge operations hashing the internal nodes, in the order of
+    /// their ids.
+    pub fn sponge_ops<Compress>(&self, hasher: &Compress) -> Vec<KeccakSpongeOp>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let num_leaves = self.leaves.len();
+        let nodes = self.nodes(hasher);
+        (1..num_leaves)
+            .map(|i| KeccakSpongeOp {
+                input: nodes[2 * i].into_iter().chain(nodes[2 * i + 1]).collect(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Returns the memory accesses of the tree: every leaf word is read at
+    /// `timestamp`, and the root is written at `timestamp + 1`.
+    pub fn memory_ops<Compress>(&self, hasher: &Compress) -> Vec<MemoryOp<MEMORY_WORD_BYTES>>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+
+        let reads = self.leaves.iter().enumerate().flat_map(|(j, leaf)| {
+            (0..words_per_digest).map(move |k| MemoryOp {
+                addr: self.leaves_addr + (j * words_per_digest + k) as u32,
+                timestamp: self.timestamp,
+                value: image_word(leaf, k),
+                kind: OperationKind::Read,
+            })
+        });
+        let root = self.root(hasher);
+        let writes = (0..words_per_digest).map(|k| MemoryOp {
+            addr: self.root_addr + k as u32,
+            timestamp: self.timestamp + 1,
+            value: image_word(&root, k),
+            kind: OperationKind::Write,
+        });
+        reads.chain(writes).collect()
+    }
+}
+
+impl<const DIGEST_WIDTH: usize> MerkleTreeChip<DIGEST_WIDTH> {
+    #[instrument(name = "generate MerkleTree trace", skip_all)]
+    pub fn generate_trace<F, Compress>(
+        operations: &[MerkleTreeOp<DIGEST_WIDTH>],
+        hasher: &Compress,
+    ) -> RowMajorMatrix<F>
+    where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let num_cols = MerkleTreeCols::<F, DIGEST_WIDTH>::num_cols();
+        let num_real_rows = operations
+            .iter()
+            .map(|op| op.leaves.len() - 1)
+            .sum::<usize>();
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<MerkleTreeCols<F, DIGEST_WIDTH>>()
+        };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations, hasher);
+
+        trace
+    }
+
+    pub fn populate_rows_for_ops<F, Compress>(
+        rows: &mut [&mut MerkleTreeCols<F, DIGEST_WIDTH>],
+        ops: &[MerkleTreeOp<DIGEST_WIDTH>],
+        hasher: &Compress,
+    ) where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let mut offset = 0;
+        for (tree_id, op) in ops.iter().enumerate() {
+            let len = op.leaves.len() - 1;
+            Self::populate_rows_for_op(&mut rows[offset..offset + len], tree_id, op, hasher);
+            offset += len;
+        }
+    }
+
+    /// Generates the rows of the internal nodes of a tree, from the root down.
+    pub fn populate_rows_for_op<F, Compress>(
+        rows: &mut [&mut MerkleTreeCols<F, DIGEST_WIDTH>],
+        tree_id: usize,
+        op: &MerkleTreeOp<DIGEST_WIDTH>,
+        hasher: &Compress,
+    ) where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let num_leaves = op.leaves.len();
+        let nodes = op.nodes(hasher);
+        for (row, node_index) in rows.iter_mut().zip(1..num_leaves) {
+            row.is_real = F::one();
+            row.is_root = F::from_bool(node_index == 1);
+            row.is_bottom = F::from_bool(2 * node_index >= num_leaves);
+            row.tree_id = F::from_canonical_usize(tree_id);
+            row.timestamp = F::from_canonical_u32(op.timestamp);
+            row.leaves_addr = F::from_canonical_u32(op.leaves_addr);
+            row.root_addr = F::from_canonical_u32(op.root_addr);
+            row.num_leaves = F::from_canonical_usize(num_leaves);
+            row.node_index = F::from_canonical_usize(node_index);
+            row.is_level_start = F::from_bool(node_index.is_power_of_two());
+            row.level_size = F::from_canonical_usize(1 << node_index.ilog2());
+            row.hash_id = F::from_canonical_usize(op.first_hash_id + node_index - 1);
+            row.left = nodes[2 * node_index].map(F::from_canonical_u8);
+            row.right = nodes[2 * node_index + 1].map(F::from_canonical_u8);
+            row.output = nodes[node_index].map(F::from_canonical_u8);
+        }
+    }
+}
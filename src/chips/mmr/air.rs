@@ -0,0 +1,174 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_air_util::builders::SubRangeAirBuilder;
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use crate::airs::step_flags::StepFlagsAir;
+
+use super::{columns::MmrCols, MmrChip};
+
+impl<F, const HEIGHT: usize, const DIGEST_WIDTH: usize> BaseAir<F>
+    for MmrChip<HEIGHT, DIGEST_WIDTH>
+{
+    fn width(&self) -> usize {
+        MmrCols::<F, HEIGHT, DIGEST_WIDTH>::num_cols()
+    }
+}
+
+impl<AB, const HEIGHT: usize, const DIGEST_WIDTH: usize> Air<AB> for MmrChip<HEIGHT, DIGEST_WIDTH>
+where
+    AB: AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let col_map = MmrCols::<AB::Var, HEIGHT, DIGEST_WIDTH>::col_map();
+
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MmrCols<AB::Var, HEIGHT, DIGEST_WIDTH> = (*local).borrow();
+        let next: &MmrCols<AB::Var, HEIGHT, DIGEST_WIDTH> = (*next).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_append);
+        builder.assert_bool(local.is_bagging);
+        builder.assert_bool(local.is_inclusion);
+        builder.assert_bool(local.has_node);
+        builder.assert_bool(local.present);
+        builder.assert_bool(local.new_present);
+        builder.assert_bool(local.is_right_child);
+        builder.assert_bool(local.hashes);
+        builder.assert_eq(
+            local.is_append + local.is_bagging + local.is_inclusion,
+            local.is_real,
+        );
+        builder.when(local.has_node).assert_one(local.is_real);
+        builder.when(local.hashes).assert_one(local.has_node);
+
+        let step_flags_air = StepFlagsAir::<HEIGHT>;
+        let mut sub_builder = SubRangeAirBuilder::new_main(builder, col_map.step_flags.as_range());
+        step_flags_air.eval(&mut sub_builder);
+
+        let is_first_step = local.step_flags.flags[0];
+        let is_final_step = local.step_flags.flags[HEIGHT - 1];
+
+        // An operation spans the rows of all the levels.
+        for (local_col, next_col) in [
+            (local.is_append, next.is_append),
+            (local.is_bagging, next.is_bagging),
+            (local.is_inclusion, next.is_inclusion),
+            (local.timestamp, next.timestamp),
+            (local.mmr_addr, next.mmr_addr),
+            (local.addr, next.addr),
+        ] {
+            builder
+                .when_transition()
+                .when_ne(is_final_step, AB::Expr::one())
+                .assert_eq(local_col, next_col);
+        }
+
+        // Appends and inclusions start from the node they read, and baggings from
+        // nothing. Appends and inclusions can't go past the highest level.
+        builder
+            .when(is_first_step)
+            .assert_eq(local.has_node, local.is_append + local.is_inclusion);
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(is_first_step)
+                .when(local.is_bagging)
+                .assert_zero(local.node[i]);
+        }
+        builder.assert_eq(
+            local.reads_node,
+            is_first_step * (local.is_append + local.is_inclusion),
+        );
+        builder
+            .when(is_final_step)
+            .when(local.is_append + local.is_inclusion)
+            .assert_zero(local.hashes);
+        builder.assert_eq(local.writes_root, is_final_step * local.is_bagging);
+
+        // Appends and baggings merge the node into the peak whenever there are both,
+        // with the peak on the left, and an inclusion hashes the path up to its peak.
+        let merges = local.is_append + local.is_bagging;
+        builder
+            .when(merges.clone())
+            .assert_eq(local.hashes, local.has_node * local.present);
+        builder
+            .when(merges.clone())
+            .assert_one(local.is_right_child);
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(merges.clone())
+                .assert_eq(local.sibling[i], local.peak[i]);
+        }
+
+        // A bagging takes the lowest peak as it is.
+        builder.assert_eq(
+            local.takes_peak,
+            local.is_bagging * local.present * (AB::Expr::one() - local.has_node),
+        );
+
+        // Left and right nodes are selected correctly, and a level that doesn't hash
+        // outputs its node, or the peak it takes.
+        for i in 0..DIGEST_WIDTH {
+            let diff = local.node[i] - local.sibling[i];
+            builder.assert_eq(
+                local.node[i] - local.is_right_child * diff.clone(),
+                local.left[i],
+            );
+            builder.assert_eq(
+                local.sibling[i] + local.is_right_child * diff,
+                local.right[i],
+            );
+
+            builder.when_ne(local.hashes, AB::Expr::one()).assert_eq(
+                local.output[i],
+                local.node[i] + local.takes_peak * (local.peak[i] - local.node[i]),
+            );
+        }
+
+        // The path of an included element ends at a peak of its level.
+        let reaches_peak = local.has_node - local.hashes;
+        builder
+            .when(local.is_inclusion)
+            .when(reaches_peak.clone())
+            .assert_one(local.present);
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(local.is_inclusion)
+                .when(reaches_peak.clone())
+                .assert_eq(local.node[i], local.peak[i]);
+        }
+
+        // An append settles the node in an empty slot, and empties the slot it merges.
+        builder.when(local.is_append).assert_eq(
+            local.new_present,
+            local.present + local.has_node - local.has_node * local.present * AB::Expr::two(),
+        );
+        for i in 0..DIGEST_WIDTH {
+            builder.when(local.is_append).assert_eq(
+                local.new_peak[i],
+                reaches_peak.clone() * local.node[i]
+                    + (AB::Expr::one() - local.has_node) * local.peak[i],
+            );
+        }
+
+        // The node is carried up while it's merged, and a bag from its lowest peak.
+        builder
+            .when_transition()
+            .when_ne(is_final_step, AB::Expr::one())
+            .assert_eq(
+                next.has_node,
+                local.hashes
+                    + local.is_bagging * (local.has_node + local.takes_peak - local.hashes),
+            );
+
+        // Output is copied to the next row.
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when_transition()
+                .when_ne(is_final_step, AB::Expr::one())
+                .assert_eq(local.output[i], next.node[i]);
+        }
+    }
+}
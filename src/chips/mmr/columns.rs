@@ -0,0 +1,77 @@
+use p3_derive::Columnar;
+
+use crate::airs::step_flags::StepFlagsCols;
+
+/// A row per level of an operation, from height 0 up. Row `h` reads the slot of
+/// the peak of height `h` and, unless `hashes`, passes `node` through to the next
+/// level unchanged.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct MmrCols<T, const HEIGHT: usize, const DIGEST_WIDTH: usize> {
+    pub is_real: T,
+
+    pub step_flags: StepFlagsCols<T, HEIGHT>,
+
+    /// Whether the operation appends the leaf at `addr`.
+    pub is_append: T,
+
+    /// Whether the operation bags the peaks into the root written to `addr`.
+    pub is_bagging: T,
+
+    /// Whether the operation proves that the element at `addr` is a leaf of a peak.
+    pub is_inclusion: T,
+
+    /// Timestamp of the reads. The slots and the root are written at the next
+    /// timestamp.
+    pub timestamp: T,
+
+    /// Word address of the slot of the peak of height 0.
+    pub mmr_addr: T,
+
+    /// Word address of the appended leaf, of the root or of the included element.
+    pub addr: T,
+
+    /// Whether this row reads `node` from `addr`, which the first row of an append or
+    /// an inclusion does.
+    pub reads_node: T,
+
+    /// Whether this row writes `output` to `addr`, which the last row of a bagging
+    /// does.
+    pub writes_root: T,
+
+    /// Whether `node` is carried up this level: the leaf being merged into the peaks,
+    /// the bag of the lower peaks or the node on the path of the element.
+    pub has_node: T,
+
+    pub node: [T; DIGEST_WIDTH],
+
+    /// Whether the slot holds a peak.
+    pub present: T,
+
+    pub peak: [T; DIGEST_WIDTH],
+
+    /// The slot written by an append.
+    pub new_present: T,
+
+    pub new_peak: [T; DIGEST_WIDTH],
+
+    /// The peak, or a sibling on the path of an included element.
+    pub sibling: [T; DIGEST_WIDTH],
+
+    pub is_right_child: T,
+
+    /// Whether this row hashes `left || right`.
+    pub hashes: T,
+
+    /// Id of the sponge operation that hashes `left || right`.
+    pub hash_id: T,
+
+    /// Whether a bagging starts from the peak of this level, the lowest one.
+    pub takes_peak: T,
+
+    pub left: [T; DIGEST_WIDTH],
+
+    pub right: [T; DIGEST_WIDTH],
+
+    pub output: [T; DIGEST_WIDTH],
+}
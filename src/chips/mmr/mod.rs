@@ -0,0 +1,210 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+
+pub use trace::{MmrOp, MmrOpKind, MmrPeaks};
+
+use super::MEMORY_WORD_BYTES;
+
+/// Proves operations on a Merkle Mountain Range whose peaks are stored in memory,
+/// one slot per height: appending a leaf, bagging the peaks into the root, and the
+/// inclusion of an element under a peak. An operation takes a row per level, and
+/// hashes over the sponge buses.
+#[derive(Default, Clone, Debug)]
+pub struct MmrChip<const HEIGHT: usize, const DIGEST_WIDTH: usize> {
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+}
+
+impl<const HEIGHT: usize, const DIGEST_WIDTH: usize> MmrChip<HEIGHT, DIGEST_WIDTH> {
+    /// Number of words in the slot of a peak: a word flagging the peak, followed by
+    /// the peak.
+    pub const SLOT_WORDS: usize = 1 + DIGEST_WIDTH / MEMORY_WORD_BYTES;
+}
+
+#[cfg(feature = "air-logger")]
+impl<const HEIGHT: usize, const DIGEST_WIDTH: usize> p3_air_util::AirLogger
+    for MmrChip<HEIGHT, DIGEST_WIDTH>
+{
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::MmrCols::<usize, HEIGHT, DIGEST_WIDTH>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::MmrCols::<usize, HEIGHT, DIGEST_WIDTH>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::MmrCols;
+
+    use itertools::Itertools;
+    use p3_keccak::Keccak256Hash;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher};
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const HEIGHT: usize = 4;
+
+    type Hasher = CompressionFunctionFromHasher<u8, Keccak256Hash, 2, 32>;
+    type Peaks = MmrPeaks<HEIGHT, 32>;
+
+    fn hasher() -> Hasher {
+        CompressionFunctionFromHasher::new(Keccak256Hash)
+    }
+
+    fn random_leaves(num_leaves: usize, seed: u64) -> Vec<[u8; 32]> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..num_leaves).map(|_| rng.gen()).collect()
+    }
+
+    /// An append, a bagging and an inclusion on a range of 11 leaves, whose peaks
+    /// have heights 3, 1 and 0.
+    fn generate_ops(seed: u64) -> Vec<MmrOp<HEIGHT, 32>> {
+        let hasher = hasher();
+        let leaves = random_leaves(12, seed);
+        let peaks = Peaks::from_leaves(&leaves[..11], &hasher);
+
+        // The path of leaf 5 under the peak of the first 8 leaves.
+        let level_1 = leaves[..8]
+            .chunks_exact(2)
+            .map(|pair| hasher.compress([pair[0], pair[1]]))
+            .collect_vec();
+        let level_2 = level_1
+            .chunks_exact(2)
+            .map(|pair| hasher.compress([pair[0], pair[1]]))
+            .collect_vec();
+        let siblings = vec![leaves[4], level_1[3], level_2[0]];
+
+        let append = MmrOp {
+            kind: MmrOpKind::Append { leaf: leaves[11] },
+            peaks,
+            ..Default::default()
+        };
+        let bag = MmrOp {
+            kind: MmrOpKind::Bag,
+            peaks,
+            first_hash_id: 2,
+            ..Default::default()
+        };
+        let inclusion = MmrOp {
+            kind: MmrOpKind::Inclusion {
+                element: leaves[5],
+                index: 5,
+                siblings,
+            },
+            peaks,
+            first_hash_id: 4,
+            ..Default::default()
+        };
+        vec![append, bag, inclusion]
+    }
+
+    fn generate_ops_trace(ops: &[MmrOp<HEIGHT, 32>]) -> RowMajorMatrix<Val> {
+        MmrChip::generate_trace(ops, &hasher())
+    }
+
+    #[test]
+    fn test_mmr_peaks() {
+        const RANDOM_SEED: u64 = 0;
+
+        let hasher = hasher();
+        let leaves = random_leaves(3, RANDOM_SEED);
+        let mut peaks = Peaks::from_leaves(&leaves, &hasher);
+        let pair = hasher.compress([leaves[0], leaves[1]]);
+        assert_eq!(peaks.num_leaves(), 3);
+        assert_eq!(peaks.peaks, [Some(leaves[2]), Some(pair), None, None]);
+        assert_eq!(peaks.root(&hasher), hasher.compress([pair, leaves[2]]));
+
+        // Appending a fourth leaf merges all the peaks.
+        let leaf = random_leaves(4, RANDOM_SEED)[3];
+        peaks.append(leaf, &hasher);
+        let root = hasher.compress([pair, hasher.compress([leaves[2], leaf])]);
+        assert_eq!(peaks.peaks, [None, None, Some(root), None]);
+        assert_eq!(peaks.root(&hasher), root);
+    }
+
+    #[test]
+    #[should_panic(expected = "the node goes past the highest peak")]
+    fn test_mmr_full() {
+        let hasher = hasher();
+        let mut peaks = Peaks::from_leaves(&random_leaves((1 << HEIGHT) - 1, 0), &hasher);
+        peaks.append([0; 32], &hasher);
+    }
+
+    #[test]
+    fn test_mmr_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_ops_trace(&generate_ops(RANDOM_SEED));
+        prove_and_verify(&MmrChip::<HEIGHT, 32>::default(), trace, vec![])
+    }
+
+    #[test]
+    fn test_mmr_op_hashes() {
+        const RANDOM_SEED: u64 = 0;
+
+        let hasher = hasher();
+        let leaves = random_leaves(12, RANDOM_SEED);
+        let ops = generate_ops(RANDOM_SEED);
+        assert_eq!(
+            ops[0].next_peaks(&hasher),
+            Peaks::from_leaves(&leaves, &hasher)
+        );
+        assert_eq!(ops[0].sponge_ops(&hasher).len(), 2);
+        assert_eq!(ops[1].sponge_ops(&hasher).len(), 2);
+        assert_eq!(ops[2].sponge_ops(&hasher).len(), 3);
+    }
+
+    #[test]
+    fn test_mmr_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let trace = generate_ops_trace(&generate_ops(RANDOM_SEED)[..1]);
+        let chip = MmrChip::<HEIGHT, 32>::default();
+
+        // The other columns of an append are free where they aren't used.
+        let col_map = MmrCols::<usize, HEIGHT, 32>::col_map();
+        let cols = [
+            col_map.node.as_slice(),
+            col_map.peak.as_slice(),
+            col_map.new_peak.as_slice(),
+            col_map.sibling.as_slice(),
+            col_map.left.as_slice(),
+            col_map.right.as_slice(),
+            col_map.output.as_slice(),
+            &[
+                col_map.is_real,
+                col_map.is_append,
+                col_map.is_bagging,
+                col_map.is_inclusion,
+                col_map.timestamp,
+                col_map.mmr_addr,
+                col_map.addr,
+                col_map.reads_node,
+                col_map.writes_root,
+                col_map.has_node,
+                col_map.present,
+                col_map.new_present,
+                col_map.is_right_child,
+                col_map.hashes,
+                col_map.takes_peak,
+            ],
+        ]
+        .concat();
+        let mutations = random_mutations(0..HEIGHT, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+}
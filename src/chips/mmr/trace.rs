@@ -0,0 +1,400 @@
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CompressionFunction;
+use tracing::instrument;
+
+use super::{columns::MmrCols, MmrChip};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    MEMORY_WORD_BYTES,
+};
+
+/// The peaks of a Merkle Mountain Range, indexed by height. The peak of height `h`
+/// covers `2^h` leaves, and is there when bit `h` of the number of leaves is set.
+/// The root bags the peaks from the lowest one up: each peak is hashed with the bag
+/// of the lower ones on its right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MmrPeaks<const HEIGHT: usize, const DIGEST_WIDTH: usize> {
+    pub peaks: [Option<[u8; DIGEST_WIDTH]>; HEIGHT],
+}
+
+impl<const HEIGHT: usize, const DIGEST_WIDTH: usize> Default for MmrPeaks<HEIGHT, DIGEST_WIDTH> {
+    fn default() -> Self {
+        Self {
+            peaks: [None; HEIGHT],
+        }
+    }
+}
+
+impl<const HEIGHT: usize, const DIGEST_WIDTH: usize> MmrPeaks<HEIGHT, DIGEST_WIDTH> {
+    pub fn from_leaves<Compress>(leaves: &[[u8; DIGEST_WIDTH]], hasher: &Compress) -> Self
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let mut peaks = Self::default();
+        for &leaf in leaves {
+            peaks.append(leaf, hasher);
+        }
+        peaks
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        (0..HEIGHT)
+            .filter(|&h| self.peaks[h].is_some())
+            .map(|h| 1 << h)
+            .sum()
+    }
+
+    /// Appends `leaf`, merging the peaks of equal height.
+    ///
+    /// Panics if the range is full, with a peak at every height.
+    pub fn append<Compress>(&mut self, leaf: [u8; DIGEST_WIDTH], hasher: &Compress)
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let op = MmrOp {
+            kind: MmrOpKind::Append { leaf },
+            peaks: *self,
+            ..Default::default()
+        };
+        *self = op.next_peaks(hasher);
+    }
+
+    pub fn root<Compress>(&self, hasher: &Compress) -> [u8; DIGEST_WIDTH]
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        self.peaks
+            .iter()
+            .flatten()
+            .fold(None, |bag, &peak| match bag {
+                None => Some(peak),
+                Some(bag) => Some(hasher.compress([peak, bag])),
+            })
+            .unwrap_or([0; DIGEST_WIDTH])
+    }
+
+    /// The memory image of the slots, from the peak of height 0 up. A slot is a word
+    /// whose first byte flags the peak, followed by the peak, or by zeros.
+    pub fn image(&self) -> Vec<u8> {
+        self.peaks
+            .iter()
+            .flat_map(|peak| {
+                let mut slot = vec![0; MEMORY_WORD_BYTES];
+                slot[0] = peak.is_some() as u8;
+                slot.extend(peak.unwrap_or([0; DIGEST_WIDTH]));
+                slot
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum MmrOpKind<const DIGEST_WIDTH: usize> {
+    /// Appends `leaf`, read from `addr`.
+    Append { leaf: [u8; DIGEST_WIDTH] },
+    /// Bags the peaks into the root, written to `addr`.
+    #[default]
+    Bag,
+    /// Proves that `element`, read from `addr`, is the leaf at `index` of the peak
+    /// whose height is the number of `siblings`.
+    Inclusion {
+        element: [u8; DIGEST_WIDTH],
+        index: usize,
+        siblings: Vec<[u8; DIGEST_WIDTH]>,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MmrOp<const HEIGHT: usize, const DIGEST_WIDTH: usize> {
+    pub kind: MmrOpKind<DIGEST_WIDTH>,
+    /// The peaks before the operation, whose slots start at `mmr_addr`.
+    pub peaks: MmrPeaks<HEIGHT, DIGEST_WIDTH>,
+    /// Timestamp of the reads. An append writes the slots, and a bagging the root, at
+    /// the next timestamp.
+    pub timestamp: u32,
+    /// Word address of the slot of the peak of height 0.
+    pub mmr_addr: u32,
+    /// Word address of the appended leaf, of the root or of the included element.
+    pub addr: u32,
+    /// Id of the sponge operation of the first hash. The following hashes, from the
+    /// lowest level up, take the following ids.
+    pub first_hash_id: usize,
+}
+
+/// The values of an operation at one level.
+struct Level<const DIGEST_WIDTH: usize> {
+    has_node: bool,
+    node: [u8; DIGEST_WIDTH],
+    sibling: [u8; DIGEST_WIDTH],
+    is_right_child: bool,
+    hashes: bool,
+    takes_peak: bool,
+    output: [u8; DIGEST_WIDTH],
+    new_peak: Option<[u8; DIGEST_WIDTH]>,
+}
+
+impl<const DIGEST_WIDTH: usize> Level<DIGEST_WIDTH> {
+    fn pair(&self) -> ([u8; DIGEST_WIDTH], [u8; DIGEST_WIDTH]) {
+        if self.is_right_child {
+            (self.sibling, self.node)
+        } else {
+            (self.node, self.sibling)
+        }
+    }
+}
+
+impl<const HEIGHT: usize, const DIGEST_WIDTH: usize> MmrOp<HEIGHT, DIGEST_WIDTH> {
+    /// Returns the values of the operation at each level, from height 0 up.
+    ///
+    /// Panics if an append overflows the range, or if an included element doesn't
+    /// lead to a peak.
+    fn levels<Compress>(&self, hasher: &Compress) -> Vec<Level<DIGEST_WIDTH>>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let (mut has_node, mut node) = match &self.kind {
+            MmrOpKind::Append { leaf } => (true, *leaf),
+            MmrOpKind::Bag => (false, [0; DIGEST_WIDTH]),
+            MmrOpKind::Inclusion { element, .. } => (true, *element),
+        };
+
+        (0..HEIGHT)
+            .map(|h| {
+                let peak = self.peaks.peaks[h];
+                let (sibling, is_right_child, hashes) = match &self.kind {
+                    MmrOpKind::Inclusion {
+                        index, siblings, ..
+                    } => match siblings.get(h) {
+                        Some(&sibling) => (sibling, (index >> h) & 1 == 1, true),
+                        None => {
+                            if h == siblings.len() {
+                                assert_eq!(peak, Some(node), "the element isn't under a peak");
+                            }
+                            ([0; DIGEST_WIDTH], false, false)
+                        }
+                    },
+                    _ => (
+                        peak.unwrap_or([0; DIGEST_WIDTH]),
+                        true,
+                        has_node && peak.is_some(),
+                    ),
+                };
+                let takes_peak = matches!(self.kind, MmrOpKind::Bag) && !has_node && peak.is_some();
+                assert!(
+                    !(hashes && h == HEIGHT - 1 && !matches!(self.kind, MmrOpKind::Bag)),
+                    "the node goes past the highest peak"
+                );
+
+                let mut level = Level {
+                    has_node,
+                    node,
+                    sibling,
+                    is_right_child,
+                    hashes,
+                    takes_peak,
+                    output: node,
+                    new_peak: match (has_node, hashes) {
+                        (true, true) => None,
+                        (true, false) => Some(node),
+                        (false, _) => peak,
+                    },
+                };
+                if hashes {
+                    let (left, right) = level.pair();
+                    level.output = hasher.compress([left, right]);
+                } else if takes_peak {
+                    level.output = peak.unwrap();
+                }
+
+                has_node = match self.kind {
+                    MmrOpKind::Bag => has_node || peak.is_some(),
+                    _ => hashes,
+                };
+                node = level.output;
+                level
+            })
+            .collect()
+    }
+
+    /// The peaks after the operation, which only an append changes.
+    pub fn next_peaks<Compress>(&self, hasher: &Compress) -> MmrPeaks<HEIGHT, DIGEST_WIDTH>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        match self.kind {
+            MmrOpKind::Append { .. } => MmrPeaks {
+                peaks: self
+                    .levels(hasher)
+                    .iter()
+                    .map(|level| level.new_peak)
+                    .collect_vec()
+                    .try_into()
+                    .unwrap(),
+            },
+            _ => self.peaks,
+        }
+    }
+
+    /// Returns the sponge operations of the hashes, in the order of their ids.
+    pub fn sponge_ops<Compress>(&self, hasher: &Compress) -> Vec<KeccakSpongeOp>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        self.levels(hasher)
+            .iter()
+            .filter(|level| level.hashes)
+            .map(|level| {
+                let (left, right) = level.pair();
+                KeccakSpongeOp {
+                    input: left.into_iter().chain(right).collect(),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the memory accesses of the operation: the slots are read at
+    /// `timestamp`, along with the appended leaf or the included element, and an
+    /// append writes the slots, and a bagging the root, at `timestamp + 1`.
+    pub fn memory_ops<Compress>(&self, hasher: &Compress) -> Vec<MemoryOp<MEMORY_WORD_BYTES>>
+    where
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let words = |addr: u32, bytes: &[u8], timestamp: u32, kind: OperationKind| {
+            (0..bytes.len() / MEMORY_WORD_BYTES)
+                .map(|k| MemoryOp {
+                    addr: addr + k as u32,
+                    timestamp,
+                    value: image_word(bytes, k),
+                    kind: kind.clone(),
+                })
+                .collect_vec()
+        };
+
+        let mut ops = words(
+            self.mmr_addr,
+            &self.peaks.image(),
+            self.timestamp,
+            OperationKind::Read,
+        );
+        match &self.kind {
+            MmrOpKind::Append { leaf } => {
+                ops.extend(words(self.addr, leaf, self.timestamp, OperationKind::Read));
+                ops.extend(words(
+                    self.mmr_addr,
+                    &self.next_peaks(hasher).image(),
+                    self.timestamp + 1,
+                    OperationKind::Write,
+                ));
+            }
+            MmrOpKind::Bag => ops.extend(words(
+                self.addr,
+                &self.peaks.root(hasher),
+                self.timestamp + 1,
+                OperationKind::Write,
+            )),
+            MmrOpKind::Inclusion { element, .. } => ops.extend(words(
+                self.addr,
+                element,
+                self.timestamp,
+                OperationKind::Read,
+            )),
+        }
+        ops
+    }
+}
+
+impl<const HEIGHT: usize, const DIGEST_WIDTH: usize> MmrChip<HEIGHT, DIGEST_WIDTH> {
+    #[instrument(name = "generate Mmr trace", skip_all)]
+    pub fn generate_trace<F, Compress>(
+        operations: &[MmrOp<HEIGHT, DIGEST_WIDTH>],
+        hasher: &Compress,
+    ) -> RowMajorMatrix<F>
+    where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let num_cols = MmrCols::<F, HEIGHT, DIGEST_WIDTH>::num_cols();
+
+        let num_real_rows = operations.len() * HEIGHT;
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe {
+            trace
+                .values
+                .align_to_mut::<MmrCols<F, HEIGHT, DIGEST_WIDTH>>()
+        };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        // The step flags cycle through the padding rows too, which are otherwise zero.
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.step_flags.flags[i % HEIGHT] = F::one();
+        }
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations, hasher);
+
+        trace
+    }
+
+    pub fn populate_rows_for_ops<F, Compress>(
+        rows: &mut [&mut MmrCols<F, HEIGHT, DIGEST_WIDTH>],
+        ops: &[MmrOp<HEIGHT, DIGEST_WIDTH>],
+        hasher: &Compress,
+    ) where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        for (op_rows, op) in rows.chunks_mut(HEIGHT).zip(ops.iter()) {
+            Self::populate_rows_for_op(op_rows, op, hasher);
+        }
+    }
+
+    pub fn populate_rows_for_op<F, Compress>(
+        rows: &mut [&mut MmrCols<F, HEIGHT, DIGEST_WIDTH>],
+        op: &MmrOp<HEIGHT, DIGEST_WIDTH>,
+        hasher: &Compress,
+    ) where
+        F: PrimeField64,
+        Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    {
+        let bytes = |bytes: [u8; DIGEST_WIDTH]| bytes.map(F::from_canonical_u8);
+
+        let mut hash_id = op.first_hash_id;
+        for (h, (row, level)) in rows.iter_mut().zip(op.levels(hasher)).enumerate() {
+            let peak = op.peaks.peaks[h];
+            let (left, right) = level.pair();
+
+            row.is_real = F::one();
+            row.is_append = F::from_bool(matches!(op.kind, MmrOpKind::Append { .. }));
+            row.is_bagging = F::from_bool(matches!(op.kind, MmrOpKind::Bag));
+            row.is_inclusion = F::from_bool(matches!(op.kind, MmrOpKind::Inclusion { .. }));
+            row.timestamp = F::from_canonical_u32(op.timestamp);
+            row.mmr_addr = F::from_canonical_u32(op.mmr_addr);
+            row.addr = F::from_canonical_u32(op.addr);
+            row.reads_node = F::from_bool(h == 0 && !matches!(op.kind, MmrOpKind::Bag));
+            row.writes_root = F::from_bool(h == HEIGHT - 1 && matches!(op.kind, MmrOpKind::Bag));
+            row.has_node = F::from_bool(level.has_node);
+            row.node = bytes(level.node);
+            row.present = F::from_bool(peak.is_some());
+            row.peak = bytes(peak.unwrap_or([0; DIGEST_WIDTH]));
+            row.new_present = F::from_bool(level.new_peak.is_some());
+            row.new_peak = bytes(level.new_peak.unwrap_or([0; DIGEST_WIDTH]));
+            row.sibling = bytes(level.sibling);
+            row.is_right_child = F::from_bool(level.is_right_child);
+            row.hashes = F::from_bool(level.hashes);
+            row.hash_id = F::from_canonical_usize(hash_id);
+            row.takes_peak = F::from_bool(level.takes_peak);
+            row.left = bytes(left);
+            row.right = bytes(right);
+            row.output = bytes(level.output);
+
+            hash_id += level.hashes as usize;
+        }
+    }
+}
@@ -1,29 +1,85 @@
 use core::fmt::Debug;
 use p3_derive::EnumDispatch;
 
+use crate::{
+    airs::keccak::{bits_per_limb, u64_limbs},
+    config::VAL_BITS,
+};
+
+pub mod byte_memory;
+pub mod contract_address;
+pub mod eip712;
+pub mod eth_address;
+pub mod incremental_merkle_tree;
 pub mod keccak_permute;
 pub mod keccak_sponge;
 pub mod memory;
+pub mod memory_image;
 pub mod merkle_root;
+pub mod merkle_sum_root;
+pub mod merkle_tree;
+pub mod mmr;
 pub mod range_checker;
+pub mod storage_slot;
 pub mod xor;
 
 use self::{
+    byte_memory::ByteMemoryChip, contract_address::ContractAddressChip, eip712::Eip712Chip,
+    eth_address::EthAddressChip, incremental_merkle_tree::IncrementalMerkleTreeChip,
     keccak_permute::KeccakPermuteChip, keccak_sponge::KeccakSpongeChip, memory::MemoryChip,
-    merkle_root::MerkleRootChip, range_checker::RangeCheckerChip, xor::XorChip,
+    memory_image::MemoryImageChip, merkle_root::MerkleRootChip, merkle_sum_root::MerkleSumRootChip,
+    merkle_tree::MerkleTreeChip, mmr::MmrChip, range_checker::RangeCheckerChip,
+    storage_slot::StorageSlotChip, xor::XorChip,
 };
 
 pub const MERKLE_TREE_DEPTH: usize = 8;
 pub const DIGEST_WIDTH: usize = 32;
+/// Maximum number of leaves of a tree proven by the `MerkleTree` chip.
+pub const MERKLE_TREE_MAX_LEAVES: usize = 1 << 16;
+/// Number of bytes of the sums of a Merkle sum tree, which are `u128`s.
+pub const MERKLE_SUM_BYTES: usize = 16;
+/// Number of peak heights of a Merkle Mountain Range, which holds fewer than
+/// `2^MMR_HEIGHT` leaves.
+pub const MMR_HEIGHT: usize = 32;
+/// Depth of an incremental Merkle tree, which holds fewer than
+/// `2^INCREMENTAL_TREE_DEPTH` leaves, as the Ethereum deposit contract does.
+pub const INCREMENTAL_TREE_DEPTH: usize = 32;
+/// Number of bytes of an uncompressed secp256k1 public key `x || y`, without the
+/// `0x04` prefix.
+pub const PUBLIC_KEY_BYTES: usize = 64;
+/// Number of bytes of an Ethereum address.
+pub const ETH_ADDRESS_BYTES: usize = 20;
 pub const MAX_U8: u32 = 256;
 pub const NUM_BYTES: usize = 2;
+/// Limbs per Keccak lane, as wide as `Val` allows. The machine keeps these limbs on
+/// the other fields it is proven over, which are at least as wide.
+pub const KECCAK_U64_LIMBS: usize = u64_limbs(VAL_BITS);
+// The sponge exchanges lanes with the permutation as 16-bit limbs.
+const _: () = assert!(
+    bits_per_limb(KECCAK_U64_LIMBS) == 16,
+    "the sponge needs 16-bit Keccak limbs"
+);
+/// Number of bytes in a memory word, which is also the size of a Keccak lane.
+pub const MEMORY_WORD_BYTES: usize = 8;
 
 #[derive(Clone, Debug, EnumDispatch)]
 pub enum KeccakMachineChip {
-    KeccakPermute(KeccakPermuteChip),
+    KeccakPermute(KeccakPermuteChip<KECCAK_U64_LIMBS>),
     KeccakSponge(KeccakSpongeChip),
     MerkleRoot(MerkleRootChip<MERKLE_TREE_DEPTH, DIGEST_WIDTH>),
+    MerkleRoot16(MerkleRootChip<16, DIGEST_WIDTH>),
+    MerkleRoot32(MerkleRootChip<32, DIGEST_WIDTH>),
     Range8(RangeCheckerChip<MAX_U8>),
     Xor(XorChip<2>),
-    Memory(MemoryChip),
+    Memory(MemoryChip<MEMORY_WORD_BYTES>),
+    MemoryImage(MemoryImageChip<MEMORY_WORD_BYTES>),
+    ByteMemory(ByteMemoryChip<MEMORY_WORD_BYTES>),
+    MerkleTree(MerkleTreeChip<DIGEST_WIDTH>),
+    MerkleSumRoot(MerkleSumRootChip<MERKLE_TREE_DEPTH, DIGEST_WIDTH, MERKLE_SUM_BYTES>),
+    Mmr(MmrChip<MMR_HEIGHT, DIGEST_WIDTH>),
+    IncrementalMerkleTree(IncrementalMerkleTreeChip<INCREMENTAL_TREE_DEPTH, DIGEST_WIDTH>),
+    EthAddress(EthAddressChip),
+    ContractAddress(ContractAddressChip),
+    StorageSlot(StorageSlotChip),
+    Eip712(Eip712Chip),
 }
@@ -32,20 +32,23 @@ impl<const MAX: u32> p3_air_util::AirLogger for RangeCheckerChip<MAX> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::prove_and_verify;
+    use crate::test_util::{assert_mutations_rejected, prove_and_verify, random_mutations};
+    use columns::RangeCols;
 
     use p3_uni_stark::VerificationError;
-    use rand::random;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
     use std::collections::BTreeMap;
 
     #[test]
     fn test_range_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
         const NUM: usize = 400;
 
+        let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
         let mut count = BTreeMap::new();
         for _ in 0..NUM {
             count
-                .entry(random::<u8>() as u32)
+                .entry(rng.gen::<u8>() as u32)
                 .and_modify(|c| *c += 1)
                 .or_insert(1);
         }
@@ -56,4 +59,27 @@ mod tests {
 
         prove_and_verify(&chip, trace, vec![])
     }
+
+    #[test]
+    fn test_range_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 100;
+
+        let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+        let mut count = BTreeMap::new();
+        for _ in 0..400 {
+            count
+                .entry(rng.gen::<u8>() as u32)
+                .and_modify(|c| *c += 1)
+                .or_insert(1);
+        }
+        let trace = RangeCheckerChip::<256>::generate_trace(count);
+        let chip = RangeCheckerChip::<256> {
+            ..Default::default()
+        };
+
+        let col_map = RangeCols::<usize>::col_map();
+        let mutations = random_mutations(0..256, &[col_map.mult], NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
 }
@@ -1,13 +1,13 @@
 use alloc::collections::BTreeMap;
 
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
 use p3_matrix::dense::RowMajorMatrix;
 
 use super::{columns::RangeCols, RangeCheckerChip};
 
 impl<const MAX: u32> RangeCheckerChip<MAX> {
-    pub fn generate_trace<F: PrimeField32>(count: BTreeMap<u32, u32>) -> RowMajorMatrix<F> {
+    pub fn generate_trace<F: PrimeField64>(count: BTreeMap<u32, u32>) -> RowMajorMatrix<F> {
         let num_cols = RangeCols::<F>::num_cols();
         let num_real_rows = MAX as usize;
         let num_rows = num_real_rows.next_power_of_two();
@@ -25,7 +25,7 @@ impl<const MAX: u32> RangeCheckerChip<MAX> {
 
     pub fn populate_rows_for_counts<F>(rows: &mut [&mut RangeCols<F>], count: BTreeMap<u32, u32>)
     where
-        F: PrimeField32,
+        F: PrimeField64,
     {
         for (n, row) in rows.iter_mut().enumerate() {
             // FIXME: This is very inefficient when the range is large.
@@ -0,0 +1,99 @@
+use core::borrow::Borrow;
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+
+use super::{columns::StorageSlotCols, StorageSlotChip};
+use crate::chips::DIGEST_WIDTH;
+
+impl<F> BaseAir<F> for StorageSlotChip {
+    fn width(&self) -> usize {
+        StorageSlotCols::<F>::num_cols()
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for StorageSlotChip {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &StorageSlotCols<AB::Var> = (*local).borrow();
+        let next: &StorageSlotCols<AB::Var> = (*next).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_first_step);
+        builder.assert_bool(local.is_last_step);
+        builder.assert_bool(local.is_array);
+        builder.when(local.is_first_step).assert_one(local.is_real);
+        builder.when(local.is_last_step).assert_one(local.is_real);
+        builder.when(local.is_array).assert_one(local.is_real);
+
+        // Real rows come first, and a path starts on the first row and after the last
+        // step of the previous one.
+        builder
+            .when_transition()
+            .when(next.is_real)
+            .assert_one(local.is_real);
+        builder
+            .when_first_row()
+            .assert_eq(local.is_first_step, local.is_real);
+        builder
+            .when_transition()
+            .when(local.is_last_step)
+            .assert_eq(next.is_first_step, next.is_real);
+        builder
+            .when_last_row()
+            .assert_eq(local.is_last_step, local.is_real);
+
+        // The steps of a path follow each other, each one starting from the slot
+        // derived by the previous one.
+        let continues = local.is_real - local.is_last_step;
+        builder
+            .when_transition()
+            .when(continues.clone())
+            .assert_one(next.is_real);
+        builder
+            .when_transition()
+            .when(continues.clone())
+            .assert_zero(next.is_first_step);
+        builder.when_transition().when(continues.clone()).assert_eq(
+            next.step_addr,
+            local.step_addr + AB::Expr::from_canonical_usize(Self::STEP_WORDS),
+        );
+        for (local_col, next_col) in [
+            (local.timestamp, next.timestamp),
+            (local.slot_addr, next.slot_addr),
+        ] {
+            builder
+                .when_transition()
+                .when(continues.clone())
+                .assert_eq(local_col, next_col);
+        }
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when_transition()
+                .when(continues.clone())
+                .assert_eq(local.output[i], next.slot[i]);
+        }
+
+        // A mapping's slot is the digest. An array's is the digest plus the index,
+        // added from the last byte up, and the carry out of the first byte is dropped.
+        let is_mapping = local.is_real - local.is_array;
+        for i in 0..DIGEST_WIDTH {
+            builder
+                .when(is_mapping.clone())
+                .assert_eq(local.output[i], local.digest[i]);
+        }
+        for i in 0..DIGEST_WIDTH {
+            builder.assert_bool(local.carry[i]);
+            let carry_in = if i + 1 < DIGEST_WIDTH {
+                local.carry[i + 1].into()
+            } else {
+                AB::Expr::zero()
+            };
+            builder.when(local.is_array).assert_eq(
+                local.digest[i] + local.key[i] + carry_in,
+                local.output[i] + local.carry[i] * AB::Expr::from_canonical_u32(256),
+            );
+        }
+    }
+}
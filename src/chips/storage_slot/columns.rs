@@ -0,0 +1,49 @@
+use p3_derive::Columnar;
+
+use crate::chips::DIGEST_WIDTH;
+
+/// A row per step of a storage path, from the base slot on. The slots, keys and
+/// indices are big-endian 32-byte words.
+#[repr(C)]
+#[derive(Columnar)]
+pub struct StorageSlotCols<T> {
+    pub is_real: T,
+
+    /// Whether this row is the first step of its path, which reads the base slot.
+    pub is_first_step: T,
+
+    /// Whether this row is the last step of its path, which writes the derived slot.
+    pub is_last_step: T,
+
+    /// Whether the step indexes a dynamic array, rather than looking up a mapping.
+    pub is_array: T,
+
+    /// Timestamp of the reads. The derived slot is written at the next timestamp.
+    pub timestamp: T,
+
+    /// Word address of the record of the step: a word whose first byte is
+    /// `is_array`, followed by `key`. The base slot is stored just before the record
+    /// of the first step.
+    pub step_addr: T,
+
+    /// Word address the derived slot is written to.
+    pub slot_addr: T,
+
+    /// The slot of the mapping or of the array.
+    pub slot: [T; DIGEST_WIDTH],
+
+    /// The padded key of the mapping, or the index in the array.
+    pub key: [T; DIGEST_WIDTH],
+
+    /// Id of the sponge operation that hashes `key || slot`, or `slot` for an array.
+    pub hash_id: T,
+
+    pub digest: [T; DIGEST_WIDTH],
+
+    /// The carries of `digest + key` for an array, out of each byte.
+    pub carry: [T; DIGEST_WIDTH],
+
+    /// The slot of the value: `digest` for a mapping, and `digest + key` modulo
+    /// `2^256` for an array.
+    pub output: [T; DIGEST_WIDTH],
+}
@@ -0,0 +1,147 @@
+use core::iter::once;
+
+use itertools::Itertools;
+use p3_air::VirtualPairCol;
+use p3_field::Field;
+use p3_interaction::{BaseInteractionAir, Interaction, InteractionAir, InteractionAirBuilder, Rap};
+
+use super::{columns::StorageSlotCols, StorageSlotChip};
+use crate::chips::{
+    keccak_sponge::util::{sponge_input_interactions, sponge_output_interaction},
+    DIGEST_WIDTH, MEMORY_WORD_BYTES,
+};
+
+impl<F: Field> BaseInteractionAir<F> for StorageSlotChip {
+    fn receives_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = StorageSlotCols::from_slice(main_indices);
+        vec![sponge_output_interaction(
+            VirtualPairCol::single_main(col_map.hash_id),
+            col_map
+                .digest
+                .into_iter()
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(col_map.is_real),
+            self.bus_hasher_output,
+        )]
+    }
+
+    fn sends_from_indices(
+        &self,
+        _preprocessed_indices: &[usize],
+        main_indices: &[usize],
+    ) -> Vec<Interaction<F>> {
+        let col_map = StorageSlotCols::from_slice(main_indices);
+        let bytes = |cols: &[usize]| {
+            cols.iter()
+                .map(|&col| VirtualPairCol::single_main(col))
+                .collect_vec()
+        };
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+
+        // Accesses the words `bytes` from `addr + offset` on, where `offset` may be
+        // negative. Reads happen at `timestamp` and writes at the next timestamp.
+        let memory_accesses = |addr: usize,
+                               offset: F,
+                               bytes: Vec<VirtualPairCol<F>>,
+                               is_write: bool,
+                               count: VirtualPairCol<F>| {
+            bytes
+                .chunks(MEMORY_WORD_BYTES)
+                .enumerate()
+                .map(|(k, word)| Interaction {
+                    fields: once(VirtualPairCol::new_main(
+                        vec![(col_map.timestamp, F::one())],
+                        F::from_bool(is_write),
+                    ))
+                    .chain(once(VirtualPairCol::new_main(
+                        vec![(addr, F::one())],
+                        offset + F::from_canonical_usize(k),
+                    )))
+                    .chain(word.iter().cloned())
+                    .chain(once(VirtualPairCol::constant(F::from_bool(is_write))))
+                    .collect(),
+                    count: count.clone(),
+                    argument_index: self.bus_memory,
+                })
+                .collect_vec()
+        };
+
+        // The record of a step is a word flagging an array, followed by the key.
+        let record = once(VirtualPairCol::single_main(col_map.is_array))
+            .chain((1..MEMORY_WORD_BYTES).map(|_| VirtualPairCol::constant(F::zero())))
+            .chain(bytes(&col_map.key))
+            .collect_vec();
+        let is_mapping = VirtualPairCol::new_main(
+            vec![(col_map.is_real, F::one()), (col_map.is_array, -F::one())],
+            F::zero(),
+        );
+
+        // The slot of an array element is a sum of bytes, which are range checked.
+        let output_range_checks = col_map
+            .output
+            .into_iter()
+            .map(|byte| Interaction {
+                fields: vec![VirtualPairCol::single_main(byte)],
+                count: VirtualPairCol::single_main(col_map.is_array),
+                argument_index: self.bus_range_8,
+            })
+            .collect_vec();
+
+        [
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                [bytes(&col_map.key), bytes(&col_map.slot)].concat(),
+                is_mapping,
+                self.bus_hasher_input,
+            ),
+            sponge_input_interactions(
+                VirtualPairCol::single_main(col_map.hash_id),
+                bytes(&col_map.slot),
+                VirtualPairCol::single_main(col_map.is_array),
+                self.bus_hasher_input,
+            ),
+            output_range_checks,
+            memory_accesses(
+                col_map.step_addr,
+                -F::from_canonical_usize(words_per_digest),
+                bytes(&col_map.slot),
+                false,
+                VirtualPairCol::single_main(col_map.is_first_step),
+            ),
+            memory_accesses(
+                col_map.step_addr,
+                F::zero(),
+                record,
+                false,
+                VirtualPairCol::single_main(col_map.is_real),
+            ),
+            memory_accesses(
+                col_map.slot_addr,
+                F::zero(),
+                bytes(&col_map.output),
+                true,
+                VirtualPairCol::single_main(col_map.is_last_step),
+            ),
+        ]
+        .concat()
+    }
+}
+
+impl<F: Field> InteractionAir<F> for StorageSlotChip {
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let col_map = StorageSlotCols::<F>::col_map();
+        self.receives_from_main_indices(col_map.as_slice())
+    }
+
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let col_map = StorageSlotCols::<F>::col_map();
+        self.sends_from_main_indices(col_map.as_slice())
+    }
+}
+
+impl<AB: InteractionAirBuilder> Rap<AB> for StorageSlotChip {}
@@ -0,0 +1,186 @@
+mod air;
+pub(crate) mod columns;
+mod interaction;
+mod trace;
+
+pub use trace::{add_u256, StoragePath, StorageSlotOp, StorageStep};
+
+use super::{DIGEST_WIDTH, MEMORY_WORD_BYTES};
+
+/// Derives the storage slots of values of Solidity mappings and dynamic arrays,
+/// along paths read from memory. Each step of a path is a row that hashes the
+/// current slot over the sponge buses, with the key for a mapping, and adds the
+/// index to the digest for an array, with the carries of the 256-bit addition. The
+/// last step writes the derived slot back to memory.
+#[derive(Default, Clone, Debug)]
+pub struct StorageSlotChip {
+    pub bus_hasher_input: usize,
+    pub bus_hasher_output: usize,
+
+    pub bus_memory: usize,
+
+    pub bus_range_8: usize,
+}
+
+impl StorageSlotChip {
+    /// Number of words in the record of a step: a word flagging an array, followed
+    /// by the key or the index.
+    pub const STEP_WORDS: usize = 1 + DIGEST_WIDTH / MEMORY_WORD_BYTES;
+}
+
+#[cfg(feature = "air-logger")]
+impl p3_air_util::AirLogger for StorageSlotChip {
+    fn main_headers(&self) -> Vec<String> {
+        self::columns::StorageSlotCols::<usize>::headers()
+    }
+
+    #[cfg(feature = "schema")]
+    fn main_headers_and_types(&self) -> Vec<(String, String, core::ops::Range<usize>)> {
+        self::columns::StorageSlotCols::<usize>::headers_and_types()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Val,
+        test_util::{assert_mutations_rejected, prove_and_verify, random_mutations},
+    };
+    use columns::StorageSlotCols;
+
+    use itertools::Itertools;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn from_hex(hex: &str) -> [u8; 32] {
+        (0..32)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect_vec()
+            .try_into()
+            .unwrap()
+    }
+
+    fn u256(value: u64) -> [u8; 32] {
+        let mut word = [0; 32];
+        word[24..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    /// Paths through a mapping of mappings, an array of arrays, and a mapping of
+    /// arrays whose index wraps around.
+    fn generate_ops(seed: u64) -> Vec<StorageSlotOp> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let paths = [
+            vec![
+                StorageStep::Mapping { key: rng.gen() },
+                StorageStep::Mapping { key: rng.gen() },
+            ],
+            vec![
+                StorageStep::Array { index: u256(3) },
+                StorageStep::Array {
+                    index: u256(1 << 40),
+                },
+            ],
+            vec![
+                StorageStep::Mapping { key: rng.gen() },
+                StorageStep::Array { index: [0xff; 32] },
+            ],
+        ];
+
+        let mut first_hash_id = 0;
+        let mut path_addr = 0;
+        paths
+            .into_iter()
+            .enumerate()
+            .map(|(i, steps)| {
+                let path = StoragePath {
+                    base_slot: u256(i as u64),
+                    steps,
+                };
+                let op = StorageSlotOp {
+                    timestamp: 0,
+                    path_addr,
+                    slot_addr: path_addr + (path.image().len() / MEMORY_WORD_BYTES) as u32,
+                    first_hash_id,
+                    path,
+                };
+                first_hash_id += op.path.steps.len();
+                path_addr = op.slot_addr + 4;
+                op
+            })
+            .collect()
+    }
+
+    fn generate_ops_trace(ops: &[StorageSlotOp]) -> RowMajorMatrix<Val> {
+        StorageSlotChip::generate_trace(ops)
+    }
+
+    #[test]
+    fn test_storage_slot_known_slots() {
+        // The first element of an array at slot 0, and its third one.
+        let array = |index| StoragePath {
+            base_slot: u256(0),
+            steps: vec![StorageStep::Array { index: u256(index) }],
+        };
+        assert_eq!(
+            array(0).slot(),
+            from_hex("290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563")
+        );
+        assert_eq!(
+            array(2).slot(),
+            from_hex("290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e565")
+        );
+
+        // The value of key 1 in a mapping at slot 0, and the third element of the
+        // array there.
+        let mut path = StoragePath {
+            base_slot: u256(0),
+            steps: vec![StorageStep::Mapping { key: u256(1) }],
+        };
+        assert_eq!(
+            path.slot(),
+            from_hex("ada5013122d395ba3c54772283fb069b10426056ef8ca54750cb9bb552a59e7d")
+        );
+        path.steps.push(StorageStep::Array { index: u256(2) });
+        assert_eq!(
+            path.slot(),
+            from_hex("3f9553dc324cd1fd24b54243720c42e18e5c20165bc5e523e42b440a8654abd3")
+        );
+    }
+
+    #[test]
+    fn test_storage_slot_add_u256() {
+        let (sum, carry) = add_u256(&u256(0xff), &u256(1));
+        assert_eq!(sum, u256(0x100));
+        assert_eq!(carry[31..], [true]);
+        assert!(!carry[30]);
+
+        // The carry out of the first byte is dropped.
+        let (sum, carry) = add_u256(&[0xff; 32], &u256(2));
+        assert_eq!(sum, u256(1));
+        assert_eq!(carry, [true; 32]);
+    }
+
+    #[test]
+    fn test_storage_slot_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = generate_ops_trace(&generate_ops(RANDOM_SEED));
+        prove_and_verify(&StorageSlotChip::default(), trace, vec![])
+    }
+
+    #[test]
+    fn test_storage_slot_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let ops = generate_ops(RANDOM_SEED);
+        let trace = generate_ops_trace(&ops);
+
+        let cols = (0..StorageSlotCols::<Val>::num_cols()).collect_vec();
+        let mutations = random_mutations(0..6, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&StorageSlotChip::default(), &trace, &mutations);
+    }
+}
@@ -0,0 +1,248 @@
+use alloc::collections::BTreeMap;
+use core::borrow::Borrow;
+
+use itertools::Itertools;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use tracing::instrument;
+
+use super::{columns::StorageSlotCols, StorageSlotChip};
+use crate::chips::{
+    keccak_sponge::trace::KeccakSpongeOp,
+    memory::trace::{image_word, MemoryOp, OperationKind},
+    DIGEST_WIDTH, MEMORY_WORD_BYTES,
+};
+
+/// A step of a storage path. Keys and indices are big-endian 32-byte words, with
+/// keys padded as Solidity pads them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageStep {
+    /// The value of `key` in the mapping: `keccak(key || slot)`.
+    Mapping { key: [u8; DIGEST_WIDTH] },
+    /// The element `index` of the dynamic array: `keccak(slot) + index`.
+    Array { index: [u8; DIGEST_WIDTH] },
+}
+
+impl StorageStep {
+    fn is_array(&self) -> bool {
+        matches!(self, StorageStep::Array { .. })
+    }
+
+    fn key(&self) -> [u8; DIGEST_WIDTH] {
+        match *self {
+            StorageStep::Mapping { key } => key,
+            StorageStep::Array { index } => index,
+        }
+    }
+
+    fn preimage(&self, slot: &[u8; DIGEST_WIDTH]) -> Vec<u8> {
+        match self {
+            StorageStep::Mapping { key } => [*key, *slot].concat(),
+            StorageStep::Array { .. } => slot.to_vec(),
+        }
+    }
+}
+
+/// Adds two big-endian 256-bit words modulo `2^256`, and returns the sum along with
+/// the carry out of each byte.
+pub fn add_u256(
+    a: &[u8; DIGEST_WIDTH],
+    b: &[u8; DIGEST_WIDTH],
+) -> ([u8; DIGEST_WIDTH], [bool; DIGEST_WIDTH]) {
+    let mut sum = [0; DIGEST_WIDTH];
+    let mut carry = [false; DIGEST_WIDTH];
+    let mut carry_in = 0;
+    for i in (0..DIGEST_WIDTH).rev() {
+        let byte_sum = a[i] as u16 + b[i] as u16 + carry_in;
+        sum[i] = byte_sum as u8;
+        carry[i] = byte_sum >= 256;
+        carry_in = carry[i] as u16;
+    }
+    (sum, carry)
+}
+
+/// A path from the slot of a state variable down to one of its values, through
+/// mappings and dynamic arrays.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StoragePath {
+    pub base_slot: [u8; DIGEST_WIDTH],
+    pub steps: Vec<StorageStep>,
+}
+
+impl StoragePath {
+    /// The slot of the path after each step.
+    pub fn slots(&self) -> Vec<[u8; DIGEST_WIDTH]> {
+        let mut slot = self.base_slot;
+        self.steps
+            .iter()
+            .map(|step| {
+                let digest: [u8; DIGEST_WIDTH] = Keccak256Hash.hash_iter(step.preimage(&slot));
+                slot = match step {
+                    StorageStep::Mapping { .. } => digest,
+                    StorageStep::Array { index } => add_u256(&digest, index).0,
+                };
+                slot
+            })
+            .collect()
+    }
+
+    /// The slot the path leads to.
+    pub fn slot(&self) -> [u8; DIGEST_WIDTH] {
+        self.slots().last().copied().unwrap_or(self.base_slot)
+    }
+
+    /// The memory image of the path: the base slot, followed by a record per step,
+    /// a word whose first byte flags an array, followed by the key or the index.
+    pub fn image(&self) -> Vec<u8> {
+        let records = self.steps.iter().flat_map(|step| {
+            let mut record = vec![0; MEMORY_WORD_BYTES];
+            record[0] = step.is_array() as u8;
+            record.extend(step.key());
+            record
+        });
+        self.base_slot.into_iter().chain(records).collect()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StorageSlotOp {
+    pub path: StoragePath,
+    /// Timestamp of the reads. The derived slot is written at the next timestamp.
+    pub timestamp: u32,
+    /// Word address of the image of the path.
+    pub path_addr: u32,
+    /// Word address the derived slot is written to.
+    pub slot_addr: u32,
+    /// Id of the sponge operation of the first step. The following steps take the
+    /// following ids.
+    pub first_hash_id: usize,
+}
+
+impl StorageSlotOp {
+    pub fn sponge_ops(&self) -> Vec<KeccakSpongeOp> {
+        let slots = [vec![self.path.base_slot], self.path.slots()].concat();
+        self.path
+            .steps
+            .iter()
+            .zip(slots)
+            .map(|(step, slot)| KeccakSpongeOp {
+                input: step.preimage(&slot),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Returns the memory accesses of the operation: the path is read at
+    /// `timestamp`, and the derived slot is written at `timestamp + 1`.
+    pub fn memory_ops(&self) -> Vec<MemoryOp<MEMORY_WORD_BYTES>> {
+        let image = self.path.image();
+        let reads = (0..image.len() / MEMORY_WORD_BYTES).map(|k| MemoryOp {
+            addr: self.path_addr + k as u32,
+            timestamp: self.timestamp,
+            value: image_word(&image, k),
+            kind: OperationKind::Read,
+        });
+        let slot = self.path.slot();
+        let writes = (0..DIGEST_WIDTH / MEMORY_WORD_BYTES).map(|k| MemoryOp {
+            addr: self.slot_addr + k as u32,
+            timestamp: self.timestamp + 1,
+            value: image_word(&slot, k),
+            kind: OperationKind::Write,
+        });
+        reads.chain(writes).collect()
+    }
+}
+
+impl StorageSlotChip {
+    #[instrument(name = "generate StorageSlot trace", skip_all)]
+    pub fn generate_trace<F: PrimeField64>(operations: &[StorageSlotOp]) -> RowMajorMatrix<F> {
+        let num_cols = StorageSlotCols::<F>::num_cols();
+
+        let num_real_rows = operations
+            .iter()
+            .map(|op| op.path.steps.len())
+            .sum::<usize>();
+        let num_rows = num_real_rows.next_power_of_two();
+        let mut trace = RowMajorMatrix::new(vec![F::zero(); num_rows * num_cols], num_cols);
+        let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<StorageSlotCols<F>>() };
+        assert!(prefix.is_empty(), "Alignment should match");
+        assert!(suffix.is_empty(), "Alignment should match");
+        assert_eq!(rows.len(), num_rows);
+
+        let mut real_rows = rows[0..num_real_rows].iter_mut().collect_vec();
+        Self::populate_rows_for_ops(&mut real_rows, operations);
+
+        trace
+    }
+
+    /// Returns the bytes of the slots of the array elements, which are range
+    /// checked.
+    pub fn generate_range_counts<F: PrimeField64>(trace: &RowMajorMatrix<F>) -> BTreeMap<u32, u32> {
+        let mut count = BTreeMap::new();
+        for row in trace.values.chunks_exact(trace.width) {
+            let row: &StorageSlotCols<F> = row.borrow();
+            if row.is_array.is_zero() {
+                continue;
+            }
+            for byte in row.output {
+                count
+                    .entry(byte.as_canonical_u64() as u32)
+                    .and_modify(|c| *c += 1)
+                    .or_insert(1);
+            }
+        }
+        count
+    }
+
+    pub fn populate_rows_for_ops<F: PrimeField64>(
+        rows: &mut [&mut StorageSlotCols<F>],
+        ops: &[StorageSlotOp],
+    ) {
+        let mut offset = 0;
+        for op in ops {
+            let len = op.path.steps.len();
+            Self::populate_rows_for_op(&mut rows[offset..offset + len], op);
+            offset += len;
+        }
+    }
+
+    /// Generates the rows of the steps of a path, which must have at least one.
+    pub fn populate_rows_for_op<F: PrimeField64>(
+        rows: &mut [&mut StorageSlotCols<F>],
+        op: &StorageSlotOp,
+    ) {
+        let steps = &op.path.steps;
+        assert!(!steps.is_empty(), "the path has no steps");
+
+        let mut slot = op.path.base_slot;
+        for (i, (row, step)) in rows.iter_mut().zip(steps).enumerate() {
+            let digest: [u8; DIGEST_WIDTH] = Keccak256Hash.hash_iter(step.preimage(&slot));
+            let (output, carry) = match step {
+                StorageStep::Mapping { .. } => (digest, [false; DIGEST_WIDTH]),
+                StorageStep::Array { index } => add_u256(&digest, index),
+            };
+
+            row.is_real = F::one();
+            row.is_first_step = F::from_bool(i == 0);
+            row.is_last_step = F::from_bool(i == steps.len() - 1);
+            row.is_array = F::from_bool(step.is_array());
+            row.timestamp = F::from_canonical_u32(op.timestamp);
+            row.step_addr = F::from_canonical_usize(
+                op.path_addr as usize
+                    + DIGEST_WIDTH / MEMORY_WORD_BYTES
+                    + i * StorageSlotChip::STEP_WORDS,
+            );
+            row.slot_addr = F::from_canonical_u32(op.slot_addr);
+            row.slot = slot.map(F::from_canonical_u8);
+            row.key = step.key().map(F::from_canonical_u8);
+            row.hash_id = F::from_canonical_usize(op.first_hash_id + i);
+            row.digest = digest.map(F::from_canonical_u8);
+            row.carry = carry.map(F::from_bool);
+            row.output = output.map(F::from_canonical_u8);
+
+            slot = output;
+        }
+    }
+}
@@ -17,26 +17,8 @@ where
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
         let col_map = XorCols::<_, NUM_BYTES>::from_slice(main_indices);
-        let vc1 = {
-            let column_weights = col_map
-                .input1
-                .into_iter()
-                .enumerate()
-                .map(|(i, c)| (c, F::from_canonical_usize(1 << (8 * i))))
-                .collect();
-            VirtualPairCol::new_main(column_weights, F::zero())
-        };
-        let vc2 = {
-            let column_weights = col_map
-                .input2
-                .into_iter()
-                .enumerate()
-                .map(|(i, c)| (c, F::from_canonical_usize(1 << (8 * i))))
-                .collect();
-            VirtualPairCol::new_main(column_weights, F::zero())
-        };
         vec![Interaction {
-            fields: vec![vc1, vc2],
+            fields: vec![pack_bytes(col_map.input1), pack_bytes(col_map.input2)],
             count: VirtualPairCol::single_main(col_map.is_real),
             argument_index: self.bus_input,
         }]
@@ -48,20 +30,31 @@ where
         main_indices: &[usize],
     ) -> Vec<Interaction<F>> {
         let col_map = XorCols::<_, NUM_BYTES>::from_slice(main_indices);
-        let column_weights = col_map
-            .output
-            .into_iter()
-            .enumerate()
-            .map(|(i, c)| (c, F::from_canonical_usize(1 << (8 * i))))
-            .collect();
         vec![Interaction {
-            fields: vec![VirtualPairCol::new_main(column_weights, F::zero())],
+            fields: vec![pack_bytes(col_map.output)],
             count: VirtualPairCol::single_main(col_map.is_real),
             argument_index: self.bus_output,
         }]
     }
 }
 
+/// Packs little-endian bytes into a single field element. The packing is only
+/// injective if the field has more than `8 * NUM_BYTES` bits, e.g. at most 3 bytes
+/// on BabyBear and Mersenne31, and at most 7 bytes on Goldilocks.
+fn pack_bytes<F: Field, const NUM_BYTES: usize>(bytes: [usize; NUM_BYTES]) -> VirtualPairCol<F> {
+    assert!(
+        8 * NUM_BYTES < F::bits(),
+        "{NUM_BYTES} bytes don't fit in a {}-bit field",
+        F::bits()
+    );
+    let column_weights = bytes
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| (c, F::from_canonical_u64(1 << (8 * i))))
+        .collect();
+    VirtualPairCol::new_main(column_weights, F::zero())
+}
+
 impl<F, const NUM_BYTES: usize> InteractionAir<F> for XorChip<NUM_BYTES>
 where
     F: Field,
@@ -22,3 +22,56 @@ impl<const NUM_BYTES: usize> p3_air_util::AirLogger for XorChip<NUM_BYTES> {
         self::columns::XorCols::<usize, NUM_BYTES>::headers_and_types()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{assert_mutations_rejected, prove_and_verify, random_mutations};
+    use columns::XorCols;
+
+    use itertools::Itertools;
+    use p3_uni_stark::VerificationError;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use trace::XorOp;
+
+    const NUM_OPS: usize = 100;
+
+    fn random_ops(num_ops: usize, seed: u64) -> Vec<XorOp> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..num_ops)
+            .map(|_| XorOp {
+                input1: rng.gen(),
+                input2: rng.gen(),
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn test_xor_prove() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let trace = XorChip::<2>::generate_trace(random_ops(NUM_OPS, RANDOM_SEED));
+        let chip = XorChip::<2> {
+            bus_input: 0,
+            bus_output: 1,
+        };
+
+        prove_and_verify(&chip, trace, vec![])
+    }
+
+    #[test]
+    fn test_xor_mutations() {
+        const RANDOM_SEED: u64 = 0;
+        const NUM_MUTATIONS: usize = 200;
+
+        let trace = XorChip::<2>::generate_trace(random_ops(NUM_OPS, RANDOM_SEED));
+        let chip = XorChip::<2> {
+            bus_input: 0,
+            bus_output: 1,
+        };
+
+        let cols = XorCols::<usize, 2>::col_map().as_slice().to_vec();
+        let mutations = random_mutations(0..NUM_OPS, &cols, NUM_MUTATIONS, RANDOM_SEED);
+        assert_mutations_rejected(&chip, &trace, &mutations);
+    }
+}
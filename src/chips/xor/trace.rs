@@ -1,16 +1,17 @@
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
 use p3_matrix::dense::RowMajorMatrix;
 
 use super::{columns::XorCols, XorChip};
 
+#[derive(Clone)]
 pub struct XorOp {
     pub input1: u16,
     pub input2: u16,
 }
 
 impl<const NUM_BYTES: usize> XorChip<NUM_BYTES> {
-    pub fn generate_trace<F: PrimeField32>(operations: Vec<XorOp>) -> RowMajorMatrix<F> {
+    pub fn generate_trace<F: PrimeField64>(operations: Vec<XorOp>) -> RowMajorMatrix<F> {
         let num_cols = XorCols::<F, NUM_BYTES>::num_cols();
         let num_real_rows = operations.len();
         let num_rows = num_real_rows.next_power_of_two();
@@ -28,7 +29,7 @@ impl<const NUM_BYTES: usize> XorChip<NUM_BYTES> {
         trace
     }
 
-    pub fn populate_rows_for_ops<F: PrimeField32>(
+    pub fn populate_rows_for_ops<F: PrimeField64>(
         rows: &mut [&mut XorCols<F, NUM_BYTES>],
         ops: &[XorOp],
     ) {
@@ -37,7 +38,7 @@ impl<const NUM_BYTES: usize> XorChip<NUM_BYTES> {
         }
     }
 
-    pub fn populate_row_for_op<F: PrimeField32>(row: &mut XorCols<F, NUM_BYTES>, op: &XorOp) {
+    pub fn populate_row_for_op<F: PrimeField64>(row: &mut XorCols<F, NUM_BYTES>, op: &XorOp) {
         row.is_real = F::one();
 
         let input1_bytes = op.input1.to_le_bytes();
@@ -1,52 +1,285 @@
-use p3_baby_bear::BabyBear;
-use p3_challenger::{HashChallenger, SerializingChallenger32};
+use core::marker::PhantomData;
+
+use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
+use p3_challenger::{
+    DuplexChallenger, HashChallenger, SerializingChallenger32, SerializingChallenger64,
+};
+use p3_circle::CirclePcs;
 use p3_commit::ExtensionMmcs;
 use p3_dft::Radix2DitParallel;
-use p3_field::extension::BinomialExtensionField;
+use p3_field::{extension::BinomialExtensionField, Field};
 use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_goldilocks::Goldilocks;
 use p3_keccak::Keccak256Hash;
 use p3_merkle_tree::FieldMerkleTreeMmcs;
-use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use p3_mersenne_31::Mersenne31;
+use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
+use p3_symmetric::{
+    CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher32, SerializingHasher64,
+    TruncatedPermutation,
+};
 use p3_uni_stark::StarkConfig;
+use rand::{rngs::StdRng, SeedableRng};
 
 pub type Val = BabyBear;
+/// Number of bits of `Val`, for sizing columns at compile time.
+pub const VAL_BITS: usize = 31;
 pub type Challenge = BinomialExtensionField<Val, 4>;
+pub type Dft = Radix2DitParallel;
+
+// Keccak256 commitments, which are the fastest to verify natively.
 pub type ByteHash = Keccak256Hash;
 pub type FieldHash = SerializingHasher32<ByteHash>;
 pub type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
 pub type ValMmcs = FieldMerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
 pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
-pub type Dft = Radix2DitParallel;
 pub type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
 pub type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
 pub type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
 
-pub fn default_config() -> MyConfig {
-    let byte_hash = ByteHash {};
-    let field_hash = FieldHash::new(Keccak256Hash {});
+// Poseidon2 commitments, which are cheap to verify inside another STARK.
+pub type Poseidon2Perm =
+    Poseidon2<Val, Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear, 16, 7>;
+pub type Poseidon2Hash = PaddingFreeSponge<Poseidon2Perm, 16, 8, 8>;
+pub type Poseidon2Compress = TruncatedPermutation<Poseidon2Perm, 2, 8, 16>;
+pub type Poseidon2ValMmcs = FieldMerkleTreeMmcs<
+    <Val as Field>::Packing,
+    <Val as Field>::Packing,
+    Poseidon2Hash,
+    Poseidon2Compress,
+    8,
+>;
+pub type Poseidon2ChallengeMmcs = ExtensionMmcs<Val, Challenge, Poseidon2ValMmcs>;
+pub type Poseidon2Challenger = DuplexChallenger<Val, Poseidon2Perm, 16, 8>;
+pub type Poseidon2Pcs = TwoAdicFriPcs<Val, Dft, Poseidon2ValMmcs, Poseidon2ChallengeMmcs>;
+pub type Poseidon2Config = StarkConfig<Poseidon2Pcs, Challenge, Poseidon2Challenger>;
+
+// Goldilocks with Keccak256 commitments. The field has 64 bits, so the extension
+// only needs degree 2.
+pub type GoldilocksVal = Goldilocks;
+pub type GoldilocksChallenge = BinomialExtensionField<GoldilocksVal, 2>;
+pub type GoldilocksFieldHash = SerializingHasher64<ByteHash>;
+pub type GoldilocksValMmcs =
+    FieldMerkleTreeMmcs<GoldilocksVal, u8, GoldilocksFieldHash, MyCompress, 32>;
+pub type GoldilocksChallengeMmcs =
+    ExtensionMmcs<GoldilocksVal, GoldilocksChallenge, GoldilocksValMmcs>;
+pub type GoldilocksChallenger =
+    SerializingChallenger64<GoldilocksVal, HashChallenger<u8, ByteHash, 32>>;
+pub type GoldilocksPcs =
+    TwoAdicFriPcs<GoldilocksVal, Dft, GoldilocksValMmcs, GoldilocksChallengeMmcs>;
+pub type GoldilocksConfig = StarkConfig<GoldilocksPcs, GoldilocksChallenge, GoldilocksChallenger>;
+
+// Mersenne31 with Keccak256 commitments. The field isn't two-adic, so it uses the
+// circle PCS instead of the two-adic FRI PCS.
+pub type Mersenne31Val = Mersenne31;
+pub type Mersenne31Challenge = BinomialExtensionField<Mersenne31Val, 3>;
+pub type Mersenne31ValMmcs = FieldMerkleTreeMmcs<Mersenne31Val, u8, FieldHash, MyCompress, 32>;
+pub type Mersenne31ChallengeMmcs =
+    ExtensionMmcs<Mersenne31Val, Mersenne31Challenge, Mersenne31ValMmcs>;
+pub type Mersenne31Challenger =
+    SerializingChallenger32<Mersenne31Val, HashChallenger<u8, ByteHash, 32>>;
+pub type Mersenne31Pcs = CirclePcs<Mersenne31Val, Mersenne31ValMmcs, Mersenne31ChallengeMmcs>;
+pub type Mersenne31Config = StarkConfig<Mersenne31Pcs, Mersenne31Challenge, Mersenne31Challenger>;
 
-    let compress = MyCompress::new(byte_hash);
+/// Seed of the Poseidon2 round constants. Changing it changes every commitment.
+const POSEIDON2_SEED: u64 = 1;
 
-    let val_mmcs = ValMmcs::new(field_hash, compress.clone());
+/// FRI parameters. The conjectured security of a proof is
+/// `log_blowup * num_queries + proof_of_work_bits` bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriParameters {
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+impl Default for FriParameters {
+    /// 100 bits of conjectured security.
+    fn default() -> Self {
+        Self {
+            log_blowup: 2,
+            num_queries: 42,
+            proof_of_work_bits: 16,
+        }
+    }
+}
 
-    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+impl FriParameters {
+    /// The fewest queries reaching `security_bits` with the given blowup and
+    /// proof-of-work.
+    pub fn with_security_bits(
+        security_bits: usize,
+        log_blowup: usize,
+        proof_of_work_bits: usize,
+    ) -> Self {
+        let query_bits = security_bits.saturating_sub(proof_of_work_bits);
+        Self {
+            log_blowup,
+            num_queries: query_bits.div_ceil(log_blowup),
+            proof_of_work_bits,
+        }
+    }
 
-    let dft = Dft {};
+    /// Small blowup and many queries: faster proving, larger proofs.
+    pub fn fast_proving(security_bits: usize) -> Self {
+        Self::with_security_bits(security_bits, 1, 16)
+    }
 
-    let fri_config = FriConfig {
-        log_blowup: 2,
-        num_queries: 42,
-        proof_of_work_bits: 16,
-        mmcs: challenge_mmcs,
-    };
-    let pcs = Pcs::new(dft, val_mmcs, fri_config);
+    /// Large blowup and few queries: slower proving, smaller proofs that are faster
+    /// to verify.
+    pub fn fast_verification(security_bits: usize) -> Self {
+        Self::with_security_bits(security_bits, 3, 16)
+    }
 
-    MyConfig::new(pcs)
+    pub fn conjectured_security_bits(&self) -> usize {
+        self.log_blowup * self.num_queries + self.proof_of_work_bits
+    }
+}
+
+/// Builds a `StarkConfig` and the matching challenger for one of the supported
+/// commitment schemes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StarkConfigBuilder {
+    fri: FriParameters,
+}
+
+impl StarkConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fri(mut self, fri: FriParameters) -> Self {
+        self.fri = fri;
+        self
+    }
+
+    /// Keccak256 Merkle commitments over bytes.
+    pub fn keccak(self) -> (MyConfig, Challenger) {
+        let byte_hash = ByteHash {};
+        let field_hash = FieldHash::new(Keccak256Hash {});
+
+        let compress = MyCompress::new(byte_hash);
+
+        let val_mmcs = ValMmcs::new(field_hash, compress.clone());
+
+        let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+        let dft = Dft {};
+
+        let fri_config = self.fri_config(challenge_mmcs);
+        let pcs = Pcs::new(dft, val_mmcs, fri_config);
+
+        let challenger = Challenger::from_hasher(vec![], byte_hash);
+
+        (MyConfig::new(pcs), challenger)
+    }
+
+    /// Poseidon2 Merkle commitments over BabyBear elements.
+    pub fn poseidon2(self) -> (Poseidon2Config, Poseidon2Challenger) {
+        let mut rng = StdRng::seed_from_u64(POSEIDON2_SEED);
+        let perm = Poseidon2Perm::new_from_rng_128(
+            Poseidon2ExternalMatrixGeneral,
+            DiffusionMatrixBabyBear::default(),
+            &mut rng,
+        );
+
+        let hash = Poseidon2Hash::new(perm.clone());
+        let compress = Poseidon2Compress::new(perm.clone());
+
+        let val_mmcs = Poseidon2ValMmcs::new(hash, compress);
+
+        let challenge_mmcs = Poseidon2ChallengeMmcs::new(val_mmcs.clone());
+
+        let dft = Dft {};
+
+        let fri_config = self.fri_config(challenge_mmcs);
+        let pcs = Poseidon2Pcs::new(dft, val_mmcs, fri_config);
+
+        let challenger = Poseidon2Challenger::new(perm);
+
+        (Poseidon2Config::new(pcs), challenger)
+    }
+
+    /// Keccak256 Merkle commitments over Goldilocks.
+    pub fn goldilocks(self) -> (GoldilocksConfig, GoldilocksChallenger) {
+        let byte_hash = ByteHash {};
+        let field_hash = GoldilocksFieldHash::new(byte_hash);
+
+        let compress = MyCompress::new(byte_hash);
+
+        let val_mmcs = GoldilocksValMmcs::new(field_hash, compress);
+
+        let challenge_mmcs = GoldilocksChallengeMmcs::new(val_mmcs.clone());
+
+        let dft = Dft {};
+
+        let fri_config = self.fri_config(challenge_mmcs);
+        let pcs = GoldilocksPcs::new(dft, val_mmcs, fri_config);
+
+        let challenger = GoldilocksChallenger::from_hasher(vec![], byte_hash);
+
+        (GoldilocksConfig::new(pcs), challenger)
+    }
+
+    /// Keccak256 Merkle commitments over Mersenne31, using the circle PCS.
+    pub fn mersenne31(self) -> (Mersenne31Config, Mersenne31Challenger) {
+        let byte_hash = ByteHash {};
+        let field_hash = FieldHash::new(byte_hash);
+
+        let compress = MyCompress::new(byte_hash);
+
+        let val_mmcs = Mersenne31ValMmcs::new(field_hash, compress);
+
+        let challenge_mmcs = Mersenne31ChallengeMmcs::new(val_mmcs.clone());
+
+        let fri_config = self.fri_config(challenge_mmcs);
+        let pcs = Mersenne31Pcs {
+            mmcs: val_mmcs,
+            fri_config,
+            _phantom: PhantomData,
+        };
+
+        let challenger = Mersenne31Challenger::from_hasher(vec![], byte_hash);
+
+        (Mersenne31Config::new(pcs), challenger)
+    }
+
+    fn fri_config<M>(&self, mmcs: M) -> FriConfig<M> {
+        FriConfig {
+            log_blowup: self.fri.log_blowup,
+            num_queries: self.fri.num_queries,
+            proof_of_work_bits: self.fri.proof_of_work_bits,
+            mmcs,
+        }
+    }
+}
+
+pub fn default_config() -> MyConfig {
+    StarkConfigBuilder::new().keccak().0
 }
 
 pub fn default_challenger() -> Challenger {
-    let byte_hash = ByteHash {};
-    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+    StarkConfigBuilder::new().keccak().1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_val_bits() {
+        assert_eq!(Val::bits(), VAL_BITS);
+    }
 
-    Challenger::from_hasher(vec![], byte_hash)
+    #[test]
+    fn test_fri_presets_reach_security_bits() {
+        assert_eq!(
+            FriParameters::with_security_bits(100, 2, 16),
+            FriParameters::default()
+        );
+        for bits in [80, 100, 128] {
+            assert!(FriParameters::fast_proving(bits).conjectured_security_bits() >= bits);
+            assert!(FriParameters::fast_verification(bits).conjectured_security_bits() >= bits);
+        }
+    }
 }
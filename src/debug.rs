@@ -0,0 +1,290 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use itertools::Itertools;
+use p3_air::{BaseAir, VirtualPairCol};
+use p3_field::{Field, PrimeField64};
+use p3_interaction::{Interaction, InteractionAir};
+use p3_machine::machine::Machine;
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+
+use crate::{bus::KeccakMachineBus, chips::KeccakMachineChip, machine::KeccakMachine};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Send,
+    Receive,
+}
+
+/// One occurrence of a message whose sends and receives don't cancel out on its bus.
+#[derive(Clone, Debug)]
+pub struct UnmatchedMessage<F> {
+    pub bus: usize,
+    pub chip: String,
+    pub row: usize,
+    pub direction: Direction,
+    /// The message, as canonical field elements.
+    pub fields: Vec<u64>,
+    pub count: F,
+    /// For each field, the names of the columns it's computed from.
+    pub columns: Vec<Vec<String>>,
+}
+
+/// The multisets of messages on every bus of a machine, as seen in its traces.
+#[derive(Clone, Debug)]
+pub struct BusBalanceReport<F> {
+    /// For each bus, the net multiplicity (sends minus receives) of every message.
+    pub buses: BTreeMap<usize, BTreeMap<Vec<u64>, F>>,
+    /// Every occurrence of a message with a non-zero net multiplicity.
+    pub unmatched: Vec<UnmatchedMessage<F>>,
+}
+
+impl<F: PrimeField64> BusBalanceReport<F> {
+    pub fn is_balanced(&self) -> bool {
+        self.unmatched.is_empty()
+    }
+}
+
+impl<F: PrimeField64> fmt::Display for BusBalanceReport<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_balanced() {
+            return writeln!(f, "all buses are balanced");
+        }
+        for message in self.unmatched.iter() {
+            let direction = match message.direction {
+                Direction::Send => "sends",
+                Direction::Receive => "receives",
+            };
+            let fields = message
+                .fields
+                .iter()
+                .zip(message.columns.iter())
+                .map(|(value, columns)| match columns.as_slice() {
+                    [] => format!("{value}"),
+                    columns => format!("{}={value}", columns.join("+")),
+                })
+                .join(", ");
+            writeln!(
+                f,
+                "bus {}: {} row {} {direction} ({fields}) with multiplicity {}",
+                bus_name(message.bus),
+                message.chip,
+                message.row,
+                message.count.as_canonical_u64(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A message sent or received on a row, before matching.
+struct Occurrence<F> {
+    chip_index: usize,
+    interaction_index: usize,
+    row: usize,
+    direction: Direction,
+    fields: Vec<u64>,
+    count: F,
+}
+
+impl KeccakMachine {
+    /// Evaluates the sends and receives of every chip over `traces`, and reports the
+    /// messages that don't balance. This is meant for debugging a machine whose
+    /// proofs don't verify, so it doesn't try to be fast.
+    pub fn debug_bus_balance<F: PrimeField64>(
+        &self,
+        traces: &[Option<RowMajorMatrix<F>>],
+    ) -> BusBalanceReport<F> {
+        let chips = self.chips();
+
+        let mut interactions: Vec<Vec<(Direction, Interaction<F>)>> = vec![];
+        let mut occurrences = vec![];
+        for (chip_index, (chip, trace)) in chips.iter().zip(traces.iter()).enumerate() {
+            let chip_interactions = chip_interactions::<F, _>(chip);
+
+            if let Some(trace) = trace {
+                let preprocessed = BaseAir::<F>::preprocessed_trace(chip);
+                for row in 0..trace.height() {
+                    let main = trace.row_slice(row);
+                    let preprocessed = preprocessed
+                        .as_ref()
+                        .map(|preprocessed| preprocessed.row_slice(row).to_vec())
+                        .unwrap_or_default();
+                    for (interaction_index, (direction, interaction)) in
+                        chip_interactions.iter().enumerate()
+                    {
+                        let (fields, count) = eval_interaction(interaction, &preprocessed, &main);
+                        if count.is_zero() {
+                            continue;
+                        }
+                        occurrences.push(Occurrence {
+                            chip_index,
+                            interaction_index,
+                            row,
+                            direction: *direction,
+                            fields,
+                            count,
+                        });
+                    }
+                }
+            }
+
+            interactions.push(chip_interactions);
+        }
+
+        let mut buses: BTreeMap<usize, BTreeMap<Vec<u64>, F>> = BTreeMap::new();
+        for occurrence in occurrences.iter() {
+            let (_, interaction) =
+                &interactions[occurrence.chip_index][occurrence.interaction_index];
+            let net = buses
+                .entry(interaction.argument_index)
+                .or_default()
+                .entry(occurrence.fields.clone())
+                .or_insert(F::zero());
+            match occurrence.direction {
+                Direction::Send => *net += occurrence.count,
+                Direction::Receive => *net -= occurrence.count,
+            }
+        }
+
+        // The columns of each field are only looked up for interactions with unmatched
+        // messages, once per interaction.
+        let mut interaction_columns = BTreeMap::new();
+        let unmatched = occurrences
+            .into_iter()
+            .filter_map(|occurrence| {
+                let chip = &chips[occurrence.chip_index];
+                let (_, interaction) =
+                    &interactions[occurrence.chip_index][occurrence.interaction_index];
+                let bus = interaction.argument_index;
+                if buses[&bus][&occurrence.fields].is_zero() {
+                    return None;
+                }
+
+                let columns = interaction_columns
+                    .entry((occurrence.chip_index, occurrence.interaction_index))
+                    .or_insert_with(|| {
+                        let (preprocessed_names, main_names) = column_names::<F>(chip);
+                        interaction
+                            .fields
+                            .iter()
+                            .map(|field| field_columns(field, &preprocessed_names, &main_names))
+                            .collect_vec()
+                    })
+                    .clone();
+                Some(UnmatchedMessage {
+                    bus,
+                    chip: chip_name(chip),
+                    row: occurrence.row,
+                    direction: occurrence.direction,
+                    fields: occurrence.fields,
+                    count: occurrence.count,
+                    columns,
+                })
+            })
+            .collect();
+
+        BusBalanceReport { buses, unmatched }
+    }
+}
+
+/// The sends and receives of `chip`.
+pub(crate) fn chip_interactions<F: Field, C: InteractionAir<F>>(
+    chip: &C,
+) -> Vec<(Direction, Interaction<F>)> {
+    chip.sends()
+        .into_iter()
+        .map(|interaction| (Direction::Send, interaction))
+        .chain(
+            chip.receives()
+                .into_iter()
+                .map(|interaction| (Direction::Receive, interaction)),
+        )
+        .collect()
+}
+
+/// Evaluates `interaction` on a row, returning its fields as canonical values and
+/// its multiplicity.
+pub(crate) fn eval_interaction<F: PrimeField64>(
+    interaction: &Interaction<F>,
+    preprocessed: &[F],
+    main: &[F],
+) -> (Vec<u64>, F) {
+    let fields = interaction
+        .fields
+        .iter()
+        .map(|field| field.apply::<F, F>(preprocessed, main).as_canonical_u64())
+        .collect();
+    let count = interaction.count.apply::<F, F>(preprocessed, main);
+    (fields, count)
+}
+
+/// The name of the chip's `KeccakMachineChip` variant.
+pub(crate) fn chip_name(chip: &KeccakMachineChip) -> String {
+    let name = format!("{chip:?}");
+    name.split('(').next().unwrap_or_default().into()
+}
+
+pub(crate) fn bus_name(bus: usize) -> String {
+    KeccakMachineBus::ALL
+        .iter()
+        .find(|&&b| b as usize == bus)
+        .map(|b| format!("{b:?}"))
+        .unwrap_or_else(|| format!("{bus}"))
+}
+
+/// The preprocessed and main column names of `chip`. These come from the `Columnar`
+/// headers with the `air-logger` feature, and are plain column indices otherwise.
+#[cfg(feature = "air-logger")]
+fn column_names<F: PrimeField64>(chip: &KeccakMachineChip) -> (Vec<String>, Vec<String>) {
+    use p3_air_util::AirLogger;
+
+    (chip.preprocessed_headers(), chip.main_headers())
+}
+
+#[cfg(not(feature = "air-logger"))]
+fn column_names<F: PrimeField64>(chip: &KeccakMachineChip) -> (Vec<String>, Vec<String>) {
+    let preprocessed_width = BaseAir::<F>::preprocessed_trace(chip)
+        .map(|preprocessed| preprocessed.width())
+        .unwrap_or_default();
+    let main_width = BaseAir::<F>::width(chip);
+    (
+        (0..preprocessed_width)
+            .map(|i| format!("preprocessed[{i}]"))
+            .collect(),
+        (0..main_width).map(|i| format!("main[{i}]")).collect(),
+    )
+}
+
+/// The columns `field` depends on. `VirtualPairCol` doesn't expose its columns, so
+/// they are found by evaluating it on every unit vector.
+fn field_columns<F: PrimeField64>(
+    field: &VirtualPairCol<F>,
+    preprocessed_names: &[String],
+    main_names: &[String],
+) -> Vec<String> {
+    let mut preprocessed = vec![F::zero(); preprocessed_names.len()];
+    let mut main = vec![F::zero(); main_names.len()];
+    let constant = field.apply::<F, F>(&preprocessed, &main);
+
+    let mut columns = vec![];
+    for (i, name) in preprocessed_names.iter().enumerate() {
+        preprocessed[i] = F::one();
+        if field.apply::<F, F>(&preprocessed, &main) != constant {
+            columns.push(name.clone());
+        }
+        preprocessed[i] = F::zero();
+    }
+    for (i, name) in main_names.iter().enumerate() {
+        main[i] = F::one();
+        if field.apply::<F, F>(&preprocessed, &main) != constant {
+            columns.push(name.clone());
+        }
+        main[i] = F::zero();
+    }
+    columns
+}
@@ -5,10 +5,26 @@ extern crate alloc;
 mod airs;
 mod bus;
 pub mod chips;
-mod config;
+pub mod config;
+mod debug;
 mod machine;
+mod proof;
+mod report;
 #[cfg(test)]
 mod test_util;
+#[cfg(feature = "prover")]
 mod trace;
 
+pub use debug::*;
 pub use machine::*;
+pub use proof::*;
+pub use report::*;
+#[cfg(feature = "prover")]
+pub use trace::{
+    generate_contract_address_trace, generate_eip712_trace, generate_eth_address_trace,
+    generate_hash_trace, generate_incremental_tree_trace, generate_leaf_preimage_trace,
+    generate_machine_trace, generate_merkle_path_trace, generate_mmr_append_trace,
+    generate_mmr_inclusion_trace, generate_sorted_pair_trace, generate_storage_slot_trace,
+    generate_sum_tree_trace, generate_tree_trace, Deployment, Eip712Traces,
+    MachineIncrementalTreeState, MachineMmrPeaks, MachineSumNode,
+};
@@ -1,25 +1,170 @@
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
 
+use p3_field::AbstractField;
+use p3_keccak::Keccak256Hash;
 use p3_machine::machine::Machine;
+use p3_symmetric::{CompressionFunctionFromHasher, CryptographicHasher};
 
 use crate::{
     bus::KeccakMachineBus,
     chips::{
-        keccak_permute::KeccakPermuteChip, keccak_sponge::KeccakSpongeChip,
-        merkle_root::MerkleRootChip, xor::XorChip, KeccakMachineChip,
+        contract_address::ContractAddressChip,
+        eip712::Eip712Chip,
+        eth_address::EthAddressChip,
+        incremental_merkle_tree::{zero_hashes, IncrementalMerkleTreeChip},
+        keccak_permute::KeccakPermuteChip,
+        keccak_sponge::{trace::SpongeCall, KeccakSpongeChip},
+        memory::{trace::image_num_words, MemoryChip},
+        memory_image::MemoryImageChip,
+        merkle_root::{MerkleRootChip, RootWrite},
+        merkle_sum_root::MerkleSumRootChip,
+        merkle_tree::MerkleTreeChip,
+        mmr::MmrChip,
+        range_checker::RangeCheckerChip,
+        storage_slot::StorageSlotChip,
+        xor::XorChip,
+        KeccakMachineChip, DIGEST_WIDTH, INCREMENTAL_TREE_DEPTH, MEMORY_WORD_BYTES,
+        MERKLE_TREE_DEPTH,
     },
 };
 
-pub struct KeccakMachine;
+/// Number of public values, the bytes of the Keccak256 hash of the output words.
+pub const NUM_PUBLIC_VALUES: usize = DIGEST_WIDTH;
+
+/// Depth of the paths proven by the `MerkleRoot` chip of a machine.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MerkleDepth {
+    /// `MERKLE_TREE_DEPTH`, which the other chips of the machine use too.
+    #[default]
+    Depth8,
+    Depth16,
+    Depth32,
+}
+
+impl MerkleDepth {
+    /// Returns the depth of the paths, or `None` if the machine can't prove paths of
+    /// that depth.
+    pub const fn new(depth: usize) -> Option<Self> {
+        match depth {
+            MERKLE_TREE_DEPTH => Some(MerkleDepth::Depth8),
+            16 => Some(MerkleDepth::Depth16),
+            32 => Some(MerkleDepth::Depth32),
+            _ => None,
+        }
+    }
+
+    pub const fn depth(self) -> usize {
+        match self {
+            MerkleDepth::Depth8 => MERKLE_TREE_DEPTH,
+            MerkleDepth::Depth16 => 16,
+            MerkleDepth::Depth32 => 32,
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct KeccakMachine {
+    /// Initial memory image, committed to in the verifying key.
+    pub image: Vec<u8>,
+
+    /// Word addresses of the output words, whose final values the public values
+    /// commit to.
+    pub output: Range<usize>,
+
+    /// Depth of the paths proven by the `MerkleRoot` chip.
+    pub merkle_depth: MerkleDepth,
+
+    /// Memory operations of the sponge, committed to in the verifying key. These are
+    /// the only hashes of memory regions the machine can prove.
+    pub sponge_calls: Vec<SpongeCall>,
+}
+
+impl KeccakMachine {
+    /// Returns the machine proving the path of a leaf to its root, through a path of
+    /// depth `merkle_depth`. The root, the leaf hash and the leaf index are written to
+    /// the start of the memory image, which is the output.
+    pub fn merkle_path(merkle_depth: MerkleDepth) -> Self {
+        let num_words = RootWrite::num_words(DIGEST_WIDTH);
+        Self {
+            image: vec![0; num_words * MEMORY_WORD_BYTES],
+            output: 0..num_words,
+            merkle_depth,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the machine proving the Keccak256 hash of `input`, which is placed at
+    /// the start of the memory image. The digest is written to the words following
+    /// it, which are the output.
+    pub fn hash(input: &[u8]) -> Self {
+        let dst_addr = image_num_words::<MEMORY_WORD_BYTES>(input);
+        let mut image = input.to_vec();
+        image.resize(dst_addr * MEMORY_WORD_BYTES + DIGEST_WIDTH, 0);
+        Self {
+            image,
+            output: dst_addr..dst_addr + DIGEST_WIDTH / MEMORY_WORD_BYTES,
+            sponge_calls: vec![SpongeCall {
+                timestamp: 0,
+                addr: 0,
+                len: input.len() as u32,
+                dst_addr: dst_addr as u32,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Returns the machine proving the root of the tree with the given `leaves`, along
+    /// with a Keccak256 commitment to the leaves, the hash of their concatenation. The
+    /// leaves are placed at the start of the memory image, and the root and the
+    /// commitment are written to the words following them, which are the output.
+    pub fn tree(leaves: &[[u8; DIGEST_WIDTH]]) -> Self {
+        let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+        let root_addr = leaves.len() * words_per_digest;
+        let commitment_addr = root_addr + words_per_digest;
+        let mut image = leaves.concat();
+        image.resize((commitment_addr + words_per_digest) * MEMORY_WORD_BYTES, 0);
+        Self {
+            image,
+            output: root_addr..commitment_addr + words_per_digest,
+            sponge_calls: vec![SpongeCall {
+                timestamp: 0,
+                addr: 0,
+                len: (leaves.len() * DIGEST_WIDTH) as u32,
+                dst_addr: commitment_addr as u32,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Returns the public values of a proof whose output words end up holding
+    /// `output`.
+    pub fn public_values<F: AbstractField>(output: &[u8]) -> Vec<F> {
+        Keccak256Hash
+            .hash_iter(output.iter().copied())
+            .map(F::from_canonical_u8)
+            .to_vec()
+    }
+
+    fn merkle_root_chip<const DEPTH: usize>() -> MerkleRootChip<DEPTH, DIGEST_WIDTH> {
+        MerkleRootChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+        }
+    }
+}
 
 impl Machine for KeccakMachine {
     type Chip = KeccakMachineChip;
     type Bus = KeccakMachineBus;
 
     fn chips(&self) -> Vec<KeccakMachineChip> {
-        let merkle_tree_chip = MerkleRootChip {
-            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
-            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+        let merkle_root_chip = match self.merkle_depth {
+            MerkleDepth::Depth8 => KeccakMachineChip::MerkleRoot(Self::merkle_root_chip()),
+            MerkleDepth::Depth16 => KeccakMachineChip::MerkleRoot16(Self::merkle_root_chip()),
+            MerkleDepth::Depth32 => KeccakMachineChip::MerkleRoot32(Self::merkle_root_chip()),
         };
         let keccak_sponge_chip = KeccakSpongeChip {
             bus_input: KeccakMachineBus::KeccakSpongeInput as usize,
@@ -28,10 +173,13 @@ impl Machine for KeccakMachine {
             bus_permute_output: KeccakMachineBus::KeccakPermuteOutput as usize,
             bus_xor_input: KeccakMachineBus::XorInput as usize,
             bus_xor_output: KeccakMachineBus::XorOutput as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_call: KeccakMachineBus::SpongeCall as usize,
+        };
+        let range_chip = RangeCheckerChip {
+            bus_range_8: KeccakMachineBus::Range8 as usize,
         };
-        // let range_chip = RangeCheckerChip {
-        //     bus_range_8: KeccakMachineBus::Range8 as usize,
-        // };
         let xor_chip = XorChip {
             bus_input: KeccakMachineBus::XorInput as usize,
             bus_output: KeccakMachineBus::XorOutput as usize,
@@ -40,35 +188,139 @@ impl Machine for KeccakMachine {
             bus_input: KeccakMachineBus::KeccakPermuteInput as usize,
             bus_output: KeccakMachineBus::KeccakPermuteOutput as usize,
         };
-        // let memory_chip = MemoryChip {
-        //     bus_memory: KeccakMachineBus::Memory as usize,
-        //     bus_range_8: KeccakMachineBus::Range8 as usize,
-        // };
+        let memory_chip = MemoryChip {
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_memory_init: KeccakMachineBus::MemoryInit as usize,
+            bus_memory_final: KeccakMachineBus::MemoryFinal as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+        };
+        let memory_image_chip = MemoryImageChip {
+            image: self.image.clone(),
+            output: self.output.clone(),
+            calls: self.sponge_calls.clone(),
+            bus_memory_init: KeccakMachineBus::MemoryInit as usize,
+            bus_memory_final: KeccakMachineBus::MemoryFinal as usize,
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_call: KeccakMachineBus::SpongeCall as usize,
+        };
+        let merkle_tree_chip = MerkleTreeChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_node: KeccakMachineBus::MerkleTreeNode as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+        };
+        let merkle_sum_root_chip = MerkleSumRootChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+        };
+        let mmr_chip = MmrChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+        };
+        let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+        let incremental_merkle_tree_chip = IncrementalMerkleTreeChip {
+            zero_hashes: zero_hashes(INCREMENTAL_TREE_DEPTH, &hasher),
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+        };
+        let eth_address_chip = EthAddressChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+        };
+        let contract_address_chip = ContractAddressChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+        };
+        let storage_slot_chip = StorageSlotChip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+            bus_range_8: KeccakMachineBus::Range8 as usize,
+        };
+        let eip712_chip = Eip712Chip {
+            bus_hasher_input: KeccakMachineBus::KeccakSpongeInput as usize,
+            bus_hasher_output: KeccakMachineBus::KeccakSpongeOutput as usize,
+            bus_memory: KeccakMachineBus::Memory as usize,
+        };
 
         vec![
-            KeccakMachineChip::MerkleRoot(merkle_tree_chip),
+            merkle_root_chip,
             KeccakMachineChip::KeccakSponge(keccak_sponge_chip),
             KeccakMachineChip::Xor(xor_chip),
             KeccakMachineChip::KeccakPermute(keccak_permute_chip),
-            // KeccakMachineChip::Range8(range_chip),
-            // KeccakMachineChip::Memory(memory_chip),
+            KeccakMachineChip::Range8(range_chip),
+            KeccakMachineChip::Memory(memory_chip),
+            KeccakMachineChip::MemoryImage(memory_image_chip),
+            KeccakMachineChip::MerkleTree(merkle_tree_chip),
+            KeccakMachineChip::MerkleSumRoot(merkle_sum_root_chip),
+            KeccakMachineChip::Mmr(mmr_chip),
+            KeccakMachineChip::IncrementalMerkleTree(incremental_merkle_tree_chip),
+            KeccakMachineChip::EthAddress(eth_address_chip),
+            KeccakMachineChip::ContractAddress(contract_address_chip),
+            KeccakMachineChip::StorageSlot(storage_slot_chip),
+            KeccakMachineChip::Eip712(eip712_chip),
         ]
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "prover"))]
 mod tests {
     use super::*;
     use crate::{
-        chips::{DIGEST_WIDTH, MERKLE_TREE_DEPTH},
-        config::{default_challenger, default_config, MyConfig},
-        trace::generate_machine_trace,
+        chips::{
+            contract_address::{
+                address_word,
+                columns::{ContractAddressCols, NONCE_BYTES},
+            },
+            eip712::{columns::Eip712Cols, TypedData},
+            eth_address::columns::EthAddressCols,
+            incremental_merkle_tree::columns::IncrementalMerkleTreeCols,
+            keccak_sponge::{
+                columns::{KeccakSpongeCols, KECCAK_RATE_BYTES},
+                trace::KeccakSpongeOp,
+            },
+            merkle_root::columns::MerkleRootCols,
+            merkle_sum_root::columns::MerkleSumRootCols,
+            merkle_tree::columns::MerkleTreeCols,
+            mmr::columns::MmrCols,
+            storage_slot::{columns::StorageSlotCols, StoragePath, StorageStep},
+            DIGEST_WIDTH, ETH_ADDRESS_BYTES, MEMORY_WORD_BYTES, MERKLE_SUM_BYTES,
+            MERKLE_TREE_DEPTH, MMR_HEIGHT, PUBLIC_KEY_BYTES,
+        },
+        config::{
+            default_challenger, default_config, FriParameters, GoldilocksConfig, Mersenne31Config,
+            MyConfig, StarkConfigBuilder, Val,
+        },
+        proof::{serialize, verify_keccak_machine_proof, ProofError, ProofFormat},
+        trace::{
+            generate_contract_address_trace, generate_eip712_trace, generate_eth_address_trace,
+            generate_hash_trace, generate_incremental_tree_trace, generate_leaf_preimage_trace,
+            generate_machine_trace, generate_merkle_path_trace, generate_mmr_append_trace,
+            generate_mmr_inclusion_trace, generate_sorted_pair_trace, generate_storage_slot_trace,
+            generate_sum_tree_trace, generate_traces as generate_chip_traces, generate_tree_trace,
+            ChipTraces, Deployment, MachineIncrementalTreeState, MachineMmrPeaks, MachineSumNode,
+        },
+        Direction, InteractionReport,
     };
 
+    use alloc::collections::BTreeMap;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
     use itertools::Itertools;
+    use p3_field::{AbstractField, Field, PrimeField64};
     use p3_keccak::Keccak256Hash;
     use p3_machine::error::VerificationError;
-    use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher};
+    use p3_matrix::{dense::RowMajorMatrix, Matrix};
+    use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher, CryptographicHasher};
+    use p3_uni_stark::{StarkGenericConfig, Val as StarkVal};
     use rand::{rngs::StdRng, Rng, SeedableRng};
     use tracing_forest::{util::LevelFilter, ForestLayer};
     use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
@@ -95,10 +347,58 @@ mod tests {
         digests
     }
 
+    /// The traces proving the path of a random leaf in a tree of random leaves, along
+    /// with the machine and the output: the root, the leaf and the leaf index.
+    fn generate_traces<SC>(
+        seed: u64,
+    ) -> (
+        KeccakMachine,
+        Vec<u8>,
+        Vec<Option<RowMajorMatrix<StarkVal<SC>>>>,
+    )
+    where
+        SC: StarkGenericConfig,
+        StarkVal<SC>: PrimeField64,
+    {
+        let mut seeded_rng = StdRng::seed_from_u64(seed);
+
+        const NUM_LEAVES: usize = 1 << MERKLE_TREE_DEPTH;
+
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        let leaf_hashes = (0..NUM_LEAVES).map(|_| seeded_rng.gen()).collect_vec();
+        let digests = generate_digests(&leaf_hashes, &hasher);
+
+        let leaf_index = seeded_rng.gen_range(0..NUM_LEAVES);
+        let expected_output = [
+            digests.last().unwrap()[0].as_slice(),
+            &leaf_hashes[leaf_index],
+            &(leaf_index as u64).to_le_bytes(),
+        ]
+        .concat();
+        let (output, machine, traces) =
+            generate_machine_trace::<SC, _>(leaf_index, digests, &hasher);
+        assert_eq!(output, expected_output);
+        (machine, output, traces)
+    }
+
+    fn prove_and_verify(
+        machine: &KeccakMachine,
+        traces: Vec<Option<RowMajorMatrix<Val>>>,
+        public_values: &[Val],
+    ) -> Result<(), VerificationError> {
+        let (pk, vk) = machine.setup(&default_config());
+
+        let config = default_config();
+        let mut challenger = default_challenger();
+        let proof = machine.prove(&config, &mut challenger, &pk, traces, public_values);
+
+        let mut challenger = default_challenger();
+        machine.verify(&config, &mut challenger, &vk, &proof, public_values)
+    }
+
     #[test]
     fn test_machine_prove() -> Result<(), VerificationError> {
         const RANDOM_SEED: u64 = 0;
-        let mut seeded_rng = StdRng::seed_from_u64(RANDOM_SEED);
 
         let env_filter = EnvFilter::builder()
             .with_default_directive(LevelFilter::INFO.into())
@@ -108,23 +408,1137 @@ mod tests {
             .with(ForestLayer::default())
             .init();
 
+        let (machine, output, traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        prove_and_verify(&machine, traces, &KeccakMachine::public_values(&output))
+    }
+
+    #[test]
+    fn test_machine_prove_poseidon2() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let (machine, output, traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+
+        let (config, challenger) = StarkConfigBuilder::new()
+            .fri(FriParameters::fast_verification(100))
+            .poseidon2();
+        let (pk, vk) = machine.setup(&config);
+        let public_values = KeccakMachine::public_values(&output);
+        let proof = machine.prove(
+            &config,
+            &mut challenger.clone(),
+            &pk,
+            traces,
+            &public_values,
+        );
+        machine.verify(
+            &config,
+            &mut challenger.clone(),
+            &vk,
+            &proof,
+            &public_values,
+        )
+    }
+
+    #[test]
+    fn test_machine_prove_goldilocks() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let (machine, output, traces) = generate_traces::<GoldilocksConfig>(RANDOM_SEED);
+
+        let (config, challenger) = StarkConfigBuilder::new().goldilocks();
+        let (pk, vk) = machine.setup(&config);
+        let public_values = KeccakMachine::public_values(&output);
+        let proof = machine.prove(
+            &config,
+            &mut challenger.clone(),
+            &pk,
+            traces,
+            &public_values,
+        );
+        machine.verify(
+            &config,
+            &mut challenger.clone(),
+            &vk,
+            &proof,
+            &public_values,
+        )
+    }
+
+    #[test]
+    fn test_machine_prove_mersenne31() -> Result<(), VerificationError> {
+        const RANDOM_SEED: u64 = 0;
+
+        let (machine, output, traces) = generate_traces::<Mersenne31Config>(RANDOM_SEED);
+
+        let (config, challenger) = StarkConfigBuilder::new().mersenne31();
+        let (pk, vk) = machine.setup(&config);
+        let public_values = KeccakMachine::public_values(&output);
+        let proof = machine.prove(
+            &config,
+            &mut challenger.clone(),
+            &pk,
+            traces,
+            &public_values,
+        );
+        machine.verify(
+            &config,
+            &mut challenger.clone(),
+            &vk,
+            &proof,
+            &public_values,
+        )
+    }
+
+    #[test]
+    fn test_machine_wrong_leaf() {
+        const RANDOM_SEED: u64 = 0;
+
+        // The root of the path, claimed for another leaf.
+        let (machine, output, traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        assert_wrong_output_rejected(&machine, traces, &output, DIGEST_WIDTH);
+    }
+
+    #[test]
+    fn test_machine_wrong_leaf_index() {
+        const RANDOM_SEED: u64 = 0;
+
+        // The root and the leaf of the path, claimed at another index.
+        let (machine, output, traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        assert_wrong_output_rejected(&machine, traces, &output, 2 * DIGEST_WIDTH);
+    }
+
+    /// A statement proven by the machine, with the traces proving it.
+    struct Statement {
+        name: &'static str,
+        machine: KeccakMachine,
+        traces: Vec<Option<RowMajorMatrix<Val>>>,
+        output: Vec<u8>,
+        /// A byte of `output` that `test_machine_statements` claims wrong.
+        wrong_output_byte: usize,
+    }
+
+    #[test]
+    fn test_machine_statements() {
+        let statements = [
+            merkle_path_statement(),
+            hash_statement(),
+            leaf_preimage_statement(),
+            tree_statement(),
+            sum_tree_statement(),
+            mmr_append_statement(),
+            // Leaves under the highest, a middle and the lowest peak.
+            mmr_inclusion_statement(5),
+            mmr_inclusion_statement(9),
+            mmr_inclusion_statement(10),
+            incremental_tree_statement(),
+            eth_address_statement(),
+            contract_address_statement(),
+            eip712_statement(),
+            storage_slot_statement(),
+        ];
+        for statement in statements {
+            let Statement {
+                name,
+                machine,
+                traces,
+                output,
+                wrong_output_byte,
+            } = statement;
+            assert!(
+                machine.debug_bus_balance(&traces).is_balanced(),
+                "{name}: the buses don't balance"
+            );
+            prove_and_verify(
+                &machine,
+                traces.clone(),
+                &KeccakMachine::public_values(&output),
+            )
+            .unwrap_or_else(|err| panic!("{name}: {err:?}"));
+            assert_wrong_output_rejected(&machine, traces, &output, wrong_output_byte);
+        }
+    }
+
+    /// Asserts that the traces of `statement` don't prove its output once the column
+    /// `col` is changed in the trace of the chip matching `is_chip`, on the first row
+    /// where the column `flag` is set.
+    fn assert_tampered_witness_rejected(
+        statement: Statement,
+        is_chip: fn(&KeccakMachineChip) -> bool,
+        flag: usize,
+        col: usize,
+    ) {
+        let Statement {
+            machine,
+            mut traces,
+            output,
+            ..
+        } = statement;
+        let chip_index = machine.chips().iter().position(is_chip).unwrap();
+        let trace = traces[chip_index].as_mut().unwrap();
+        let row = trace
+            .values
+            .chunks_exact(trace.width)
+            .position(|row| row[flag].is_one())
+            .unwrap();
+        trace.values[row * trace.width + col] += Val::one();
+        assert_proof_rejected(&machine, traces, &KeccakMachine::public_values(&output));
+    }
+
+    /// The path of a random leaf in a tree of random leaves.
+    fn merkle_path_statement() -> Statement {
+        const RANDOM_SEED: u64 = 0;
+
+        let (machine, output, traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        Statement {
+            name: "merkle path",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 0,
+        }
+    }
+
+    #[test]
+    fn test_machine_merkle_path_tampered_leaf() {
+        // The leaf written next to the root isn't the one the path starts from.
+        let col_map = MerkleRootCols::<usize, MERKLE_TREE_DEPTH, DIGEST_WIDTH>::col_map();
+        assert_tampered_witness_rejected(
+            merkle_path_statement(),
+            |chip| matches!(chip, KeccakMachineChip::MerkleRoot(_)),
+            col_map.writes_root,
+            col_map.leaf[0],
+        );
+    }
+
+    #[test]
+    fn test_machine_merkle_path_tampered_index() {
+        // The index written next to the root isn't the one the path takes.
+        let col_map = MerkleRootCols::<usize, MERKLE_TREE_DEPTH, DIGEST_WIDTH>::col_map();
+        assert_tampered_witness_rejected(
+            merkle_path_statement(),
+            |chip| matches!(chip, KeccakMachineChip::MerkleRoot(_)),
+            col_map.writes_root,
+            col_map.index_bytes[0],
+        );
+    }
+
+    /// The hash of an input spanning three blocks, which doesn't end on a word
+    /// boundary.
+    fn hash_statement() -> Statement {
+        const NUM_BYTES: usize = 2 * KECCAK_RATE_BYTES + 13;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let input = (0..NUM_BYTES).map(|_| seeded_rng.gen()).collect_vec();
+        let (digest, machine, traces) = generate_hash_trace::<MyConfig>(&input);
+        assert_eq!(digest, Keccak256Hash.hash_iter(input));
+
+        let output = digest.to_vec();
+        Statement {
+            name: "hash",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 0,
+        }
+    }
+
+    /// Asserts that `traces` don't prove `public_values` on `machine`, either because
+    /// proving fails or because the proof doesn't verify.
+    fn assert_proof_rejected(
+        machine: &KeccakMachine,
+        traces: Vec<Option<RowMajorMatrix<Val>>>,
+        public_values: &[Val],
+    ) {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            prove_and_verify(machine, traces, public_values)
+        }));
+        assert!(!matches!(result, Ok(Ok(()))));
+    }
+
+    /// Asserts that `traces`, whose output words end up holding `output`, don't prove
+    /// `output` with its byte at `i` changed.
+    fn assert_wrong_output_rejected(
+        machine: &KeccakMachine,
+        traces: Vec<Option<RowMajorMatrix<Val>>>,
+        output: &[u8],
+        i: usize,
+    ) {
+        let mut wrong_output = output.to_vec();
+        wrong_output[i] ^= 1;
+        assert_proof_rejected(
+            machine,
+            traces,
+            &KeccakMachine::public_values(&wrong_output),
+        );
+    }
+
+    #[test]
+    fn test_machine_hash_prefix() {
+        const NUM_BYTES: usize = 2 * KECCAK_RATE_BYTES + 13;
+        const PREFIX_BYTES: usize = KECCAK_RATE_BYTES;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let input = (0..NUM_BYTES).map(|_| seeded_rng.gen()).collect_vec();
+        let (_, machine, _) = generate_hash_trace::<MyConfig>(&input);
+
+        // Hashing a prefix of the input into the output words gives valid traces for a
+        // machine calling that hash, but not for the one calling the hash of the input.
+        let prefix_op = KeccakSpongeOp {
+            timestamp: 0,
+            addr: 0,
+            dst_addr: machine.output.start as u32,
+            is_memory_op: true,
+            input: input[..PREFIX_BYTES].to_vec(),
+        };
+        let prefix_machine = KeccakMachine {
+            sponge_calls: prefix_op.call().into_iter().collect(),
+            ..machine.clone()
+        };
+        let traces = generate_chip_traces::<MyConfig>(
+            &prefix_machine,
+            vec![prefix_op],
+            vec![],
+            BTreeMap::new(),
+            ChipTraces::default(),
+        );
+        assert!(prefix_machine.debug_bus_balance(&traces).is_balanced());
+        assert!(!machine.debug_bus_balance(&traces).is_balanced());
+
+        let prefix_digest = Keccak256Hash.hash_iter(input[..PREFIX_BYTES].iter().copied());
+        assert_proof_rejected(
+            &machine,
+            traces,
+            &KeccakMachine::public_values(&prefix_digest),
+        );
+    }
+
+    /// The traces proving the path of a leaf hashed from `data` in a tree of random
+    /// leaves, along with the machine and the output: `data`, padded to a word
+    /// boundary, the leaf, the root, the leaf again and the leaf index.
+    fn generate_preimage_traces(
+        data: &[u8],
+        double_hash: bool,
+    ) -> (KeccakMachine, Vec<u8>, Vec<Option<RowMajorMatrix<Val>>>) {
         const NUM_LEAVES: usize = 1 << MERKLE_TREE_DEPTH;
 
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let mut leaf_hashes: Vec<[u8; DIGEST_WIDTH]> =
+            (0..NUM_LEAVES).map(|_| seeded_rng.gen()).collect_vec();
+        let leaf_index = seeded_rng.gen_range(0..NUM_LEAVES);
+        let hash = Keccak256Hash.hash_iter(data.iter().copied());
+        leaf_hashes[leaf_index] = if double_hash {
+            Keccak256Hash.hash_iter(hash)
+        } else {
+            hash
+        };
+
         let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
-        let leaf_hashes = (0..NUM_LEAVES).map(|_| seeded_rng.gen()).collect_vec();
         let digests = generate_digests(&leaf_hashes, &hasher);
+        let mut output = data.to_vec();
+        output.resize(data.len().next_multiple_of(MEMORY_WORD_BYTES), 0);
+        output.extend(leaf_hashes[leaf_index]);
+        output.extend(digests.last().unwrap()[0]);
+        output.extend(leaf_hashes[leaf_index]);
+        output.extend((leaf_index as u64).to_le_bytes());
 
-        let leaf_index = seeded_rng.gen_range(0..NUM_LEAVES);
-        let machine = KeccakMachine;
+        let (machine, traces) = generate_leaf_preimage_trace::<MyConfig, _>(
+            leaf_index,
+            data,
+            double_hash,
+            digests,
+            &hasher,
+        );
+        (machine, output, traces)
+    }
 
-        let (pk, vk) = machine.setup(&default_config());
+    /// The path of a leaf hashed from its preimage.
+    fn leaf_preimage_statement() -> Statement {
+        // An `abi.encode(address, uint256)` preimage, hashed twice as in OpenZeppelin's
+        // `StandardMerkleTree`.
+        let mut data = [0u8; 64];
+        StdRng::seed_from_u64(1).fill(&mut data[12..]);
+        let (machine, output, traces) = generate_preimage_traces(&data, true);
+
+        Statement {
+            name: "leaf preimage",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 20,
+        }
+    }
+
+    #[test]
+    fn test_machine_leaf_preimage_bus_balance() {
+        const MERKLE_ROOT_TRACE_INDEX: usize = 0;
+
+        // Spans two blocks and doesn't end on a word boundary.
+        let data = (0..KECCAK_RATE_BYTES + 13).map(|i| i as u8).collect_vec();
+        let (machine, _, mut traces) = generate_preimage_traces(&data, false);
+        assert!(machine.debug_bus_balance(&traces).is_balanced());
+
+        // The leaf no longer matches the hash written to memory.
+        let merkle_root_trace = traces[MERKLE_ROOT_TRACE_INDEX].as_mut().unwrap();
+        let (_, rows, _) = unsafe {
+            merkle_root_trace
+                .values
+                .align_to_mut::<MerkleRootCols<Val, MERKLE_TREE_DEPTH, DIGEST_WIDTH>>()
+        };
+        rows[0].node[0] += Val::one();
+
+        let report = machine.debug_bus_balance(&traces);
+        assert!(report
+            .unmatched
+            .iter()
+            .any(|message| message.bus == KeccakMachineBus::Memory as usize
+                && message.chip == "MerkleRoot"
+                && message.row == 0));
+    }
+
+    #[test]
+    fn test_machine_prove_sorted_pair() -> Result<(), VerificationError> {
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaf_hash: [u8; DIGEST_WIDTH] = seeded_rng.gen();
+        let mut proof: [[u8; DIGEST_WIDTH]; MERKLE_TREE_DEPTH] = seeded_rng.gen();
+        // Siblings sharing a prefix with the node, or equal to it, are compared past
+        // their first byte.
+        let node = compress_sorted(leaf_hash, proof[0]);
+        proof[1] = node;
+        proof[1][DIGEST_WIDTH - 1] ^= 1;
+        proof[2] = compress_sorted(node, proof[1]);
+
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        let (output, machine, traces) =
+            generate_sorted_pair_trace::<MyConfig, _>(leaf_hash, proof, &hasher);
+
+        // OpenZeppelin's `MerkleProof.processProof`.
+        let expected_root = proof.into_iter().fold(leaf_hash, compress_sorted);
+        assert_eq!(output[..DIGEST_WIDTH], expected_root);
+        assert_eq!(output[DIGEST_WIDTH..2 * DIGEST_WIDTH], leaf_hash);
+
+        assert!(machine.debug_bus_balance(&traces).is_balanced());
+        prove_and_verify(&machine, traces, &KeccakMachine::public_values(&output))
+    }
+
+    #[test]
+    fn test_machine_prove_merkle_depth_16() -> Result<(), VerificationError> {
+        const DEPTH: usize = 16;
+        const LEAF_INDEX: usize = 0xbeef;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaf_hash: [u8; DIGEST_WIDTH] = seeded_rng.gen();
+        let siblings: [[u8; DIGEST_WIDTH]; DEPTH] = core::array::from_fn(|_| seeded_rng.gen());
+
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        let (output, machine, traces) = generate_merkle_path_trace::<MyConfig, _, DEPTH>(
+            LEAF_INDEX, leaf_hash, siblings, &hasher,
+        );
+
+        let expected_root =
+            siblings
+                .into_iter()
+                .enumerate()
+                .fold(leaf_hash, |node, (level, sibling)| {
+                    if (LEAF_INDEX >> level) & 1 == 0 {
+                        hasher.compress([node, sibling])
+                    } else {
+                        hasher.compress([sibling, node])
+                    }
+                });
+        let expected_output = [
+            expected_root.as_slice(),
+            &leaf_hash,
+            &(LEAF_INDEX as u64).to_le_bytes(),
+        ]
+        .concat();
+        assert_eq!(output, expected_output);
+        assert_eq!(machine.merkle_depth, MerkleDepth::Depth16);
+
+        assert!(machine.debug_bus_balance(&traces).is_balanced());
+        prove_and_verify(&machine, traces, &KeccakMachine::public_values(&output))
+    }
+
+    fn compress_sorted(a: [u8; DIGEST_WIDTH], b: [u8; DIGEST_WIDTH]) -> [u8; DIGEST_WIDTH] {
+        let (left, right) = if a < b { (a, b) } else { (b, a) };
+        Keccak256Hash.hash_iter(left.into_iter().chain(right))
+    }
+
+    /// The root of a tree and the commitment to its leaves.
+    fn tree_statement() -> Statement {
+        const NUM_LEAVES: usize = 16;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaves: Vec<[u8; DIGEST_WIDTH]> =
+            (0..NUM_LEAVES).map(|_| seeded_rng.gen()).collect_vec();
+        let (root, commitment, machine, traces) = generate_tree_trace::<MyConfig>(&leaves);
+
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        assert_eq!(root, generate_digests(&leaves, &hasher).last().unwrap()[0]);
+        assert_eq!(commitment, Keccak256Hash.hash_iter(leaves.concat()));
+
+        let output = [root, commitment].concat();
+        Statement {
+            name: "tree",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: DIGEST_WIDTH,
+        }
+    }
+
+    #[test]
+    fn test_machine_tree_tampered_leaf() {
+        // A bottom node isn't the leaf read from memory.
+        let col_map = MerkleTreeCols::<usize, DIGEST_WIDTH>::col_map();
+        assert_tampered_witness_rejected(
+            tree_statement(),
+            |chip| matches!(chip, KeccakMachineChip::MerkleTree(_)),
+            col_map.is_bottom,
+            col_map.left[0],
+        );
+    }
+
+    /// The root of a Merkle sum tree and its sum.
+    fn sum_tree_statement() -> Statement {
+        const LEAF_INDEX: usize = 5;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let balances: Vec<u64> = (0..1 << MERKLE_TREE_DEPTH)
+            .map(|_| seeded_rng.gen())
+            .collect();
+        let leaves = balances
+            .iter()
+            .map(|&balance| MachineSumNode {
+                hash: seeded_rng.gen(),
+                sum: u128::from(balance).to_be_bytes(),
+            })
+            .collect_vec();
+        let (root, machine, traces) = generate_sum_tree_trace::<MyConfig>(LEAF_INDEX, &leaves);
+
+        let total: u128 = balances.iter().map(|&balance| u128::from(balance)).sum();
+        assert_eq!(root.sum, total.to_be_bytes());
+
+        let output = [root.hash.as_slice(), &root.sum].concat();
+        Statement {
+            name: "sum tree",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: output.len() - 1,
+        }
+    }
+
+    #[test]
+    fn test_machine_sum_tree_tampered_sum() {
+        // The sum of the leaf isn't the one hashed.
+        let col_map =
+            MerkleSumRootCols::<usize, MERKLE_TREE_DEPTH, DIGEST_WIDTH, MERKLE_SUM_BYTES>::col_map(
+            );
+        assert_tampered_witness_rejected(
+            sum_tree_statement(),
+            |chip| matches!(chip, KeccakMachineChip::MerkleSumRoot(_)),
+            col_map.is_real,
+            col_map.node_sum[MERKLE_SUM_BYTES - 1],
+        );
+    }
+
+    /// The roots of a Merkle Mountain Range before and after appending leaves.
+    fn mmr_append_statement() -> Statement {
+        const NUM_LEAVES: usize = 11;
+        const NUM_APPENDED: usize = 6;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaves: Vec<[u8; DIGEST_WIDTH]> = (0..NUM_LEAVES + NUM_APPENDED)
+            .map(|_| seeded_rng.gen())
+            .collect_vec();
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        let peaks = MachineMmrPeaks::from_leaves(&leaves[..NUM_LEAVES], &hasher);
+        let (old_root, new_root, machine, traces) =
+            generate_mmr_append_trace::<MyConfig>(&peaks, &leaves[NUM_LEAVES..]);
+
+        assert_eq!(old_root, peaks.root(&hasher));
+        assert_eq!(
+            new_root,
+            MachineMmrPeaks::from_leaves(&leaves, &hasher).root(&hasher)
+        );
+
+        let output = [&leaves[NUM_LEAVES..], &[old_root, new_root][..]]
+            .concat()
+            .concat();
+        Statement {
+            name: "mmr append",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: output.len() - 1,
+        }
+    }
+
+    #[test]
+    fn test_machine_mmr_tampered_leaf() {
+        // An appended leaf isn't the one read from memory.
+        let col_map = MmrCols::<usize, MMR_HEIGHT, DIGEST_WIDTH>::col_map();
+        assert_tampered_witness_rejected(
+            mmr_append_statement(),
+            |chip| matches!(chip, KeccakMachineChip::Mmr(_)),
+            col_map.reads_node,
+            col_map.node[0],
+        );
+    }
+
+    /// The roots of an incremental tree before and after inserting leaves.
+    fn incremental_tree_statement() -> Statement {
+        const NUM_LEAVES: usize = 11;
+        const NUM_INSERTED: usize = 6;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaves: Vec<[u8; DIGEST_WIDTH]> = (0..NUM_LEAVES + NUM_INSERTED)
+            .map(|_| seeded_rng.gen())
+            .collect_vec();
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        let state = MachineIncrementalTreeState::from_leaves(&leaves[..NUM_LEAVES], &hasher);
+        let (old_root, new_root, machine, traces) =
+            generate_incremental_tree_trace::<MyConfig>(&state, &leaves[NUM_LEAVES..]);
+
+        assert_eq!(old_root, state.root(&hasher));
+        assert_eq!(
+            new_root,
+            MachineIncrementalTreeState::from_leaves(&leaves, &hasher).root(&hasher)
+        );
+
+        let output = [&leaves[NUM_LEAVES..], &[old_root, new_root][..]]
+            .concat()
+            .concat();
+        Statement {
+            name: "incremental tree",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 0,
+        }
+    }
+
+    #[test]
+    fn test_machine_incremental_tree_tampered_leaf() {
+        // An inserted leaf isn't the one read from memory.
+        let col_map =
+            IncrementalMerkleTreeCols::<usize, INCREMENTAL_TREE_DEPTH, DIGEST_WIDTH>::col_map();
+        assert_tampered_witness_rejected(
+            incremental_tree_statement(),
+            |chip| matches!(chip, KeccakMachineChip::IncrementalMerkleTree(_)),
+            col_map.reads_node,
+            col_map.node[0],
+        );
+    }
+
+    /// The Ethereum addresses of public keys.
+    fn eth_address_statement() -> Statement {
+        const NUM_KEYS: usize = 5;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let public_keys = (0..NUM_KEYS)
+            .map(|_| {
+                let mut public_key = [0; PUBLIC_KEY_BYTES];
+                seeded_rng.fill(&mut public_key[..]);
+                public_key
+            })
+            .collect_vec();
+        let (addresses, machine, traces) = generate_eth_address_trace::<MyConfig>(&public_keys);
+
+        for (public_key, address) in public_keys.iter().zip(&addresses) {
+            let digest: [u8; DIGEST_WIDTH] = Keccak256Hash.hash_iter(*public_key);
+            assert_eq!(*address, digest[DIGEST_WIDTH - ETH_ADDRESS_BYTES..]);
+        }
+
+        let output = eth_address_output(&public_keys, &addresses);
+        Statement {
+            name: "eth address",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: output.len() - 1,
+        }
+    }
+
+    #[test]
+    fn test_machine_eth_address_tampered_key() {
+        // The hashed public key isn't the one read from memory.
+        let col_map = EthAddressCols::<usize>::col_map();
+        assert_tampered_witness_rejected(
+            eth_address_statement(),
+            |chip| matches!(chip, KeccakMachineChip::EthAddress(_)),
+            col_map.is_real,
+            col_map.public_key[0],
+        );
+    }
+
+    /// The output of the proof of the Ethereum `addresses` of `public_keys`: the keys,
+    /// and then the addresses, each padded to a word.
+    fn eth_address_output(
+        public_keys: &[[u8; PUBLIC_KEY_BYTES]],
+        addresses: &[[u8; ETH_ADDRESS_BYTES]],
+    ) -> Vec<u8> {
+        let address_words = addresses.iter().map(address_word).collect_vec();
+        [public_keys.concat(), address_words.concat()].concat()
+    }
+
+    /// The addresses of contracts deployed with `CREATE2` and `CREATE`.
+    fn contract_address_statement() -> Statement {
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let deployer: [u8; ETH_ADDRESS_BYTES] = seeded_rng.gen();
+        let salt: [u8; DIGEST_WIDTH] = seeded_rng.gen();
+
+        // The longer init code takes several sponge blocks.
+        let deployments = [
+            Deployment::Create2 {
+                deployer,
+                salt,
+                init_code: vec![0xde, 0xad, 0xbe, 0xef],
+            },
+            Deployment::Create2 {
+                deployer,
+                salt,
+                init_code: (0..500).map(|_| seeded_rng.gen()).collect(),
+            },
+            Deployment::Create { deployer, nonce: 0 },
+            Deployment::Create {
+                deployer,
+                nonce: 0x1234,
+            },
+        ];
+        let (addresses, machine, traces) =
+            generate_contract_address_trace::<MyConfig>(&deployments);
+
+        for (deployment, address) in deployments.iter().zip(&addresses) {
+            let preimage = match deployment {
+                Deployment::Create2 {
+                    deployer,
+                    salt,
+                    init_code,
+                } => {
+                    let init_code_hash: [u8; DIGEST_WIDTH] =
+                        Keccak256Hash.hash_iter(init_code.iter().copied());
+                    [&[0xff][..], deployer, salt, &init_code_hash].concat()
+                }
+                Deployment::Create { deployer, nonce } => {
+                    let nonce_bytes = nonce.to_be_bytes();
+                    let nonce_bytes = &nonce_bytes[(nonce.leading_zeros() / 8) as usize..];
+                    let encoded_nonce = match nonce_bytes {
+                        [] => vec![0x80],
+                        _ => [&[0x80 + nonce_bytes.len() as u8][..], nonce_bytes].concat(),
+                    };
+                    let payload = [&[0x94][..], deployer, &encoded_nonce].concat();
+                    [&[0xc0 + payload.len() as u8][..], &payload].concat()
+                }
+            };
+            let digest: [u8; DIGEST_WIDTH] = Keccak256Hash.hash_iter(preimage);
+            assert_eq!(*address, digest[DIGEST_WIDTH - ETH_ADDRESS_BYTES..]);
+        }
+
+        let output = contract_address_output(&deployments, &addresses);
+        Statement {
+            name: "contract address",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 2 * DIGEST_WIDTH,
+        }
+    }
+
+    #[test]
+    fn test_machine_contract_address_tampered_nonce() {
+        // The encoded nonce of a `CREATE` isn't the one read from memory.
+        let col_map = ContractAddressCols::<usize>::col_map();
+        assert_tampered_witness_rejected(
+            contract_address_statement(),
+            |chip| matches!(chip, KeccakMachineChip::ContractAddress(_)),
+            col_map.is_create,
+            col_map.nonce[NONCE_BYTES - 1],
+        );
+    }
+
+    /// The output of the proof of the `addresses` of `deployments`, the regions of the
+    /// deployments: the deployer, the salt and the init code hash or the nonce, the
+    /// address and the init code, each padded to a word.
+    fn contract_address_output(
+        deployments: &[Deployment],
+        addresses: &[[u8; ETH_ADDRESS_BYTES]],
+    ) -> Vec<u8> {
+        let mut output = vec![];
+        for (deployment, address) in deployments.iter().zip(addresses) {
+            match deployment {
+                Deployment::Create2 {
+                    deployer,
+                    salt,
+                    init_code,
+                } => {
+                    let init_code_hash: [u8; DIGEST_WIDTH] =
+                        Keccak256Hash.hash_iter(init_code.iter().copied());
+                    output.extend(address_word(deployer));
+                    output.extend(salt);
+                    output.extend(init_code_hash);
+                    output.extend(address_word(address));
+                    output.extend(init_code);
+                    output.resize(output.len().next_multiple_of(MEMORY_WORD_BYTES), 0);
+                }
+                Deployment::Create { deployer, nonce } => {
+                    output.extend(address_word(deployer));
+                    output.extend(nonce.to_be_bytes());
+                    output.extend(address_word(address));
+                }
+            }
+        }
+        output
+    }
+
+    /// The digests of typed data.
+    fn eip712_statement() -> Statement {
+        // An order with an array of structs, empty bytes and a negative integer.
+        let order = r#"{
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Order": [
+                    { "name": "maker", "type": "address" },
+                    { "name": "items", "type": "Item[]" },
+                    { "name": "salt", "type": "uint256" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "offset", "type": "int64" }
+                ],
+                "Item": [
+                    { "name": "token", "type": "address" },
+                    { "name": "amount", "type": "uint256" }
+                ]
+            },
+            "primaryType": "Order",
+            "domain": {
+                "name": "Exchange",
+                "chainId": "0x1",
+                "verifyingContract": "0x1111111111111111111111111111111111111111"
+            },
+            "message": {
+                "maker": "0x2222222222222222222222222222222222222222",
+                "items": [
+                    { "token": "0x3333333333333333333333333333333333333333", "amount": "1000000000000000000000" },
+                    { "token": "0x4444444444444444444444444444444444444444", "amount": 5 }
+                ],
+                "salt": "123456789012345678901234567890",
+                "data": "0x",
+                "offset": -42
+            }
+        }"#;
+        let mail = r#"{
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" }
+                ],
+                "Mail": [
+                    { "name": "to", "type": "address" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": { "name": "Ether Mail", "version": "1" },
+            "message": {
+                "to": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB",
+                "contents": "Hello, Bob!"
+            }
+        }"#;
+        let typed_data = [order, mail]
+            .map(|json| TypedData::from_json(json).unwrap())
+            .to_vec();
+
+        let (digests, machine, traces) = generate_eip712_trace::<MyConfig>(&typed_data).unwrap();
+        for (typed_data, (domain_separator, digest)) in typed_data.iter().zip(digests) {
+            assert_eq!(domain_separator, typed_data.domain_separator().unwrap());
+            assert_eq!(digest, typed_data.digest().unwrap());
+        }
+
+        let output = eip712_output(&typed_data);
+        Statement {
+            name: "eip712",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 2 * DIGEST_WIDTH,
+        }
+    }
+
+    #[test]
+    fn test_machine_eip712_tampered_domain_separator() {
+        // The domain separator of the digest isn't the one read from memory.
+        let col_map = Eip712Cols::<usize>::col_map();
+        assert_tampered_witness_rejected(
+            eip712_statement(),
+            |chip| matches!(chip, KeccakMachineChip::Eip712(_)),
+            col_map.is_real,
+            col_map.domain_separator[0],
+        );
+    }
+
+    /// The output of the proof of the digests of `typed_data`: the domain separator,
+    /// the message hash and the digest of each typed data.
+    fn eip712_output(typed_data: &[TypedData]) -> Vec<u8> {
+        typed_data
+            .iter()
+            .flat_map(|typed_data| {
+                [
+                    typed_data.domain_separator().unwrap(),
+                    typed_data.message_hash().unwrap(),
+                    typed_data.digest().unwrap(),
+                ]
+                .concat()
+            })
+            .collect()
+    }
+
+    /// The storage slots of paths through mappings and arrays.
+    fn storage_slot_statement() -> Statement {
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+
+        // A mapping of mappings, and an array in a mapping whose index wraps around.
+        let paths = [
+            StoragePath {
+                base_slot: seeded_rng.gen(),
+                steps: vec![
+                    StorageStep::Mapping {
+                        key: seeded_rng.gen(),
+                    },
+                    StorageStep::Mapping {
+                        key: seeded_rng.gen(),
+                    },
+                ],
+            },
+            StoragePath {
+                base_slot: seeded_rng.gen(),
+                steps: vec![
+                    StorageStep::Mapping {
+                        key: seeded_rng.gen(),
+                    },
+                    StorageStep::Array {
+                        index: [0xff; DIGEST_WIDTH],
+                    },
+                ],
+            },
+        ];
+        let (slots, machine, traces) = generate_storage_slot_trace::<MyConfig>(&paths);
+        assert_eq!(slots, paths.iter().map(StoragePath::slot).collect_vec());
+
+        let output = storage_slot_output(&paths, &slots);
+        Statement {
+            name: "storage slot",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: output.len() - 1,
+        }
+    }
+
+    #[test]
+    fn test_machine_storage_slot_tampered_key() {
+        // The hashed mapping key isn't the one read from memory.
+        let col_map = StorageSlotCols::<usize>::col_map();
+        assert_tampered_witness_rejected(
+            storage_slot_statement(),
+            |chip| matches!(chip, KeccakMachineChip::StorageSlot(_)),
+            col_map.is_real,
+            col_map.key[0],
+        );
+    }
+
+    /// The output of the proof of the `slots` of `paths`, the regions of the paths:
+    /// the image of the path followed by its slot.
+    fn storage_slot_output(paths: &[StoragePath], slots: &[[u8; DIGEST_WIDTH]]) -> Vec<u8> {
+        paths
+            .iter()
+            .zip(slots)
+            .flat_map(|(path, slot)| [path.image(), slot.to_vec()].concat())
+            .collect()
+    }
+
+    /// The inclusion of the leaf at `leaf_index` in a Merkle Mountain Range of 11
+    /// leaves.
+    fn mmr_inclusion_statement(leaf_index: usize) -> Statement {
+        const NUM_LEAVES: usize = 11;
+
+        let mut seeded_rng = StdRng::seed_from_u64(0);
+        let leaves: Vec<[u8; DIGEST_WIDTH]> =
+            (0..NUM_LEAVES).map(|_| seeded_rng.gen()).collect_vec();
+        let (root, machine, traces) = generate_mmr_inclusion_trace::<MyConfig>(&leaves, leaf_index);
+        let hasher = CompressionFunctionFromHasher::new(Keccak256Hash);
+        assert_eq!(
+            root,
+            MachineMmrPeaks::from_leaves(&leaves, &hasher).root(&hasher)
+        );
+
+        let output = [leaves[leaf_index], root].concat();
+        Statement {
+            name: "mmr inclusion",
+            machine,
+            traces,
+            output,
+            wrong_output_byte: 0,
+        }
+    }
+
+    #[test]
+    fn test_machine_out_of_range_sponge_byte() {
+        const RANDOM_SEED: u64 = 0;
+        const SPONGE_TRACE_INDEX: usize = 1;
+
+        let (machine, output, mut traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        let sponge_trace = traces[SPONGE_TRACE_INDEX].as_mut().unwrap();
+        let (_, rows, _) = unsafe { sponge_trace.values.align_to_mut::<KeccakSpongeCols<Val>>() };
+
+        // Move 256 from the high byte of a limb into its low byte, which keeps the
+        // 16-bit limb sent to the xor bus unchanged.
+        let row = &mut rows[0];
+        let i = (0..KECCAK_RATE_BYTES)
+            .step_by(2)
+            .find(|&i| !row.block_bytes[i + 1].is_zero())
+            .unwrap();
+        row.block_bytes[i] += Val::from_canonical_u32(1 << 8);
+        row.block_bytes[i + 1] -= Val::one();
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            prove_and_verify(&machine, traces, &KeccakMachine::public_values(&output))
+        }));
+        assert!(!matches!(result, Ok(Ok(()))));
+    }
+
+    #[test]
+    fn test_machine_bus_balance() {
+        const RANDOM_SEED: u64 = 0;
+        const SPONGE_TRACE_INDEX: usize = 1;
+
+        let (machine, _, mut traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        assert!(machine.debug_bus_balance(&traces).is_balanced());
+
+        // Change a byte that is both range-checked and sent to the xor bus.
+        let sponge_trace = traces[SPONGE_TRACE_INDEX].as_mut().unwrap();
+        let (_, rows, _) = unsafe { sponge_trace.values.align_to_mut::<KeccakSpongeCols<Val>>() };
+        rows[0].block_bytes[0] += Val::one();
+
+        let report = machine.debug_bus_balance(&traces);
+        assert!(!report.is_balanced());
+        for bus in [KeccakMachineBus::Range8, KeccakMachineBus::XorInput] {
+            assert!(report
+                .unmatched
+                .iter()
+                .any(|message| message.bus == bus as usize
+                    && message.chip == "KeccakSponge"
+                    && message.row == 0
+                    && message.direction == Direction::Send));
+        }
+    }
+
+    #[test]
+    fn test_machine_report() {
+        const RANDOM_SEED: u64 = 0;
+
+        let (machine, _, traces) = generate_traces::<MyConfig>(RANDOM_SEED);
+        let report = machine.report(&traces);
+
+        assert_eq!(report.chips.len(), traces.len());
+        for (chip, trace) in report.chips.iter().zip(traces.iter()) {
+            assert_eq!(chip.height, trace.as_ref().map(|trace| trace.height()));
+            assert!(
+                chip.max_constraint_degree <= 3,
+                "{} has degree > 3",
+                chip.name
+            );
+        }
+
+        let range = report
+            .chips
+            .iter()
+            .find(|chip| chip.name == "Range8")
+            .unwrap();
+        assert_eq!(range.preprocessed_width, 1);
+        assert_eq!(range.num_constraints, 0);
+        assert_eq!(
+            range.interactions,
+            vec![InteractionReport {
+                direction: Direction::Receive,
+                bus: KeccakMachineBus::Range8 as usize,
+                num_fields: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_machine_serialized_proof() -> Result<(), ProofError> {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let (digest, machine, traces) = generate_hash_trace::<MyConfig>(input);
 
         let config = default_config();
+        let (pk, _) = machine.setup(&config);
+        let public_values = KeccakMachine::public_values(&digest);
         let mut challenger = default_challenger();
-        let traces = generate_machine_trace::<MyConfig, _>(leaf_index, digests, &hasher);
-        let proof = machine.prove(&config, &mut challenger, &pk, traces, &[]);
+        let proof = machine.prove(&config, &mut challenger, &pk, traces, &public_values);
+
+        let mut wrong_digest = digest;
+        wrong_digest[DIGEST_WIDTH - 1] ^= 1;
+        let wrong_public_values = KeccakMachine::public_values(&wrong_digest);
+
+        let statement = KeccakMachine::hash(input);
+        for format in [ProofFormat::Bincode, ProofFormat::Json] {
+            let proof_bytes = serialize(&proof, format)?;
+
+            verify_keccak_machine_proof(&proof_bytes, &statement, &public_values)?;
+            assert!(matches!(
+                verify_keccak_machine_proof(&proof_bytes, &statement, &wrong_public_values),
+                Err(ProofError::Verification(_))
+            ));
+            assert!(matches!(
+                verify_keccak_machine_proof(&proof_bytes, &statement, &public_values[1..]),
+                Err(ProofError::WrongNumberOfPublicValues { .. })
+            ));
+        }
 
+        Ok(())
+    }
+
+    #[test]
+    fn test_machine_forged_image_rejected() -> Result<(), ProofError> {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let statement = KeccakMachine::hash(input);
+
+        // A prover who sets up its own machine can place any output in the image and
+        // prove it without hashing anything.
+        let forged_digest = [0xab; DIGEST_WIDTH];
+        let mut image = statement.image.clone();
+        image[statement.output.start * MEMORY_WORD_BYTES..].copy_from_slice(&forged_digest);
+        let forged_machine = KeccakMachine {
+            image,
+            sponge_calls: vec![],
+            ..statement.clone()
+        };
+        let traces = generate_chip_traces::<MyConfig>(
+            &forged_machine,
+            vec![],
+            vec![],
+            BTreeMap::new(),
+            ChipTraces::default(),
+        );
+
+        let config = default_config();
+        let (pk, _) = forged_machine.setup(&config);
+        let public_values = KeccakMachine::public_values(&forged_digest);
         let mut challenger = default_challenger();
-        machine.verify(&config, &mut challenger, &vk, &proof, &[])
+        let proof = forged_machine.prove(&config, &mut challenger, &pk, traces, &public_values);
+        let proof_bytes = serialize(&proof, ProofFormat::Bincode)?;
+
+        verify_keccak_machine_proof(&proof_bytes, &forged_machine, &public_values)?;
+        assert!(matches!(
+            verify_keccak_machine_proof(&proof_bytes, &statement, &public_values),
+            Err(ProofError::Verification(_))
+        ));
+
+        Ok(())
     }
 }
@@ -0,0 +1,183 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use p3_machine::{
+    error::VerificationError,
+    machine::Machine,
+    proof::{MachineProof, VerifyingKey},
+};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize,
+};
+
+use crate::{
+    config::{default_challenger, default_config, MyConfig, Val},
+    machine::{KeccakMachine, NUM_PUBLIC_VALUES},
+};
+
+/// Version of the on-disk format. Bump it whenever the layout of the proof, the
+/// verifying key or the machine's chips changes.
+pub const PROOF_FORMAT_VERSION: u32 = 17;
+
+pub type KeccakMachineProof = MachineProof<MyConfig>;
+pub type KeccakMachineVerifyingKey = VerifyingKey<MyConfig>;
+pub type PublicValues = Vec<Val>;
+
+/// Encoding used to persist proofs, verifying keys and public values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+    Bincode,
+    Json,
+}
+
+impl ProofFormat {
+    /// Detects the format of `bytes` by parsing them as JSON. Looking at the first
+    /// byte alone is not enough: bincode starts with the little-endian format
+    /// version, which may well be the code of `{`.
+    pub fn detect(bytes: &[u8]) -> Self {
+        match serde_json::from_slice::<IgnoredAny>(bytes) {
+            Ok(_) => ProofFormat::Json,
+            Err(_) => ProofFormat::Bincode,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ProofError {
+    Bincode(bincode::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion { found: u32, expected: u32 },
+    WrongNumberOfPublicValues { found: usize, expected: usize },
+    Verification(VerificationError),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Bincode(err) => write!(f, "invalid bincode encoding: {err}"),
+            ProofError::Json(err) => write!(f, "invalid JSON encoding: {err}"),
+            ProofError::UnsupportedVersion { found, expected } => write!(
+                f,
+                "unsupported proof format version {found}, expected {expected}"
+            ),
+            ProofError::WrongNumberOfPublicValues { found, expected } => {
+                write!(f, "expected {expected} public values, found {found}")
+            }
+            ProofError::Verification(err) => write!(f, "verification failed: {err:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Wraps every persisted value so that readers can reject data written by an
+/// incompatible version.
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    value: T,
+}
+
+/// Serializes `value` with the current format version.
+pub fn serialize<T: Serialize>(value: &T, format: ProofFormat) -> Result<Vec<u8>, ProofError> {
+    let versioned = Versioned {
+        version: PROOF_FORMAT_VERSION,
+        value,
+    };
+    match format {
+        ProofFormat::Bincode => bincode::serialize(&versioned).map_err(ProofError::Bincode),
+        ProofFormat::Json => serde_json::to_vec(&versioned).map_err(ProofError::Json),
+    }
+}
+
+/// Deserializes a value written by [`serialize`], detecting its format.
+pub fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ProofError> {
+    let versioned: Versioned<T> = match ProofFormat::detect(bytes) {
+        ProofFormat::Bincode => bincode::deserialize(bytes).map_err(ProofError::Bincode)?,
+        ProofFormat::Json => serde_json::from_slice(bytes).map_err(ProofError::Json)?,
+    };
+    if versioned.version != PROOF_FORMAT_VERSION {
+        return Err(ProofError::UnsupportedVersion {
+            found: versioned.version,
+            expected: PROOF_FORMAT_VERSION,
+        });
+    }
+    Ok(versioned.value)
+}
+
+/// Verifies a serialized proof of `machine` against `public_values`, which are given
+/// by `KeccakMachine::public_values` for the claimed output. The verifying key is set
+/// up from `machine`, whose memory image, output words and calls are those of the
+/// claimed statement, rather than taken from the prover, who could otherwise place
+/// any output in the image. This only needs the machine's AIRs, not any trace
+/// generation code.
+pub fn verify_keccak_machine_proof(
+    bytes: &[u8],
+    machine: &KeccakMachine,
+    public_values: &[Val],
+) -> Result<(), ProofError> {
+    if public_values.len() != NUM_PUBLIC_VALUES {
+        return Err(ProofError::WrongNumberOfPublicValues {
+            found: public_values.len(),
+            expected: NUM_PUBLIC_VALUES,
+        });
+    }
+    let proof: KeccakMachineProof = deserialize(bytes)?;
+
+    let config = default_config();
+    let (_, vk) = machine.setup(&config);
+    let mut challenger = default_challenger();
+    machine
+        .verify(&config, &mut challenger, &vk, &proof, public_values)
+        .map_err(ProofError::Verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use p3_field::AbstractField;
+
+    #[test]
+    fn test_unsupported_version() {
+        let bytes = serde_json::to_vec(&Versioned {
+            version: PROOF_FORMAT_VERSION + 1,
+            value: 0u32,
+        })
+        .unwrap();
+
+        assert!(matches!(
+            deserialize::<u32>(&bytes),
+            Err(ProofError::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<(), ProofError> {
+        let public_values: PublicValues = vec![Val::from_canonical_u32(42)];
+        for format in [ProofFormat::Bincode, ProofFormat::Json] {
+            let bytes = serialize(&public_values, format)?;
+            assert_eq!(ProofFormat::detect(&bytes), format);
+            assert_eq!(deserialize::<PublicValues>(&bytes)?, public_values);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_bincode_starting_with_brace() {
+        // A bincode value whose version is the code of `{`.
+        let bytes = bincode::serialize(&Versioned {
+            version: u32::from(b'{'),
+            value: 0u32,
+        })
+        .unwrap();
+
+        assert_eq!(bytes[0], b'{');
+        assert_eq!(ProofFormat::detect(&bytes), ProofFormat::Bincode);
+        assert!(matches!(
+            deserialize::<u32>(&bytes),
+            Err(ProofError::UnsupportedVersion { found: 123, .. })
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use itertools::Itertools;
+use p3_air::BaseAir;
+use p3_field::PrimeField64;
+use p3_machine::machine::Machine;
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+use p3_uni_stark::get_symbolic_constraints;
+
+use crate::{
+    debug::{bus_name, chip_interactions, chip_name, Direction},
+    machine::{KeccakMachine, NUM_PUBLIC_VALUES},
+};
+
+/// One send or receive of a chip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InteractionReport {
+    pub direction: Direction,
+    pub bus: usize,
+    pub num_fields: usize,
+}
+
+/// The shape and cost of a single chip.
+#[derive(Clone, Debug)]
+pub struct ChipReport {
+    pub name: String,
+    pub main_width: usize,
+    pub preprocessed_width: usize,
+    pub max_constraint_degree: usize,
+    pub num_constraints: usize,
+    pub interactions: Vec<InteractionReport>,
+    /// The height of the chip's trace for the reported workload, if it has one.
+    pub height: Option<usize>,
+}
+
+impl ChipReport {
+    /// Number of main trace cells for the reported workload.
+    pub fn main_cells(&self) -> usize {
+        self.height.unwrap_or_default() * self.main_width
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MachineReport {
+    pub chips: Vec<ChipReport>,
+}
+
+impl fmt::Display for MachineReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>8} {:>6} {:>6} {:>6} {:>11} {:>6} {:>8} {:>12}",
+            "chip", "height", "main", "prep", "degree", "constraints", "sends", "receives", "cells"
+        )?;
+        for chip in self.chips.iter() {
+            let height = chip
+                .height
+                .map(|height| height.to_string())
+                .unwrap_or_else(|| "-".into());
+            let count = |direction| {
+                chip.interactions
+                    .iter()
+                    .filter(|interaction| interaction.direction == direction)
+                    .count()
+            };
+            writeln!(
+                f,
+                "{:<16} {:>8} {:>6} {:>6} {:>6} {:>11} {:>6} {:>8} {:>12}",
+                chip.name,
+                height,
+                chip.main_width,
+                chip.preprocessed_width,
+                chip.max_constraint_degree,
+                chip.num_constraints,
+                count(Direction::Send),
+                count(Direction::Receive),
+                chip.main_cells()
+            )?;
+        }
+        writeln!(
+            f,
+            "total main cells: {}",
+            self.chips.iter().map(ChipReport::main_cells).sum::<usize>()
+        )?;
+
+        // The interactions are grouped by direction, bus and number of fields, since
+        // chips like the sponge have dozens of identical ones.
+        writeln!(f)?;
+        writeln!(f, "interactions:")?;
+        for chip in self.chips.iter() {
+            let mut groups = BTreeMap::new();
+            for interaction in chip.interactions.iter() {
+                let direction = match interaction.direction {
+                    Direction::Send => "sends",
+                    Direction::Receive => "receives",
+                };
+                *groups
+                    .entry((direction, interaction.bus, interaction.num_fields))
+                    .or_insert(0) += 1;
+            }
+            let groups = groups
+                .into_iter()
+                .map(|((direction, bus, num_fields), count)| {
+                    format!(
+                        "{direction} {count} x {num_fields} fields on {}",
+                        bus_name(bus)
+                    )
+                })
+                .join(", ");
+            writeln!(f, "  {}: {groups}", chip.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl KeccakMachine {
+    /// Reports the widths, constraints and interactions of every chip, along with the
+    /// heights of `traces`, which can be left empty to only report the layout.
+    pub fn report<F: PrimeField64>(&self, traces: &[Option<RowMajorMatrix<F>>]) -> MachineReport {
+        let chips = self
+            .chips()
+            .iter()
+            .enumerate()
+            .map(|(i, chip)| {
+                let preprocessed_width = BaseAir::<F>::preprocessed_trace(chip)
+                    .map(|preprocessed| preprocessed.width())
+                    .unwrap_or_default();
+                let constraints =
+                    get_symbolic_constraints::<F, _>(chip, preprocessed_width, NUM_PUBLIC_VALUES);
+                let interactions = chip_interactions::<F, _>(chip)
+                    .into_iter()
+                    .map(|(direction, interaction)| InteractionReport {
+                        direction,
+                        bus: interaction.argument_index,
+                        num_fields: interaction.fields.len(),
+                    })
+                    .collect();
+                ChipReport {
+                    name: chip_name(chip),
+                    main_width: BaseAir::<F>::width(chip),
+                    preprocessed_width,
+                    max_constraint_degree: constraints
+                        .iter()
+                        .map(|constraint| constraint.degree_multiple())
+                        .max()
+                        .unwrap_or_default(),
+                    num_constraints: constraints.len(),
+                    interactions,
+                    height: traces
+                        .get(i)
+                        .and_then(|trace| trace.as_ref())
+                        .map(|trace| trace.height()),
+                }
+            })
+            .collect();
+        MachineReport { chips }
+    }
+}
@@ -1,14 +1,26 @@
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
+use core::ops::Range;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 
-use p3_air::Air;
-use p3_field::PrimeField32;
-use p3_matrix::dense::RowMajorMatrix;
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues};
+use p3_field::{AbstractField, Field, PrimeField64};
+use p3_interaction::{Interaction, InteractionAir};
+use p3_matrix::{
+    dense::{RowMajorMatrix, RowMajorMatrixView},
+    stack::VerticalPair,
+    Matrix,
+};
 #[cfg(debug_assertions)]
 use p3_uni_stark::DebugConstraintBuilder;
 use p3_uni_stark::{prove, verify, SymbolicAirBuilder, Val, VerificationError};
 use p3_uni_stark::{ProverConstraintFolder, VerifierConstraintFolder};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use crate::config::{default_challenger, default_config, MyConfig};
+use crate::{
+    config::{default_challenger, default_config, MyConfig},
+    debug::{chip_interactions, eval_interaction, Direction},
+};
 
 pub(crate) fn prove_and_verify<
     #[cfg(not(debug_assertions))] A: for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
@@ -24,7 +36,7 @@ pub(crate) fn prove_and_verify<
     public_values: Vec<Val<MyConfig>>,
 ) -> Result<(), VerificationError>
 where
-    Val<MyConfig>: PrimeField32,
+    Val<MyConfig>: PrimeField64,
 {
     let config = default_config();
 
@@ -34,3 +46,288 @@ where
     let mut challenger = default_challenger();
     verify(&config, air, &mut challenger, &proof, &public_values)
 }
+
+/// Asserts that `trace` is rejected, either by the constraint checks while proving or
+/// by the verifier.
+pub(crate) fn assert_prove_and_verify_fails<
+    #[cfg(not(debug_assertions))] A: for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<SymbolicAirBuilder<Val<MyConfig>>>,
+    #[cfg(debug_assertions)] A: for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<SymbolicAirBuilder<Val<MyConfig>>>
+        + for<'a> Air<DebugConstraintBuilder<'a, Val<MyConfig>>>,
+>(
+    air: &A,
+    trace: RowMajorMatrix<Val<MyConfig>>,
+    public_values: Vec<Val<MyConfig>>,
+) where
+    Val<MyConfig>: PrimeField64,
+{
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        prove_and_verify(air, trace, public_values)
+    }));
+    assert!(
+        !matches!(result, Ok(Ok(()))),
+        "Expected the trace to be rejected"
+    );
+}
+
+/// A change of a single trace cell.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Mutation {
+    pub row: usize,
+    pub col: usize,
+    pub delta: Val<MyConfig>,
+}
+
+/// `num_mutations` random non-zero changes to cells in `rows` and `cols`.
+pub(crate) fn random_mutations(
+    rows: Range<usize>,
+    cols: &[usize],
+    num_mutations: usize,
+    seed: u64,
+) -> Vec<Mutation> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..num_mutations)
+        .map(|_| {
+            let delta = loop {
+                let delta: Val<MyConfig> = rng.gen();
+                if !delta.is_zero() {
+                    break delta;
+                }
+            };
+            Mutation {
+                row: rng.gen_range(rows.clone()),
+                col: cols[rng.gen_range(0..cols.len())],
+                delta,
+            }
+        })
+        .collect()
+}
+
+/// Applies each mutation to `trace` on its own, and returns the ones that are rejected
+/// neither by the constraints nor by the buses. These point to under-constrained
+/// columns.
+///
+/// `trace` must be valid. A mutation is then rejected by the buses iff it changes the
+/// messages of its row, since the other chips' messages are unchanged. Likewise, only
+/// the constraints on the mutated row and its predecessor need to be checked.
+pub(crate) fn accepted_mutations<A>(
+    air: &A,
+    trace: &RowMajorMatrix<Val<MyConfig>>,
+    public_values: &[Val<MyConfig>],
+    mutations: &[Mutation],
+) -> Vec<Mutation>
+where
+    A: for<'a> Air<MutationCheckBuilder<'a>> + InteractionAir<Val<MyConfig>>,
+{
+    let height = trace.height();
+    let preprocessed = air.preprocessed_trace();
+    let preprocessed_row = |row: usize| {
+        preprocessed
+            .as_ref()
+            .map(|preprocessed| preprocessed.row_slice(row).to_vec())
+            .unwrap_or_default()
+    };
+    let interactions = chip_interactions(air);
+    let row_messages = |trace: &RowMajorMatrix<Val<MyConfig>>, row: usize| {
+        let mut messages = BTreeMap::new();
+        add_row_messages(
+            &mut messages,
+            &interactions,
+            &preprocessed_row(row),
+            &trace.row_slice(row),
+        );
+        messages
+    };
+
+    mutations
+        .iter()
+        .filter(|mutation| {
+            let mut mutated = trace.clone();
+            mutated.values[mutation.row * mutated.width() + mutation.col] += mutation.delta;
+
+            let constraints_hold = [(mutation.row + height - 1) % height, mutation.row]
+                .into_iter()
+                .all(|row| constraints_hold_on_row(air, &mutated, public_values, row));
+            constraints_hold
+                && row_messages(trace, mutation.row) == row_messages(&mutated, mutation.row)
+        })
+        .copied()
+        .collect()
+}
+
+/// Asserts that every mutation is rejected, listing the ones that aren't.
+pub(crate) fn assert_mutations_rejected<A>(
+    air: &A,
+    trace: &RowMajorMatrix<Val<MyConfig>>,
+    mutations: &[Mutation],
+) where
+    A: for<'a> Air<MutationCheckBuilder<'a>> + InteractionAir<Val<MyConfig>>,
+{
+    assert_mutations_rejected_with_public_values(air, trace, &[], mutations);
+}
+
+/// Like `assert_mutations_rejected`, for an AIR constraining its trace against
+/// `public_values`.
+pub(crate) fn assert_mutations_rejected_with_public_values<A>(
+    air: &A,
+    trace: &RowMajorMatrix<Val<MyConfig>>,
+    public_values: &[Val<MyConfig>],
+    mutations: &[Mutation],
+) where
+    A: for<'a> Air<MutationCheckBuilder<'a>> + InteractionAir<Val<MyConfig>>,
+{
+    let accepted = accepted_mutations(air, trace, public_values, mutations);
+    assert!(
+        accepted.is_empty(),
+        "{} of {} mutations were accepted: {accepted:?}",
+        accepted.len(),
+        mutations.len()
+    );
+}
+
+/// The net multiplicity (sends minus receives) of every `(bus, message)` pair in
+/// `trace`. Messages that cancel out within the trace are left out.
+pub(crate) fn bus_messages<A: InteractionAir<Val<MyConfig>>>(
+    air: &A,
+    trace: &RowMajorMatrix<Val<MyConfig>>,
+) -> BusMessages {
+    let preprocessed = air.preprocessed_trace();
+    let interactions = chip_interactions(air);
+    let mut messages = BTreeMap::new();
+    for row in 0..trace.height() {
+        let preprocessed_row = preprocessed
+            .as_ref()
+            .map(|preprocessed| preprocessed.row_slice(row).to_vec())
+            .unwrap_or_default();
+        add_row_messages(
+            &mut messages,
+            &interactions,
+            &preprocessed_row,
+            &trace.row_slice(row),
+        );
+    }
+    messages
+}
+
+/// Asserts that the constraints of `air` hold on every row of `trace`, without
+/// proving it.
+pub(crate) fn assert_constraints_hold<A>(air: &A, trace: &RowMajorMatrix<Val<MyConfig>>)
+where
+    A: for<'a> Air<MutationCheckBuilder<'a>>,
+{
+    for row in 0..trace.height() {
+        assert!(
+            constraints_hold_on_row(air, trace, &[], row),
+            "Constraints don't hold on row {row}"
+        );
+    }
+}
+
+pub(crate) type BusMessages = BTreeMap<(usize, Vec<u64>), Val<MyConfig>>;
+
+fn add_row_messages(
+    messages: &mut BusMessages,
+    interactions: &[(Direction, Interaction<Val<MyConfig>>)],
+    preprocessed: &[Val<MyConfig>],
+    main: &[Val<MyConfig>],
+) {
+    for (direction, interaction) in interactions.iter() {
+        let (fields, count) = eval_interaction(interaction, preprocessed, main);
+        if count.is_zero() {
+            continue;
+        }
+        let key = (interaction.argument_index, fields);
+        let net = messages
+            .entry(key.clone())
+            .or_insert(Val::<MyConfig>::zero());
+        match direction {
+            Direction::Send => *net += count,
+            Direction::Receive => *net -= count,
+        }
+        if net.is_zero() {
+            messages.remove(&key);
+        }
+    }
+}
+
+fn constraints_hold_on_row<A>(
+    air: &A,
+    trace: &RowMajorMatrix<Val<MyConfig>>,
+    public_values: &[Val<MyConfig>],
+    row: usize,
+) -> bool
+where
+    A: for<'a> Air<MutationCheckBuilder<'a>>,
+{
+    let height = trace.height();
+    let next_row = (row + 1) % height;
+    let local = trace.row_slice(row);
+    let next = trace.row_slice(next_row);
+    let mut builder = MutationCheckBuilder {
+        main: VerticalPair::new(
+            RowMajorMatrixView::new_row(&local),
+            RowMajorMatrixView::new_row(&next),
+        ),
+        is_first_row: Val::<MyConfig>::from_bool(row == 0),
+        is_last_row: Val::<MyConfig>::from_bool(row == height - 1),
+        is_transition: Val::<MyConfig>::from_bool(row != height - 1),
+        public_values,
+        holds: true,
+    };
+    air.eval(&mut builder);
+    builder.holds
+}
+
+/// Evaluates the constraints on a single row, recording whether they hold instead of
+/// panicking like `DebugConstraintBuilder`.
+pub(crate) struct MutationCheckBuilder<'a> {
+    main:
+        VerticalPair<RowMajorMatrixView<'a, Val<MyConfig>>, RowMajorMatrixView<'a, Val<MyConfig>>>,
+    is_first_row: Val<MyConfig>,
+    is_last_row: Val<MyConfig>,
+    is_transition: Val<MyConfig>,
+    public_values: &'a [Val<MyConfig>],
+    holds: bool,
+}
+
+impl<'a> AirBuilder for MutationCheckBuilder<'a> {
+    type F = Val<MyConfig>;
+    type Expr = Val<MyConfig>;
+    type Var = Val<MyConfig>;
+    type M =
+        VerticalPair<RowMajorMatrixView<'a, Val<MyConfig>>, RowMajorMatrixView<'a, Val<MyConfig>>>;
+
+    fn main(&self) -> Self::M {
+        self.main
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.is_first_row
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.is_last_row
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        assert_eq!(size, 2, "only supports a window size of 2");
+        self.is_transition
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        if !x.into().is_zero() {
+            self.holds = false;
+        }
+    }
+}
+
+impl<'a> AirBuilderWithPublicValues for MutationCheckBuilder<'a> {
+    type PublicVar = Val<MyConfig>;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
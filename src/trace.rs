@@ -1,33 +1,214 @@
+use alloc::collections::BTreeMap;
+
 use itertools::Itertools;
-use p3_field::PrimeField32;
+use p3_field::PrimeField64;
+use p3_keccak::Keccak256Hash;
+use p3_machine::machine::Machine;
 use p3_matrix::dense::RowMajorMatrix;
-use p3_symmetric::CompressionFunction;
+use p3_symmetric::{CompressionFunction, CompressionFunctionFromHasher, CryptographicHasher};
 use p3_uni_stark::{StarkGenericConfig, Val};
 
-use crate::chips::{
-    keccak_permute::{trace::KeccakPermuteOp, KeccakPermuteChip},
-    keccak_sponge::{
-        columns::{KECCAK_RATE_BYTES, KECCAK_WIDTH_BYTES},
-        trace::KeccakSpongeOp,
-        KeccakSpongeChip,
+use crate::{
+    chips::{
+        contract_address::{
+            address_word, ContractAddressChip, ContractAddressOp, ContractAddressOpKind,
+        },
+        eip712::{Eip712Chip, Eip712Op, HashPreimage, TypedData, TypedDataError},
+        eth_address::{EthAddressChip, EthAddressOp},
+        incremental_merkle_tree::{
+            IncrementalMerkleTreeChip, IncrementalTreeOp, IncrementalTreeOpKind,
+            IncrementalTreeState,
+        },
+        keccak_permute::KeccakPermuteChip,
+        keccak_sponge::{
+            trace::{KeccakSpongeOp, SpongeCall},
+            KeccakSpongeChip,
+        },
+        memory::{
+            trace::{image_num_words, MemoryOp},
+            MemoryChip,
+        },
+        memory_image::MemoryImageChip,
+        merkle_root::{LeafPreimage, MerkleRootChip, MerkleRootOp, RootWrite},
+        merkle_sum_root::{MerkleSumRootChip, MerkleSumRootOp, SumNode},
+        merkle_tree::{MerkleTreeChip, MerkleTreeOp},
+        mmr::{MmrChip, MmrOp, MmrOpKind, MmrPeaks},
+        range_checker::RangeCheckerChip,
+        storage_slot::{StoragePath, StorageSlotChip, StorageSlotOp},
+        xor::XorChip,
+        KeccakMachineChip, DIGEST_WIDTH, ETH_ADDRESS_BYTES, INCREMENTAL_TREE_DEPTH,
+        KECCAK_U64_LIMBS, MAX_U8, MEMORY_WORD_BYTES, MERKLE_SUM_BYTES, MERKLE_TREE_DEPTH,
+        MMR_HEIGHT, NUM_BYTES, PUBLIC_KEY_BYTES,
     },
-    merkle_root::{MerkleRootChip, MerkleRootOp},
-    xor::{trace::XorOp, XorChip},
-    DIGEST_WIDTH, MERKLE_TREE_DEPTH, NUM_BYTES,
+    machine::{KeccakMachine, MerkleDepth},
 };
 
-// TODO: Proper execution function for the machine that minimizes redundant computation
-// Store logs/events during execution first and then generate the traces
+type MachineSumRootChip = MerkleSumRootChip<MERKLE_TREE_DEPTH, DIGEST_WIDTH, MERKLE_SUM_BYTES>;
+
+/// A node of the Merkle sum trees of the machine.
+pub type MachineSumNode = SumNode<DIGEST_WIDTH, MERKLE_SUM_BYTES>;
+
+type MachineMmrChip = MmrChip<MMR_HEIGHT, DIGEST_WIDTH>;
+
+/// The peaks of the Merkle Mountain Ranges of the machine.
+pub type MachineMmrPeaks = MmrPeaks<MMR_HEIGHT, DIGEST_WIDTH>;
+
+type MachineIncrementalTreeChip = IncrementalMerkleTreeChip<INCREMENTAL_TREE_DEPTH, DIGEST_WIDTH>;
+
+/// The state of the incremental Merkle trees of the machine.
+pub type MachineIncrementalTreeState = IncrementalTreeState<INCREMENTAL_TREE_DEPTH, DIGEST_WIDTH>;
+
+/// The `(domain separator, digest)` pair of each typed data proven by
+/// `generate_eip712_trace`, along with the machine and its traces.
+pub type Eip712Traces<F> = (
+    Vec<([u8; DIGEST_WIDTH], [u8; DIGEST_WIDTH])>,
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<F>>>,
+);
+
+/// Generates the traces proving the path of the leaf at `leaf_index` in the tree whose
+/// levels are `digests`. The root, the leaf hash and the leaf index are written to the
+/// start of the memory image, which is the output. Returns the output, the machine,
+/// set up with the memory image, and the traces.
 pub fn generate_machine_trace<SC, Compress>(
     leaf_index: usize,
     digests: Vec<Vec<[u8; DIGEST_WIDTH]>>,
     hasher: &Compress,
-) -> Vec<Option<RowMajorMatrix<Val<SC>>>>
+) -> (Vec<u8>, KeccakMachine, Vec<Option<RowMajorMatrix<Val<SC>>>>)
+where
+    SC: StarkGenericConfig,
+    Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    Val<SC>: PrimeField64,
+{
+    let root_write = RootWrite {
+        timestamp: 1,
+        addr: 0,
+    };
+    let op = merkle_path(leaf_index, &digests, None, root_write, 0);
+    let output = op.output(hasher);
+    let machine = KeccakMachine::merkle_path(MerkleDepth::default());
+    let traces =
+        generate_merkle_root_traces::<SC, _, MERKLE_TREE_DEPTH>(&machine, vec![op], vec![], hasher);
+    (output, machine, traces)
+}
+
+/// Generates the traces proving the path of `leaf_hash`, the leaf at `leaf_index`,
+/// through its `siblings` on a machine proving paths of depth `DEPTH`, which may be
+/// deeper than `MERKLE_TREE_DEPTH`. The root, the leaf hash and the leaf index are
+/// written to the start of the memory image, which is the output. Returns the output,
+/// the machine, set up with the memory image and the depth, and the traces.
+///
+/// Panics if the machine can't prove paths of depth `DEPTH`.
+pub fn generate_merkle_path_trace<SC, Compress, const DEPTH: usize>(
+    leaf_index: usize,
+    leaf_hash: [u8; DIGEST_WIDTH],
+    siblings: [[u8; DIGEST_WIDTH]; DEPTH],
+    hasher: &Compress,
+) -> (Vec<u8>, KeccakMachine, Vec<Option<RowMajorMatrix<Val<SC>>>>)
 where
     SC: StarkGenericConfig,
     Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
-    Val<SC>: PrimeField32,
+    Val<SC>: PrimeField64,
 {
+    let merkle_depth = MerkleDepth::new(DEPTH)
+        .unwrap_or_else(|| panic!("paths of depth {DEPTH} aren't supported"));
+    let root_write = RootWrite {
+        timestamp: 1,
+        addr: 0,
+    };
+    let op = MerkleRootOp {
+        leaf_index,
+        leaf_hash,
+        siblings,
+        root_write: Some(root_write),
+        ..Default::default()
+    };
+    let output = op.output(hasher);
+    let machine = KeccakMachine::merkle_path(merkle_depth);
+    let traces = generate_merkle_root_traces::<SC, _, DEPTH>(&machine, vec![op], vec![], hasher);
+    (output, machine, traces)
+}
+
+/// Generates the traces proving the path of the leaf at `leaf_index`, like
+/// `generate_machine_trace`, where the leaf is the hash of `data`, or the hash of its
+/// hash with `double_hash`. `data` is placed at the start of the memory image, the
+/// leaf is written to the words following it, and the root, the leaf and the leaf
+/// index to the words following the leaf. The output is the whole image: the data,
+/// padded to a word boundary, the leaf, the root, the leaf again and the leaf index.
+/// Returns the machine, set up with the memory image, and the
+/// traces.
+pub fn generate_leaf_preimage_trace<SC, Compress>(
+    leaf_index: usize,
+    data: &[u8],
+    double_hash: bool,
+    digests: Vec<Vec<[u8; DIGEST_WIDTH]>>,
+    hasher: &Compress,
+) -> (KeccakMachine, Vec<Option<RowMajorMatrix<Val<SC>>>>)
+where
+    SC: StarkGenericConfig,
+    Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    Val<SC>: PrimeField64,
+{
+    let dst_addr = image_num_words::<MEMORY_WORD_BYTES>(data);
+    let mut image = data.to_vec();
+    image.resize(
+        dst_addr * MEMORY_WORD_BYTES
+            + DIGEST_WIDTH
+            + RootWrite::num_words(DIGEST_WIDTH) * MEMORY_WORD_BYTES,
+        0,
+    );
+
+    let preimage = LeafPreimage {
+        timestamp: 0,
+        addr: 0,
+        dst_addr: dst_addr as u32,
+        data: data.to_vec(),
+        double_hash,
+    };
+    assert_eq!(
+        preimage.leaf_hash(),
+        digests[0][leaf_index],
+        "the leaf isn't the hash of its preimage"
+    );
+
+    // The leaf is hashed first, so the path hashes come after it.
+    let keccak_inputs = preimage.sponge_ops();
+    let root_write = RootWrite {
+        timestamp: preimage.leaf_timestamp() + 1,
+        addr: (dst_addr + DIGEST_WIDTH / MEMORY_WORD_BYTES) as u32,
+    };
+    let first_hash_id = keccak_inputs.len();
+    let op = merkle_path(
+        leaf_index,
+        &digests,
+        Some(preimage),
+        root_write,
+        first_hash_id,
+    );
+    let machine = KeccakMachine {
+        output: 0..image_num_words::<MEMORY_WORD_BYTES>(&image),
+        image,
+        sponge_calls: sponge_calls(&keccak_inputs),
+        ..Default::default()
+    };
+    let traces = generate_merkle_root_traces::<SC, _, MERKLE_TREE_DEPTH>(
+        &machine,
+        vec![op],
+        keccak_inputs,
+        hasher,
+    );
+    (machine, traces)
+}
+
+/// Returns the operation proving the path of the leaf at `leaf_index` in the tree
+/// whose levels are `digests`, and writing its root with `root_write`.
+fn merkle_path(
+    leaf_index: usize,
+    digests: &[Vec<[u8; DIGEST_WIDTH]>],
+    leaf_preimage: Option<LeafPreimage>,
+    root_write: RootWrite,
+    first_hash_id: usize,
+) -> MerkleRootOp<u8, MERKLE_TREE_DEPTH, DIGEST_WIDTH> {
     let leaf_hash = digests[0][leaf_index];
     let siblings: [[u8; DIGEST_WIDTH]; MERKLE_TREE_DEPTH] = (0..MERKLE_TREE_DEPTH)
         .map(|i| {
@@ -37,68 +218,1038 @@ where
         .collect::<Vec<[u8; DIGEST_WIDTH]>>()
         .try_into()
         .unwrap();
-    let op = MerkleRootOp {
+    MerkleRootOp {
         leaf_index,
         leaf_hash,
         siblings,
+        first_hash_id,
+        leaf_preimage,
+        root_write: Some(root_write),
+        sorted_pair: false,
+    }
+}
+
+/// Generates the traces proving the root reached from `leaf_hash` through `proof`,
+/// hashing each pair in sorted order as OpenZeppelin's `MerkleProof` does, which
+/// needs no leaf index. The root, the leaf hash and the index of the leaf given by
+/// the order of the pairs are written to the start of the memory image, which is the
+/// output. Returns the output, the machine, set up with the memory image, and the
+/// traces.
+pub fn generate_sorted_pair_trace<SC, Compress>(
+    leaf_hash: [u8; DIGEST_WIDTH],
+    proof: [[u8; DIGEST_WIDTH]; MERKLE_TREE_DEPTH],
+    hasher: &Compress,
+) -> (Vec<u8>, KeccakMachine, Vec<Option<RowMajorMatrix<Val<SC>>>>)
+where
+    SC: StarkGenericConfig,
+    Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    Val<SC>: PrimeField64,
+{
+    let root_write = RootWrite {
+        timestamp: 1,
+        addr: 0,
+    };
+    let op = MerkleRootOp {
+        leaf_hash,
+        siblings: proof,
+        root_write: Some(root_write),
+        sorted_pair: true,
+        ..Default::default()
     };
+    let output = op.output(hasher);
+    let machine = KeccakMachine::merkle_path(MerkleDepth::default());
+    let traces =
+        generate_merkle_root_traces::<SC, _, MERKLE_TREE_DEPTH>(&machine, vec![op], vec![], hasher);
+    (output, machine, traces)
+}
 
-    let keccak_inputs = (0..MERKLE_TREE_DEPTH)
-        .map(|i| {
-            let index = leaf_index >> i;
-            let parity = index & 1;
-            let (left, right) = if parity == 0 {
-                (digests[i][index], digests[i][index ^ 1])
-            } else {
-                (digests[i][index ^ 1], digests[i][index])
-            };
-            let input = left.into_iter().chain(right).collect_vec();
-            KeccakSpongeOp {
-                timestamp: 0,
-                addr: 0,
-                input,
+/// Generates the traces of `machine`, which proves paths of depth `DEPTH`, for the
+/// paths in `ops`. Their levels are hashed after the sponge operations in
+/// `keccak_inputs`.
+fn generate_merkle_root_traces<SC, Compress, const DEPTH: usize>(
+    machine: &KeccakMachine,
+    ops: Vec<MerkleRootOp<u8, DEPTH, DIGEST_WIDTH>>,
+    mut keccak_inputs: Vec<KeccakSpongeOp>,
+    hasher: &Compress,
+) -> Vec<Option<RowMajorMatrix<Val<SC>>>>
+where
+    SC: StarkGenericConfig,
+    Compress: CompressionFunction<[u8; DIGEST_WIDTH], 2>,
+    Val<SC>: PrimeField64,
+{
+    let memory_ops = ops
+        .iter()
+        .flat_map(|op| op.memory_ops(hasher))
+        .collect_vec();
+    keccak_inputs.extend(ops.iter().flat_map(|op| op.sponge_ops(hasher)));
+
+    assert_eq!(machine.merkle_depth.depth(), DEPTH);
+    let merkle_root_trace = MerkleRootChip::<DEPTH, DIGEST_WIDTH>::generate_trace(ops, hasher);
+    let range_counts =
+        MerkleRootChip::<DEPTH, DIGEST_WIDTH>::generate_range_counts(&merkle_root_trace);
+
+    generate_traces::<SC>(
+        machine,
+        keccak_inputs,
+        memory_ops,
+        range_counts,
+        ChipTraces {
+            merkle_root: Some(merkle_root_trace),
+            ..Default::default()
+        },
+    )
+}
+
+/// Generates the traces proving the Keccak256 hash of `input`, which is placed at the
+/// start of the memory image. The digest is written to the words following it, which
+/// are the output. Returns the digest, the machine, set up with the memory image, and
+/// the traces.
+pub fn generate_hash_trace<SC>(
+    input: &[u8],
+) -> (
+    [u8; DIGEST_WIDTH],
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let machine = KeccakMachine::hash(input);
+    let op = KeccakSpongeOp {
+        timestamp: 0,
+        addr: 0,
+        dst_addr: machine.output.start as u32,
+        is_memory_op: true,
+        input: input.to_vec(),
+    };
+    let traces = generate_traces::<SC>(
+        &machine,
+        vec![op],
+        vec![],
+        BTreeMap::new(),
+        ChipTraces::default(),
+    );
+    (
+        Keccak256Hash.hash_iter(input.iter().copied()),
+        machine,
+        traces,
+    )
+}
+
+/// Generates the traces proving the root of the tree with the given `leaves`, whose
+/// number must be a power of two, along with a Keccak256 commitment to the leaves,
+/// the hash of their concatenation. The leaves are placed at the start of the memory
+/// image, and the root and the commitment are written to the words following them,
+/// which are the output. Returns the root, the commitment, the machine, set up with
+/// the memory image, and the traces.
+pub fn generate_tree_trace<SC>(
+    leaves: &[[u8; DIGEST_WIDTH]],
+) -> (
+    [u8; DIGEST_WIDTH],
+    [u8; DIGEST_WIDTH],
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let machine = KeccakMachine::tree(leaves);
+    let root_addr = machine.output.start;
+    let commitment_addr = root_addr + DIGEST_WIDTH / MEMORY_WORD_BYTES;
+
+    let op = MerkleTreeOp {
+        timestamp: 0,
+        leaves_addr: 0,
+        root_addr: root_addr as u32,
+        first_hash_id: 0,
+        leaves: leaves.to_vec(),
+    };
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let root = op.root(&hasher);
+
+    // The commitment hashes the leaves straight from memory, after the node hashes.
+    let commitment_op = KeccakSpongeOp {
+        timestamp: 0,
+        addr: 0,
+        dst_addr: commitment_addr as u32,
+        is_memory_op: true,
+        input: leaves.concat(),
+    };
+    let commitment = Keccak256Hash.hash_iter(commitment_op.input.iter().copied());
+
+    let mut keccak_inputs = op.sponge_ops(&hasher);
+    keccak_inputs.push(commitment_op);
+    let traces = generate_traces::<SC>(
+        &machine,
+        keccak_inputs,
+        op.memory_ops(&hasher),
+        BTreeMap::new(),
+        ChipTraces {
+            merkle_tree: Some(MerkleTreeChip::<DIGEST_WIDTH>::generate_trace(
+                &[op],
+                &hasher,
+            )),
+            ..Default::default()
+        },
+    );
+    (root, commitment, machine, traces)
+}
+
+/// Generates the traces proving the path of the leaf at `leaf_index` in the Merkle
+/// sum tree with the given `leaves`, of which there must be `2^MERKLE_TREE_DEPTH`.
+/// The root and its sum, the total of the leaves, are written to the start of the
+/// memory, which is the output. Returns the root, the machine, set up with the
+/// memory image, and the traces.
+///
+/// Panics if the sum of the leaves overflows.
+pub fn generate_sum_tree_trace<SC>(
+    leaf_index: usize,
+    leaves: &[MachineSumNode],
+) -> (
+    MachineSumNode,
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    assert_eq!(leaves.len(), 1 << MERKLE_TREE_DEPTH);
+
+    let mut level = leaves.to_vec();
+    let mut siblings = vec![];
+    for i in 0..MERKLE_TREE_DEPTH {
+        siblings.push(level[(leaf_index >> i) ^ 1]);
+        level = level
+            .chunks_exact(2)
+            .map(|pair| SumNode::parent(&pair[0], &pair[1], &Keccak256Hash).expect("sum overflows"))
+            .collect();
+    }
+
+    let op = MerkleSumRootOp {
+        leaf_index,
+        leaf: leaves[leaf_index],
+        siblings: siblings.try_into().unwrap(),
+        first_hash_id: 0,
+        timestamp: 0,
+        root_addr: 0,
+    };
+    let root = op.root(&Keccak256Hash);
+    let image = vec![0; DIGEST_WIDTH + MERKLE_SUM_BYTES];
+
+    let sum_trace = MachineSumRootChip::generate_trace(&[op.clone()], &Keccak256Hash);
+    let machine = KeccakMachine {
+        output: 0..image_num_words::<MEMORY_WORD_BYTES>(&image),
+        image,
+        ..Default::default()
+    };
+    let traces = generate_traces::<SC>(
+        &machine,
+        op.sponge_ops(&Keccak256Hash),
+        op.memory_ops(&Keccak256Hash),
+        MachineSumRootChip::generate_range_counts(&sum_trace),
+        ChipTraces {
+            merkle_sum_root: Some(sum_trace),
+            ..Default::default()
+        },
+    );
+    (root, machine, traces)
+}
+
+/// Generates the traces proving that appending `leaves` to the Merkle Mountain Range
+/// with the given `peaks` moves its root from the first returned root to the second
+/// one. The slots of the peaks are placed at the start of the memory image, followed
+/// by the leaves and by the two roots, which are the output along with the leaves.
+/// Returns the roots, the machine, set up with the memory image, and the traces.
+pub fn generate_mmr_append_trace<SC>(
+    peaks: &MachineMmrPeaks,
+    leaves: &[[u8; DIGEST_WIDTH]],
+) -> (
+    [u8; DIGEST_WIDTH],
+    [u8; DIGEST_WIDTH],
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+    let leaves_addr = MMR_HEIGHT * MachineMmrChip::SLOT_WORDS;
+    let old_root_addr = leaves_addr + leaves.len() * words_per_digest;
+    let new_root_addr = old_root_addr + words_per_digest;
+    let mut image = [peaks.image(), leaves.concat()].concat();
+    image.resize((new_root_addr + words_per_digest) * MEMORY_WORD_BYTES, 0);
+
+    // The old root is bagged first, and every append then reads the slots written by
+    // the previous one.
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let mut ops = vec![MmrOp {
+        kind: MmrOpKind::Bag,
+        peaks: *peaks,
+        addr: old_root_addr as u32,
+        ..Default::default()
+    }];
+    let mut peaks = *peaks;
+    for (i, &leaf) in leaves.iter().enumerate() {
+        let op = MmrOp {
+            kind: MmrOpKind::Append { leaf },
+            peaks,
+            timestamp: 2 * (i as u32 + 1),
+            addr: (leaves_addr + i * words_per_digest) as u32,
+            ..Default::default()
+        };
+        peaks = op.next_peaks(&hasher);
+        ops.push(op);
+    }
+    ops.push(MmrOp {
+        kind: MmrOpKind::Bag,
+        peaks,
+        timestamp: 2 * (leaves.len() as u32 + 1),
+        addr: new_root_addr as u32,
+        ..Default::default()
+    });
+
+    let old_root = ops[0].peaks.root(&hasher);
+    let new_root = peaks.root(&hasher);
+    let machine = KeccakMachine {
+        image,
+        output: leaves_addr..new_root_addr + words_per_digest,
+        ..Default::default()
+    };
+    let traces = generate_mmr_traces::<SC>(&machine, ops);
+    (old_root, new_root, machine, traces)
+}
+
+/// Generates the traces proving that the leaf at `leaf_index` is included in the
+/// Merkle Mountain Range of `leaves`, whose root is written to memory. The slots of
+/// the peaks are placed at the start of the memory image, followed by the leaf and by
+/// the root, which are the output. Returns the root, the machine, set up with the
+/// memory image, and the traces.
+pub fn generate_mmr_inclusion_trace<SC>(
+    leaves: &[[u8; DIGEST_WIDTH]],
+    leaf_index: usize,
+) -> (
+    [u8; DIGEST_WIDTH],
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let peaks = MachineMmrPeaks::from_leaves(leaves, &hasher);
+
+    // The peaks cover the leaves from the highest one down.
+    let mut start = 0;
+    let height = (0..MMR_HEIGHT)
+        .rev()
+        .filter(|&h| peaks.peaks[h].is_some())
+        .find(|&h| {
+            start += 1 << h;
+            leaf_index < start
+        })
+        .expect("the leaf index is out of range");
+    start -= 1 << height;
+
+    let index = leaf_index - start;
+    let mut level = leaves[start..start + (1 << height)].to_vec();
+    let mut siblings = vec![];
+    for h in 0..height {
+        siblings.push(level[(index >> h) ^ 1]);
+        level = level
+            .chunks_exact(2)
+            .map(|pair| hasher.compress([pair[0], pair[1]]))
+            .collect();
+    }
+
+    let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+    let leaf_addr = MMR_HEIGHT * MachineMmrChip::SLOT_WORDS;
+    let root_addr = leaf_addr + words_per_digest;
+    let mut image = [peaks.image(), leaves[leaf_index].to_vec()].concat();
+    image.resize((root_addr + words_per_digest) * MEMORY_WORD_BYTES, 0);
+
+    let inclusion = MmrOp {
+        kind: MmrOpKind::Inclusion {
+            element: leaves[leaf_index],
+            index,
+            siblings,
+        },
+        peaks,
+        addr: leaf_addr as u32,
+        ..Default::default()
+    };
+    let bag = MmrOp {
+        kind: MmrOpKind::Bag,
+        peaks,
+        addr: root_addr as u32,
+        ..Default::default()
+    };
+
+    let machine = KeccakMachine {
+        image,
+        output: leaf_addr..root_addr + words_per_digest,
+        ..Default::default()
+    };
+    let traces = generate_mmr_traces::<SC>(&machine, vec![inclusion, bag]);
+    (peaks.root(&hasher), machine, traces)
+}
+
+/// Generates the traces of `machine` for the Merkle Mountain Range operations in
+/// `ops`, whose hashes take consecutive ids.
+fn generate_mmr_traces<SC>(
+    machine: &KeccakMachine,
+    mut ops: Vec<MmrOp<MMR_HEIGHT, DIGEST_WIDTH>>,
+) -> Vec<Option<RowMajorMatrix<Val<SC>>>>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let mut keccak_inputs = vec![];
+    for op in ops.iter_mut() {
+        op.first_hash_id = keccak_inputs.len();
+        keccak_inputs.extend(op.sponge_ops(&hasher));
+    }
+    let memory_ops = ops
+        .iter()
+        .flat_map(|op| op.memory_ops(&hasher))
+        .collect_vec();
+
+    generate_traces::<SC>(
+        machine,
+        keccak_inputs,
+        memory_ops,
+        BTreeMap::new(),
+        ChipTraces {
+            mmr: Some(MachineMmrChip::generate_trace(&ops, &hasher)),
+            ..Default::default()
+        },
+    )
+}
+
+/// The traces of the chips outside the sponge and the chips it depends on. Chips
+/// left out get the trace of a chip without any operation.
+#[derive(Default)]
+pub(crate) struct ChipTraces<F> {
+    pub merkle_root: Option<RowMajorMatrix<F>>,
+    pub merkle_tree: Option<RowMajorMatrix<F>>,
+    pub merkle_sum_root: Option<RowMajorMatrix<F>>,
+    pub mmr: Option<RowMajorMatrix<F>>,
+    pub incremental_merkle_tree: Option<RowMajorMatrix<F>>,
+    pub eth_address: Option<RowMajorMatrix<F>>,
+    pub contract_address: Option<RowMajorMatrix<F>>,
+    pub storage_slot: Option<RowMajorMatrix<F>>,
+    pub eip712: Option<RowMajorMatrix<F>>,
+}
+
+/// Returns the calls of the memory operations among `ops`, which the machine
+/// proving them has to be set up with.
+fn sponge_calls(ops: &[KeccakSpongeOp]) -> Vec<SpongeCall> {
+    ops.iter().filter_map(KeccakSpongeOp::call).collect()
+}
+
+/// Generates the traces of `machine`, in the order of its chips. The sponge hashes
+/// `keccak_inputs` and then the output words, and the memory and range chips also
+/// get the accesses in `other_memory_ops` and the bytes in `other_range_counts`, from
+/// the chips in `chip_traces`.
+pub(crate) fn generate_traces<SC>(
+    machine: &KeccakMachine,
+    mut keccak_inputs: Vec<KeccakSpongeOp>,
+    other_memory_ops: Vec<MemoryOp<MEMORY_WORD_BYTES>>,
+    other_range_counts: BTreeMap<u32, u32>,
+    chip_traces: ChipTraces<Val<SC>>,
+) -> Vec<Option<RowMajorMatrix<Val<SC>>>>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    assert_eq!(
+        sponge_calls(&keccak_inputs)
+            .into_iter()
+            .sorted()
+            .collect_vec(),
+        machine.sponge_calls.iter().copied().sorted().collect_vec(),
+        "the memory operations of the sponge aren't the calls of the machine"
+    );
+    let memory_ops: Vec<MemoryOp<MEMORY_WORD_BYTES>> = keccak_inputs
+        .iter()
+        .flat_map(|op| op.memory_ops())
+        .chain(other_memory_ops)
+        .collect();
+
+    let output_hash_id = keccak_inputs.len();
+    keccak_inputs.push(KeccakSpongeOp {
+        input: MemoryImageChip::output(&machine.image, &memory_ops, machine.output.clone()),
+        ..Default::default()
+    });
+
+    let keccak_sponge_trace = KeccakSpongeChip::generate_trace(keccak_inputs);
+    let (xor_ops, permute_inputs) = KeccakSpongeChip::generate_dependent_ops(&keccak_sponge_trace);
+
+    let keccak_permute_trace =
+        KeccakPermuteChip::<KECCAK_U64_LIMBS>::generate_trace(permute_inputs);
+
+    let xor_trace = XorChip::<NUM_BYTES>::generate_trace(xor_ops);
+
+    let memory_image_trace = MemoryImageChip::generate_trace(
+        &machine.image,
+        machine.output.clone(),
+        machine.sponge_calls.len(),
+        output_hash_id,
+        &memory_ops,
+    );
+    let memory_trace = MemoryChip::generate_trace(&machine.image, memory_ops);
+
+    let mut range_counts = KeccakSpongeChip::generate_range_counts(&keccak_sponge_trace);
+    for (value, count) in MemoryChip::<MEMORY_WORD_BYTES>::generate_range_counts(&memory_trace)
+        .into_iter()
+        .chain(other_range_counts)
+    {
+        *range_counts.entry(value).or_insert(0) += count;
+    }
+    let range_trace = RangeCheckerChip::<MAX_U8>::generate_trace(range_counts);
+
+    let ChipTraces {
+        mut merkle_root,
+        mut merkle_tree,
+        mut merkle_sum_root,
+        mut mmr,
+        mut incremental_merkle_tree,
+        mut eth_address,
+        mut contract_address,
+        mut storage_slot,
+        mut eip712,
+    } = chip_traces;
+    let mut keccak_sponge_trace = Some(keccak_sponge_trace);
+    let mut xor_trace = Some(xor_trace);
+    let mut keccak_permute_trace = Some(keccak_permute_trace);
+    let mut range_trace = Some(range_trace);
+    let mut memory_trace = Some(memory_trace);
+    let mut memory_image_trace = Some(memory_image_trace);
+
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    machine
+        .chips()
+        .iter()
+        .map(|chip| match chip {
+            KeccakMachineChip::MerkleRoot(_) => Some(merkle_root.take().unwrap_or_else(|| {
+                MerkleRootChip::<MERKLE_TREE_DEPTH, DIGEST_WIDTH>::generate_trace::<_, u8, _>(
+                    vec![],
+                    &hasher,
+                )
+            })),
+            KeccakMachineChip::MerkleRoot16(_) => Some(merkle_root.take().unwrap_or_else(|| {
+                MerkleRootChip::<16, DIGEST_WIDTH>::generate_trace::<_, u8, _>(vec![], &hasher)
+            })),
+            KeccakMachineChip::MerkleRoot32(_) => Some(merkle_root.take().unwrap_or_else(|| {
+                MerkleRootChip::<32, DIGEST_WIDTH>::generate_trace::<_, u8, _>(vec![], &hasher)
+            })),
+            KeccakMachineChip::KeccakSponge(_) => keccak_sponge_trace.take(),
+            KeccakMachineChip::Xor(_) => xor_trace.take(),
+            KeccakMachineChip::KeccakPermute(_) => keccak_permute_trace.take(),
+            KeccakMachineChip::Range8(_) => range_trace.take(),
+            KeccakMachineChip::Memory(_) => memory_trace.take(),
+            KeccakMachineChip::MemoryImage(_) => memory_image_trace.take(),
+            KeccakMachineChip::ByteMemory(_) => None,
+            KeccakMachineChip::MerkleTree(_) => {
+                Some(merkle_tree.take().unwrap_or_else(|| {
+                    MerkleTreeChip::<DIGEST_WIDTH>::generate_trace(&[], &hasher)
+                }))
             }
+            KeccakMachineChip::MerkleSumRoot(_) => Some(
+                merkle_sum_root
+                    .take()
+                    .unwrap_or_else(|| MachineSumRootChip::generate_trace(&[], &Keccak256Hash)),
+            ),
+            KeccakMachineChip::Mmr(_) => Some(
+                mmr.take()
+                    .unwrap_or_else(|| MachineMmrChip::generate_trace(&[], &hasher)),
+            ),
+            KeccakMachineChip::IncrementalMerkleTree(_) => Some(
+                incremental_merkle_tree
+                    .take()
+                    .unwrap_or_else(|| MachineIncrementalTreeChip::generate_trace(&[], &hasher)),
+            ),
+            KeccakMachineChip::EthAddress(_) => Some(
+                eth_address
+                    .take()
+                    .unwrap_or_else(|| EthAddressChip::generate_trace(&[])),
+            ),
+            KeccakMachineChip::ContractAddress(_) => Some(
+                contract_address
+                    .take()
+                    .unwrap_or_else(|| ContractAddressChip::generate_trace(&[])),
+            ),
+            KeccakMachineChip::StorageSlot(_) => Some(
+                storage_slot
+                    .take()
+                    .unwrap_or_else(|| StorageSlotChip::generate_trace(&[])),
+            ),
+            KeccakMachineChip::Eip712(_) => Some(
+                eip712
+                    .take()
+                    .unwrap_or_else(|| Eip712Chip::generate_trace(&[])),
+            ),
         })
+        .collect()
+}
+
+/// Generates the traces proving that inserting `leaves` into the incremental Merkle
+/// tree with the given `state` moves its root from the first returned root to the
+/// second one. The slots of the state are placed at the start of the memory image,
+/// followed by the leaves and by the two roots, which are the output along with the
+/// leaves. Returns the roots, the machine, set up with the memory image, and the
+/// traces.
+pub fn generate_incremental_tree_trace<SC>(
+    state: &MachineIncrementalTreeState,
+    leaves: &[[u8; DIGEST_WIDTH]],
+) -> (
+    [u8; DIGEST_WIDTH],
+    [u8; DIGEST_WIDTH],
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+    let leaves_addr = INCREMENTAL_TREE_DEPTH * MachineIncrementalTreeChip::SLOT_WORDS;
+    let old_root_addr = leaves_addr + leaves.len() * words_per_digest;
+    let new_root_addr = old_root_addr + words_per_digest;
+    let mut image = [state.image(), leaves.concat()].concat();
+    image.resize((new_root_addr + words_per_digest) * MEMORY_WORD_BYTES, 0);
+
+    // The old root is computed first, and every insert then reads the slots written
+    // by the previous one.
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let mut ops = vec![IncrementalTreeOp {
+        kind: IncrementalTreeOpKind::Root,
+        state: *state,
+        addr: old_root_addr as u32,
+        ..Default::default()
+    }];
+    let mut state = *state;
+    for (i, &leaf) in leaves.iter().enumerate() {
+        let op = IncrementalTreeOp {
+            kind: IncrementalTreeOpKind::Insert { leaf },
+            state,
+            timestamp: 2 * (i as u32 + 1),
+            addr: (leaves_addr + i * words_per_digest) as u32,
+            ..Default::default()
+        };
+        state = op.next_state(&hasher);
+        ops.push(op);
+    }
+    ops.push(IncrementalTreeOp {
+        kind: IncrementalTreeOpKind::Root,
+        state,
+        timestamp: 2 * (leaves.len() as u32 + 1),
+        addr: new_root_addr as u32,
+        ..Default::default()
+    });
+
+    let old_root = ops[0].state.root(&hasher);
+    let new_root = state.root(&hasher);
+    let machine = KeccakMachine {
+        image,
+        output: leaves_addr..new_root_addr + words_per_digest,
+        ..Default::default()
+    };
+    let traces = generate_incremental_tree_traces::<SC>(&machine, ops);
+    (old_root, new_root, machine, traces)
+}
+
+/// Generates the traces of `machine` for the incremental Merkle tree operations in
+/// `ops`, whose hashes take consecutive ids.
+fn generate_incremental_tree_traces<SC>(
+    machine: &KeccakMachine,
+    mut ops: Vec<IncrementalTreeOp<INCREMENTAL_TREE_DEPTH, DIGEST_WIDTH>>,
+) -> Vec<Option<RowMajorMatrix<Val<SC>>>>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let hasher = CompressionFunctionFromHasher::<u8, _, 2, DIGEST_WIDTH>::new(Keccak256Hash);
+    let mut keccak_inputs = vec![];
+    for op in ops.iter_mut() {
+        op.first_hash_id = keccak_inputs.len();
+        keccak_inputs.extend(op.sponge_ops(&hasher));
+    }
+    let memory_ops = ops
+        .iter()
+        .flat_map(|op| op.memory_ops(&hasher))
         .collect_vec();
 
-    let merkle_tree_trace =
-        MerkleRootChip::<MERKLE_TREE_DEPTH, DIGEST_WIDTH>::generate_trace(vec![op], hasher);
+    generate_traces::<SC>(
+        machine,
+        keccak_inputs,
+        memory_ops,
+        BTreeMap::new(),
+        ChipTraces {
+            incremental_merkle_tree: Some(MachineIncrementalTreeChip::generate_trace(
+                &ops, &hasher,
+            )),
+            ..Default::default()
+        },
+    )
+}
 
-    let mut xor_ops = Vec::new();
-    let permute_inputs = keccak_inputs
+/// Generates the traces proving the Ethereum addresses of `public_keys`, the last
+/// `ETH_ADDRESS_BYTES` bytes of the Keccak256 hashes of the keys. The keys are placed
+/// at the start of the memory image, followed by the addresses, each left-padded with
+/// zeros to a 32-byte word. The output is the whole image, the keys and then their
+/// addresses. Returns the addresses, the machine, set up with the memory image, and
+/// the traces.
+pub fn generate_eth_address_trace<SC>(
+    public_keys: &[[u8; PUBLIC_KEY_BYTES]],
+) -> (
+    Vec<[u8; ETH_ADDRESS_BYTES]>,
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let words_per_key = PUBLIC_KEY_BYTES / MEMORY_WORD_BYTES;
+    let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+    let addresses_addr = public_keys.len() * words_per_key;
+    let mut image = public_keys.concat();
+    image.resize(
+        (addresses_addr + public_keys.len() * words_per_digest) * MEMORY_WORD_BYTES,
+        0,
+    );
+
+    let ops = public_keys
         .iter()
-        .map(|op| {
-            let mut bytes_input = [0; KECCAK_WIDTH_BYTES];
-            bytes_input[0..2 * DIGEST_WIDTH].copy_from_slice(&op.input);
-            bytes_input[2 * DIGEST_WIDTH] = 1;
-            bytes_input[KECCAK_RATE_BYTES - 1] |= 0b10000000;
-
-            bytes_input[0..KECCAK_RATE_BYTES].chunks(2).for_each(|val| {
-                let op = XorOp {
-                    input1: (val[0] as u16 + ((val[1] as u16) << 8)),
-                    input2: 0,
+        .enumerate()
+        .map(|(i, &public_key)| EthAddressOp {
+            timestamp: 0,
+            key_addr: (i * words_per_key) as u32,
+            address_addr: (addresses_addr + i * words_per_digest) as u32,
+            hash_id: i,
+            public_key,
+        })
+        .collect_vec();
+    let keccak_inputs = ops.iter().map(EthAddressOp::sponge_op).collect_vec();
+    let memory_ops = ops.iter().flat_map(EthAddressOp::memory_ops).collect_vec();
+
+    let machine = KeccakMachine {
+        output: 0..image_num_words::<MEMORY_WORD_BYTES>(&image),
+        image,
+        ..Default::default()
+    };
+    let traces = generate_traces::<SC>(
+        &machine,
+        keccak_inputs,
+        memory_ops,
+        BTreeMap::new(),
+        ChipTraces {
+            eth_address: Some(EthAddressChip::generate_trace(&ops)),
+            ..Default::default()
+        },
+    );
+
+    let addresses = ops.iter().map(EthAddressOp::address).collect();
+    (addresses, machine, traces)
+}
+
+/// A contract deployment, whose address `generate_contract_address_trace` proves.
+#[derive(Clone, Debug)]
+pub enum Deployment {
+    Create2 {
+        deployer: [u8; ETH_ADDRESS_BYTES],
+        salt: [u8; DIGEST_WIDTH],
+        init_code: Vec<u8>,
+    },
+    Create {
+        deployer: [u8; ETH_ADDRESS_BYTES],
+        nonce: u64,
+    },
+}
+
+/// Generates the traces proving the addresses of the contracts of `deployments`. Each
+/// deployment gets a region of the memory image: the deployer, left-padded with zeros
+/// to a 32-byte word, then the salt and the init code hash of a CREATE2 or the
+/// big-endian nonce of a CREATE, then the address, padded like the deployer, and then
+/// the init code of a CREATE2. The init code is hashed by the sponge into its slot
+/// first, so that it can be of any length. The output is the whole image, the regions
+/// of all the deployments. Returns the addresses, the machine, set up with the memory
+/// image, and the traces.
+pub fn generate_contract_address_trace<SC>(
+    deployments: &[Deployment],
+) -> (
+    Vec<[u8; ETH_ADDRESS_BYTES]>,
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+    let mut image = vec![];
+    let mut init_code_ops = vec![];
+    let mut ops = vec![];
+    for deployment in deployments {
+        let deployer_addr = image.len() / MEMORY_WORD_BYTES;
+        let input_addr = deployer_addr + words_per_digest;
+        let (deployer, kind, input) = match deployment {
+            Deployment::Create2 {
+                deployer,
+                salt,
+                init_code,
+            } => {
+                let kind = ContractAddressOpKind::Create2 {
+                    salt: *salt,
+                    init_code_hash: Keccak256Hash.hash_iter(init_code.iter().copied()),
                 };
-                xor_ops.push(op);
+                // The slot of the init code hash starts out empty.
+                (deployer, kind, [*salt, [0; DIGEST_WIDTH]].concat())
+            }
+            Deployment::Create { deployer, nonce } => (
+                deployer,
+                ContractAddressOpKind::Create { nonce: *nonce },
+                nonce.to_be_bytes().to_vec(),
+            ),
+        };
+        let address_addr = input_addr + input.len() / MEMORY_WORD_BYTES;
+        image.extend(address_word(deployer));
+        image.extend(input);
+        image.extend([0; DIGEST_WIDTH]);
+
+        // The init code hash is written to its slot at timestamp 1, before the
+        // address is derived at timestamp 2.
+        if let Deployment::Create2 { init_code, .. } = deployment {
+            let code_addr = image.len() / MEMORY_WORD_BYTES;
+            image.extend(init_code);
+            image.resize(
+                (code_addr + image_num_words::<MEMORY_WORD_BYTES>(init_code)) * MEMORY_WORD_BYTES,
+                0,
+            );
+            init_code_ops.push(KeccakSpongeOp {
+                timestamp: 0,
+                addr: code_addr as u32,
+                dst_addr: (input_addr + words_per_digest) as u32,
+                is_memory_op: true,
+                input: init_code.clone(),
             });
-            let input = bytes_input
-                .chunks_exact(8)
-                .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
-                .collect_vec()
-                .try_into()
-                .unwrap();
-            KeccakPermuteOp { input }
-        })
+        }
+
+        ops.push(ContractAddressOp {
+            kind,
+            deployer: *deployer,
+            timestamp: 2,
+            deployer_addr: deployer_addr as u32,
+            input_addr: input_addr as u32,
+            address_addr: address_addr as u32,
+            hash_id: 0,
+        });
+    }
+
+    // The init code hashes come first, and the preimages of the addresses follow.
+    for (i, op) in ops.iter_mut().enumerate() {
+        op.hash_id = init_code_ops.len() + i;
+    }
+    let keccak_inputs = init_code_ops
+        .into_iter()
+        .chain(ops.iter().map(ContractAddressOp::sponge_op))
         .collect_vec();
-    let keccak_sponge_trace = KeccakSpongeChip::generate_trace(keccak_inputs);
+    let memory_ops = ops
+        .iter()
+        .flat_map(ContractAddressOp::memory_ops)
+        .collect_vec();
+    let contract_address_trace = ContractAddressChip::generate_trace(&ops);
 
-    let keccak_permute_trace = KeccakPermuteChip::generate_trace(permute_inputs);
+    let machine = KeccakMachine {
+        output: 0..image_num_words::<MEMORY_WORD_BYTES>(&image),
+        image,
+        sponge_calls: sponge_calls(&keccak_inputs),
+        ..Default::default()
+    };
+    let traces = generate_traces::<SC>(
+        &machine,
+        keccak_inputs,
+        memory_ops,
+        ContractAddressChip::generate_range_counts(&contract_address_trace),
+        ChipTraces {
+            contract_address: Some(contract_address_trace),
+            ..Default::default()
+        },
+    );
 
-    let xor_trace = XorChip::<NUM_BYTES>::generate_trace(xor_ops);
+    let addresses = ops.iter().map(ContractAddressOp::address).collect();
+    (addresses, machine, traces)
+}
+
+/// Generates the traces proving the storage slots the `paths` lead to. Each path gets
+/// a region of the memory image: its base slot and the records of its steps, followed
+/// by the derived slot. The output is the whole image, the regions of all the paths.
+/// Returns the slots, the machine, set up with the memory image, and the traces.
+pub fn generate_storage_slot_trace<SC>(
+    paths: &[StoragePath],
+) -> (
+    Vec<[u8; DIGEST_WIDTH]>,
+    KeccakMachine,
+    Vec<Option<RowMajorMatrix<Val<SC>>>>,
+)
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let mut image = vec![];
+    let mut ops = vec![];
+    let mut first_hash_id = 0;
+    for path in paths {
+        let path_addr = image.len() / MEMORY_WORD_BYTES;
+        image.extend(path.image());
+        let slot_addr = image.len() / MEMORY_WORD_BYTES;
+        image.extend([0; DIGEST_WIDTH]);
+
+        ops.push(StorageSlotOp {
+            path: path.clone(),
+            timestamp: 0,
+            path_addr: path_addr as u32,
+            slot_addr: slot_addr as u32,
+            first_hash_id,
+        });
+        first_hash_id += path.steps.len();
+    }
+    let keccak_inputs = ops.iter().flat_map(StorageSlotOp::sponge_ops).collect_vec();
+    let memory_ops = ops.iter().flat_map(StorageSlotOp::memory_ops).collect_vec();
+    let storage_slot_trace = StorageSlotChip::generate_trace(&ops);
+
+    let machine = KeccakMachine {
+        output: 0..image_num_words::<MEMORY_WORD_BYTES>(&image),
+        image,
+        ..Default::default()
+    };
+    let traces = generate_traces::<SC>(
+        &machine,
+        keccak_inputs,
+        memory_ops,
+        StorageSlotChip::generate_range_counts(&storage_slot_trace),
+        ChipTraces {
+            storage_slot: Some(storage_slot_trace),
+            ..Default::default()
+        },
+    );
+
+    let slots = paths.iter().map(StoragePath::slot).collect();
+    (slots, machine, traces)
+}
+
+/// Generates the traces proving the EIP-712 digests of `typed_data`. Each typed
+/// data gets a region at the start of the memory image: the domain separator, the
+/// message hash and the digest, which start out empty and are the output. The
+/// preimages of the two hashes follow the regions, with their words that are the
+/// digests of other preimages empty too. Memory operations of the sponge hash the
+/// preimages from the leaves up, a preimage of height `h` at timestamp `2h`. Returns
+/// the domain separators and the digests, the machine, set up with the memory image,
+/// and the traces.
+pub fn generate_eip712_trace<SC>(
+    typed_data: &[TypedData],
+) -> Result<Eip712Traces<Val<SC>>, TypedDataError>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField64,
+{
+    let words_per_digest = DIGEST_WIDTH / MEMORY_WORD_BYTES;
+    let words_per_region = 3 * words_per_digest;
+    let output = 0..typed_data.len() * words_per_region;
+    let mut image = vec![0; output.end * MEMORY_WORD_BYTES];
+    let mut preimage_ops = vec![];
+    let mut ops = vec![];
+    for (i, typed_data) in typed_data.iter().enumerate() {
+        let domain_preimage = typed_data.domain_preimage()?;
+        let message_preimage = typed_data.message_preimage()?;
+
+        let addr = i * words_per_region;
+        push_preimage_ops(&domain_preimage, addr, &mut image, &mut preimage_ops);
+        push_preimage_ops(
+            &message_preimage,
+            addr + words_per_digest,
+            &mut image,
+            &mut preimage_ops,
+        );
+
+        let height = domain_preimage.height().max(message_preimage.height());
+        ops.push(Eip712Op {
+            timestamp: 2 * (height as u32 + 1),
+            addr: addr as u32,
+            hash_id: 0,
+            domain_separator: domain_preimage.digest(),
+            message_hash: message_preimage.digest(),
+        });
+    }
+
+    // The preimages come first, and the signing preimages follow.
+    for (i, op) in ops.iter_mut().enumerate() {
+        op.hash_id = preimage_ops.len() + i;
+    }
+    let keccak_inputs = preimage_ops
+        .into_iter()
+        .chain(ops.iter().map(Eip712Op::sponge_op))
+        .collect_vec();
+    let memory_ops = ops.iter().flat_map(Eip712Op::memory_ops).collect_vec();
+
+    let machine = KeccakMachine {
+        image,
+        output,
+        sponge_calls: sponge_calls(&keccak_inputs),
+        ..Default::default()
+    };
+    let traces = generate_traces::<SC>(
+        &machine,
+        keccak_inputs,
+        memory_ops,
+        BTreeMap::new(),
+        ChipTraces {
+            eip712: Some(Eip712Chip::generate_trace(&ops)),
+            ..Default::default()
+        },
+    );
+
+    let digests = ops
+        .iter()
+        .map(|op| (op.domain_separator, op.digest()))
+        .collect();
+    Ok((digests, machine, traces))
+}
+
+/// Places `preimage` at the end of the memory image, with the digests of its
+/// children left empty, and pushes the memory operations of the sponge that hash
+/// its children into place and then hash it into the word address `dst_addr`.
+fn push_preimage_ops(
+    preimage: &HashPreimage,
+    dst_addr: usize,
+    image: &mut Vec<u8>,
+    ops: &mut Vec<KeccakSpongeOp>,
+) {
+    let addr = image.len() / MEMORY_WORD_BYTES;
+    image.extend(&preimage.input);
+    for (offset, _) in &preimage.children {
+        let start = addr * MEMORY_WORD_BYTES + offset;
+        image[start..start + DIGEST_WIDTH].fill(0);
+    }
+    image.resize(
+        (addr + image_num_words::<MEMORY_WORD_BYTES>(&preimage.input)) * MEMORY_WORD_BYTES,
+        0,
+    );
 
-    vec![
-        Some(merkle_tree_trace),
-        Some(keccak_sponge_trace),
-        Some(xor_trace),
-        Some(keccak_permute_trace),
-    ]
+    for (offset, child) in &preimage.children {
+        push_preimage_ops(child, addr + offset / MEMORY_WORD_BYTES, image, ops);
+    }
+    ops.push(KeccakSpongeOp {
+        timestamp: 2 * preimage.height() as u32,
+        addr: addr as u32,
+        dst_addr: dst_addr as u32,
+        is_memory_op: true,
+        input: preimage.input.clone(),
+    });
 }